@@ -1,10 +1,16 @@
+use rocket::figment::providers::{Env, Format, Toml};
+use rocket::figment::{Figment, Profile};
 use serde::Deserialize;
+use std::collections::HashMap;
+use x_deploy_common::crypto::jwt::JwtKeyConfig;
 
 #[derive(Deserialize, Debug)]
 pub(crate) struct Config {
   // General
   pub(crate) app_name: String,
-  pub(crate) jwt_secret: String,
+  // Signing of the tokens, keys are PEM encoded by id
+  pub(crate) jwt_key_id: String,
+  pub(crate) jwt_keys: HashMap<String, JwtKeyConfig>,
   // MongoDB
   pub(crate) mongodb_url: String,
   pub(crate) mongodb_database: String,
@@ -16,6 +22,12 @@ pub(crate) struct Config {
   pub(crate) max_organization_by_owner: u64,
   pub(crate) max_apikey_by_organization: u64,
   pub(crate) jwt_key_duration_in_minutes: u64,
+  pub(crate) refresh_token_duration_in_days: u64,
+  // Organizations can only be created once the email is verified
+  #[serde(default)]
+  pub(crate) require_verified_email_for_organization: bool,
+  // Deleted organizations can be restored during this window, then purged
+  pub(crate) organization_restore_window_in_days: u64,
   // CORS
   pub(crate) cors_allowed_origins: Vec<String>,
   pub(crate) cors_allowed_methods: Vec<String>,
@@ -26,11 +38,42 @@ pub(crate) struct Config {
   pub(crate) s3_access_key: String,
   pub(crate) s3_secret_key: String,
   pub(crate) s3_region: String,
+  // Encryption of the credentials, only the PEM encoded RSA public key of
+  // the current key: the private keys stay in the daemon
+  pub(crate) encryption_key_id: String,
+  pub(crate) encryption_public_key: String,
+  // WebAuthn, the id is the domain of the origin, e.g. `x-deploy.com`
+  pub(crate) webauthn_rp_id: String,
+  pub(crate) webauthn_rp_origin: String,
+  // OAuth, a provider without client is disabled
+  pub(crate) oauth_redirect_uri: String,
+  pub(crate) oauth_github: Option<OAuthClientConfig>,
+  pub(crate) oauth_gitlab: Option<OAuthClientConfig>,
+  pub(crate) oauth_google: Option<OAuthClientConfig>,
+  // SMS, sent through Kafka unless another provider is chosen
+  #[serde(default)]
+  pub(crate) sms_provider: SmsProviderType,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub(crate) enum SmsProviderType {
+  #[default]
+  #[serde(rename = "kafka")]
+  Kafka,
+  /// Only logs the SMS, for the local development
+  #[serde(rename = "memory")]
+  InMemory,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct OAuthClientConfig {
+  pub(crate) client_id: String,
+  pub(crate) client_secret: String,
 }
 
 impl Config {
   pub(crate) fn from_rocket_config() -> Self {
-    let figment = rocket::Config::figment();
+    let figment = rocket_figment();
     let config = figment.extract::<Config>();
     return match config {
       Ok(config) => config,
@@ -38,3 +81,20 @@ impl Config {
     };
   }
 }
+
+/// The Rocket configuration, read from `Rocket.toml` and the `ROCKET_`
+/// variables as Rocket does, except that no header is trusted for the
+/// client IP by default. Behind a reverse proxy, `ip_header` must name a
+/// header the proxy overwrites on every request, e.g.
+/// `ROCKET_IP_HEADER=X-Real-IP`, otherwise clients can spoof their IP to
+/// get through the api key allowlists and the rate limits.
+pub(crate) fn rocket_figment() -> Figment {
+  Figment::from(rocket::Config::default())
+    .merge(("ip_header", false))
+    .merge(Toml::file(Env::var_or("ROCKET_CONFIG", "Rocket.toml")).nested())
+    .merge(Env::prefixed("ROCKET_").ignore(&["PROFILE"]).global())
+    .select(Profile::from_env_or(
+      "ROCKET_PROFILE",
+      rocket::Config::DEFAULT_PROFILE,
+    ))
+}
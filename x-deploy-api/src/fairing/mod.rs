@@ -1 +1,3 @@
 pub(crate) mod cors;
+pub(crate) mod outbox;
+pub(crate) mod rate_limit;
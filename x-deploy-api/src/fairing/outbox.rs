@@ -0,0 +1,121 @@
+use bson::DateTime;
+use log::{error, info};
+use mongodb::Database;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::tokio::task::spawn_blocking;
+use rocket::tokio::time::sleep;
+use rocket::{Orbit, Rocket, Shutdown};
+use std::time::Duration;
+use x_deploy_common::db::event_outbox::EventOutbox;
+use x_deploy_common::db::CommonCollection;
+use x_deploy_common::event::producer::EventProducer;
+
+// Time to wait before looking for new events when the outbox is empty
+const RELAY_IDLE_DELAY: Duration = Duration::from_secs(1);
+
+// Events claimed for longer than this are considered abandoned by a relay
+const RELAY_CLAIM_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Attempts to publish an event before it is set aside as failed
+const RELAY_MAX_ATTEMPTS: u32 = 10;
+
+// Delay before the second attempt, doubled on every following one
+const RELAY_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Publishes the events written in the outbox on Kafka.
+pub(crate) struct OutboxRelay;
+
+#[rocket::async_trait]
+impl Fairing for OutboxRelay {
+  fn info(&self) -> Info {
+    Info {
+      name: "Relay outbox events to Kafka",
+      kind: Kind::Liftoff,
+    }
+  }
+
+  async fn on_liftoff(
+    &self,
+    rocket: &Rocket<Orbit>,
+  ) {
+    let db = match rocket.state::<Database>() {
+      Some(db) => db.clone(),
+      None => return error!("Outbox relay needs a managed database"),
+    };
+    let producer = match rocket.state::<EventProducer>() {
+      Some(producer) => producer.clone(),
+      None => return error!("Outbox relay needs a managed event producer"),
+    };
+    let shutdown = rocket.shutdown();
+    rocket::tokio::spawn(relay(db, producer, shutdown));
+  }
+}
+
+async fn relay(
+  db: Database,
+  producer: EventProducer,
+  mut shutdown: Shutdown,
+) {
+  info!("Outbox relay started");
+  let outbox_coll = CommonCollection::<EventOutbox>::new(&db);
+  loop {
+    let sent = match relay_next(&outbox_coll, &producer).await {
+      Ok(sent) => sent,
+      Err(err) => {
+        error!("Outbox relay failed: {:?}", err);
+        false
+      }
+    };
+    if sent {
+      continue;
+    }
+    rocket::tokio::select! {
+      _ = &mut shutdown => break,
+      _ = sleep(RELAY_IDLE_DELAY) => {}
+    }
+  }
+  info!("Outbox relay stopped");
+}
+
+/// Publishes the oldest pending event and returns whether one was found.
+async fn relay_next(
+  outbox_coll: &CommonCollection<EventOutbox>,
+  producer: &EventProducer,
+) -> x_deploy_common::CommonResult<bool> {
+  let event = match outbox_coll.claim_next(RELAY_CLAIM_TIMEOUT).await? {
+    Some(event) => event,
+    None => return Ok(false),
+  };
+  let sender = producer.clone();
+  let topic = event.topic.clone();
+  let payload = event.payload.clone();
+  let result =
+    spawn_blocking(move || sender.send_raw(&topic, &payload)).await?;
+  match result {
+    Ok(()) => {
+      outbox_coll.delete_by_id(&event.id).await?;
+    }
+    Err(err) => {
+      error!("Unable to publish event on {}: {:?}", event.topic, err);
+      let error = format!("{:?}", err);
+      let attempts = event.attempts + 1;
+      if attempts >= RELAY_MAX_ATTEMPTS {
+        error!(
+          "Giving up on event {} after {} attempts",
+          event.id, attempts
+        );
+        outbox_coll.mark_failed(&event.id, &error).await?;
+      } else {
+        let delay = RELAY_RETRY_DELAY * 2u32.pow(attempts - 1);
+        let next_attempt_at = DateTime::from_millis(
+          DateTime::now().timestamp_millis() + delay.as_millis() as i64,
+        );
+        outbox_coll
+          .release(&event.id, &error, next_attempt_at)
+          .await?;
+      }
+      return Ok(false);
+    }
+  }
+  Ok(true)
+}
@@ -0,0 +1,204 @@
+use crate::route::ErrorMessage;
+use log::error;
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::uri::Origin;
+use rocket::http::{ContentType, Header, Method, Status};
+use rocket::{Data, Request, Response};
+use std::io::Cursor;
+use x_deploy_common::cache::rate_limit::hit;
+
+// Path no route is mounted on, rate limited requests are sent there so that
+// their handler is never called
+const RATE_LIMITED_PATH: &str = "/rate-limited";
+
+/// The number of requests allowed from the same IP on a route.
+struct RateLimitPolicy {
+  method: Method,
+  /// Path of the route, a dynamic segment such as `<provider>` matches any
+  /// segment
+  path: &'static str,
+  limit: u64,
+  window_in_seconds: u64,
+}
+
+const POLICIES: [RateLimitPolicy; 14] = [
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/login/credentials",
+    limit: 10,
+    window_in_seconds: 60,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/oauth/<provider>/callback",
+    limit: 20,
+    window_in_seconds: 60,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/magic-link",
+    limit: 5,
+    window_in_seconds: 600,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/login/magic-link",
+    limit: 10,
+    window_in_seconds: 60,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/register",
+    limit: 10,
+    window_in_seconds: 3600,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/2fa",
+    limit: 10,
+    window_in_seconds: 60,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/2fa/webauthn",
+    limit: 10,
+    window_in_seconds: 60,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/2fa/recovery",
+    limit: 5,
+    window_in_seconds: 60,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/refresh",
+    limit: 30,
+    window_in_seconds: 60,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/password/forgot",
+    limit: 5,
+    window_in_seconds: 600,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/auth/password/reset",
+    limit: 10,
+    window_in_seconds: 600,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/account/2fa/recovery-codes",
+    limit: 5,
+    window_in_seconds: 600,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/account/change-phone",
+    limit: 5,
+    window_in_seconds: 600,
+  },
+  RateLimitPolicy {
+    method: Method::Post,
+    path: "/account/phone/confirm",
+    limit: 10,
+    window_in_seconds: 600,
+  },
+];
+
+impl RateLimitPolicy {
+  fn matches(
+    &self,
+    method: Method,
+    path: &str,
+  ) -> bool {
+    if self.method != method {
+      return false;
+    }
+    let mut segments = path.trim_end_matches('/').split('/');
+    let mut policy_segments = self.path.split('/');
+    loop {
+      match (policy_segments.next(), segments.next()) {
+        (None, None) => return true,
+        (Some(expected), Some(segment)) => {
+          let dynamic = expected.starts_with('<') && expected.ends_with('>');
+          if !dynamic && expected != segment {
+            return false;
+          }
+        }
+        _ => return false,
+      }
+    }
+  }
+}
+
+/// Seconds to wait before retrying, set when the request was rate limited.
+struct RateLimited(Option<u64>);
+
+/// Limits the requests on sensitive routes by IP, counted in Redis so that
+/// the limits are shared by every instance of the API.
+pub(crate) struct RateLimiter;
+
+#[rocket::async_trait]
+impl Fairing for RateLimiter {
+  fn info(&self) -> Info {
+    Info {
+      name: "Rate limit sensitive routes",
+      kind: Kind::Request | Kind::Response,
+    }
+  }
+
+  async fn on_request(
+    &self,
+    request: &mut Request<'_>,
+    _: &mut Data<'_>,
+  ) {
+    let method = request.method();
+    let path = request.uri().path().to_string();
+    let policy = match POLICIES.iter().find(|p| p.matches(method, &path)) {
+      Some(policy) => policy,
+      None => return,
+    };
+    let redis = match request.rocket().state::<redis::Client>() {
+      Some(redis) => redis,
+      None => return error!("Rate limiter needs a managed redis client"),
+    };
+    // Only a header set by a trusted proxy is read for the client IP, see
+    // `rocket_figment`, so a client can't get a new bucket by sending one
+    let ip = match request.client_ip() {
+      Some(ip) => ip.to_canonical().to_string(),
+      None => "unknown".to_string(),
+    };
+    let key = format!("{}:{}", policy.path, ip);
+    // Requests are let through when Redis is unavailable
+    let hit = match hit(redis, &key, policy.window_in_seconds).await {
+      Ok(hit) => hit,
+      Err(err) => return error!("Unable to rate limit {}: {:?}", path, err),
+    };
+    if hit.count > policy.limit {
+      request.local_cache(|| RateLimited(Some(hit.reset_in)));
+      request.set_uri(Origin::parse(RATE_LIMITED_PATH).unwrap());
+    }
+  }
+
+  async fn on_response<'r>(
+    &self,
+    request: &'r Request<'_>,
+    response: &mut Response<'r>,
+  ) {
+    let retry_after = match request.local_cache(|| RateLimited(None)).0 {
+      Some(retry_after) => retry_after,
+      None => return,
+    };
+    let message = ErrorMessage::new(
+      "Too many requests, please try again later".to_string(),
+    );
+    let body = serde_json::to_string(&message).unwrap_or_default();
+    response.set_status(Status::TooManyRequests);
+    response.set_header(ContentType::JSON);
+    response.set_header(Header::new("Retry-After", retry_after.to_string()));
+    response.set_sized_body(body.len(), Cursor::new(body));
+  }
+}
@@ -1,20 +1,24 @@
 use crate::route::ErrorMessage;
 use bson::oid::ObjectId;
 use chrono::Utc;
+use log::warn;
 use rocket::http::Status;
 use rocket::outcome::Outcome;
 use rocket::request::FromRequest;
 use rocket::{request, Request};
 use serde::{Deserialize, Serialize};
+use x_deploy_common::data::ip_rule::is_ip_allowed;
 use x_deploy_common::db::organization_apikey::OrganizationApiKey;
 use x_deploy_common::db::organization_role::OrganizationRole;
 use x_deploy_common::db::query::organization_api_key::OrganizationApiKeyQuery;
 use x_deploy_common::db::CommonCollection;
 
+const LAST_USED_UPDATE_INTERVAL_IN_SECONDS: i64 = 60;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiKey {
   pub id: ObjectId,
-  pub value: String,
+  pub prefix: String,
   pub exp: Option<chrono::DateTime<Utc>>,
   pub org_id: ObjectId,
   pub role: Option<OrganizationRole>,
@@ -23,14 +27,14 @@ pub struct ApiKey {
 impl ApiKey {
   pub fn new(
     id: ObjectId,
-    value: String,
+    prefix: String,
     exp: Option<chrono::DateTime<Utc>>,
     org_id: ObjectId,
     role: Option<OrganizationRole>,
   ) -> Self {
     Self {
       id,
-      value,
+      prefix,
       exp,
       org_id,
       role,
@@ -53,7 +57,7 @@ impl From<OrganizationApiKeyQuery> for ApiKey {
     };
     Self {
       id: value.id,
-      value: value.key,
+      prefix: value.key_prefix,
       exp: expires_at,
       org_id: value.organization_id,
       role: value.role,
@@ -105,6 +109,29 @@ impl<'r> FromRequest<'r> for ApiKey {
         return Outcome::Error((Status::Unauthorized, message));
       }
     };
+    let client_ip = req.client_ip().map(|ip| ip.to_canonical());
+    if !api_key.allowed_ips.is_empty() {
+      let allowed = match &client_ip {
+        Some(ip) => is_ip_allowed(&api_key.allowed_ips, ip),
+        None => false,
+      };
+      if !allowed {
+        let message = ErrorMessage::new(
+          "Your IP address is not allowed to use this api key".to_string(),
+        );
+        return Outcome::Error((Status::Forbidden, message));
+      }
+    }
+
+    // Avoid writing in the database on every request
+    let last_used_outdated = match api_key.last_used_at {
+      Some(last_used_at) => {
+        let elapsed = Utc::now() - last_used_at.to_chrono();
+        elapsed.num_seconds() >= LAST_USED_UPDATE_INTERVAL_IN_SECONDS
+      }
+      None => true,
+    };
+    let api_key_id = api_key.id.clone();
     let result: ApiKey = api_key.into();
 
     if result.is_expired() {
@@ -114,6 +141,13 @@ impl<'r> FromRequest<'r> for ApiKey {
       return Outcome::Error((Status::Unauthorized, message));
     }
 
+    if last_used_outdated {
+      let ip = client_ip.map(|ip| ip.to_string());
+      if let Err(err) = akc.update_last_used(&api_key_id, ip).await {
+        warn!("Failed to record the last use of an api key: {:?}", err);
+      }
+    }
+
     return Outcome::Success(result);
   }
 }
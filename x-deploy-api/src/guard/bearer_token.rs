@@ -1,11 +1,8 @@
 use crate::error::ApiError;
 use crate::route::ErrorMessage;
-use crate::CONFIG;
+use crate::{CONFIG, JWT_KEYRING};
 use bson::oid::ObjectId;
-use errors::ErrorKind;
-use jsonwebtoken::{
-  decode, encode, errors, DecodingKey, EncodingKey, Header, Validation,
-};
+use jsonwebtoken::errors::ErrorKind;
 use rocket::http::Status;
 use rocket::outcome::Outcome;
 use rocket::request::FromRequest;
@@ -13,12 +10,17 @@ use rocket::{request, Request};
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use utoipa::ToSchema;
+use x_deploy_common::cache::session::is_session_revoked;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BearerToken {
   pub id: String,
   pub exp: i64,
   pub otp: Option<bool>,
+  /// The session the token was issued for, absent for the tokens which
+  /// are not bound to a session (2FA pending)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sid: Option<String>,
 }
 
 impl BearerToken {
@@ -34,6 +36,7 @@ impl BearerToken {
         id: id.to_string(),
         exp: exp.timestamp(),
         otp,
+        sid: None,
       }),
       None => Err(ApiError::new(
         Status::InternalServerError,
@@ -56,13 +59,10 @@ impl BearerToken {
   }
 
   pub(crate) fn parse_jwt(token: &String) -> Result<BearerToken, ApiError> {
-    let jwt_secret = CONFIG.jwt_secret.clone();
-    let decoding_key = DecodingKey::from_secret(jwt_secret.as_ref());
-    let token_data =
-      decode::<BearerToken>(token, &decoding_key, &Validation::default());
+    let claims = JWT_KEYRING.verify::<BearerToken>(token);
 
-    return match token_data {
-      Ok(token_data) => Ok(token_data.claims),
+    return match claims {
+      Ok(claims) => Ok(claims),
       Err(e) => {
         let kind = e.kind();
         return match kind {
@@ -90,10 +90,22 @@ impl BearerToken {
     };
   }
 
+  pub(crate) fn parse_session_id(&self) -> Result<Option<ObjectId>, ApiError> {
+    let sid = match &self.sid {
+      Some(sid) => sid,
+      None => return Ok(None),
+    };
+    return match ObjectId::from_str(sid.as_str()) {
+      Ok(id) => Ok(Some(id)),
+      Err(_) => {
+        let message = "Error while parsing token session id".to_string();
+        Err(ApiError::new(Status::InternalServerError, message))
+      }
+    };
+  }
+
   pub(crate) fn to_jwt(&self) -> Result<String, ApiError> {
-    let jwt_secret = CONFIG.jwt_secret.clone();
-    let encoding_key = EncodingKey::from_secret(jwt_secret.as_ref());
-    let jwt_encode = encode(&Header::default(), &self, &encoding_key);
+    let jwt_encode = JWT_KEYRING.sign(self);
     return match jwt_encode {
       Ok(token) => Ok(token),
       Err(_) => {
@@ -115,6 +127,13 @@ impl BearerToken {
     self.otp = otp;
   }
 
+  pub(crate) fn with_session(
+    &mut self,
+    session_id: ObjectId,
+  ) {
+    self.sid = Some(session_id.to_string());
+  }
+
   pub(crate) fn with_id(
     &mut self,
     id: ObjectId,
@@ -166,6 +185,38 @@ impl<'r> FromRequest<'r> for BearerToken {
             ));
           }
         }
+        // Verify if the session was revoked
+        if let Some(sid) = &token.sid {
+          let redis = match req.rocket().state::<redis::Client>() {
+            Some(redis) => redis,
+            None => {
+              return Outcome::Error((
+                Status::InternalServerError,
+                ErrorMessage::new(
+                  "Cache connection error for validate your authentication"
+                    .to_string(),
+                ),
+              ));
+            }
+          };
+          match is_session_revoked(redis, sid).await {
+            Ok(false) => {}
+            Ok(true) => {
+              return Outcome::Error((
+                Status::Unauthorized,
+                ErrorMessage::new(
+                  "Session revoked, please login again.".to_string(),
+                ),
+              ));
+            }
+            Err(_) => {
+              return Outcome::Error((
+                Status::InternalServerError,
+                ErrorMessage::new("Failed to verify your session".to_string()),
+              ));
+            }
+          }
+        }
         Outcome::Success(token)
       }
       Err(e) => Outcome::Error((e.status, ErrorMessage::new(e.message))),
@@ -0,0 +1,24 @@
+use request::FromRequest;
+use rocket::{outcome::Outcome, request, Request};
+use std::convert::Infallible;
+
+/// Describes the device a request comes from, e.g. to label a session.
+/// The IP is the one of the peer, or of the header set by a trusted proxy
+/// as configured by `rocket_figment`.
+pub struct ClientInfo {
+  pub ip: Option<String>,
+  pub user_agent: Option<String>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientInfo {
+  type Error = Infallible;
+
+  async fn from_request(
+    req: &'r Request<'_>
+  ) -> request::Outcome<Self, Self::Error> {
+    let ip = req.client_ip().map(|ip| ip.to_canonical().to_string());
+    let user_agent = req.headers().get_one("User-Agent").map(String::from);
+    Outcome::Success(ClientInfo { ip, user_agent })
+  }
+}
@@ -1,4 +1,5 @@
 mod api_key;
 pub(crate) mod auth;
 pub(crate) mod bearer_token;
+pub(crate) mod client_info;
 mod profile_picture;
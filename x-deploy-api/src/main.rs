@@ -1,13 +1,24 @@
-use crate::config::Config;
+use crate::config::{rocket_figment, Config, SmsProviderType};
 use crate::doc::security::{ApiKeySecurity, BearerSecurity};
 use crate::fairing::cors::Cors;
-use crate::oauth::{OAuth, OAuthService};
+use crate::fairing::outbox::OutboxRelay;
+use crate::fairing::rate_limit::RateLimiter;
+use crate::utils::sms::{InMemorySmsProvider, KafkaSmsProvider, SmsProvider};
 use lazy_static::lazy_static;
 use rocket::futures::StreamExt;
 use rocket::serde::Deserialize;
 use utoipa::OpenApi;
 use utoipa_redoc::{Redoc, Servable};
 use utoipa_swagger_ui::SwaggerUi;
+use webauthn_rs::prelude::Url;
+use webauthn_rs::{Webauthn, WebauthnBuilder};
+use x_deploy_common::crypto::jwt::JwtKeyring;
+use x_deploy_common::crypto::keyring::EncryptionKey;
+use x_deploy_common::crypto::migration::encrypt_plaintext_credentials;
+use x_deploy_common::db::organization_apikey::OrganizationApiKey;
+use x_deploy_common::db::user::User;
+use x_deploy_common::db::CommonCollection;
+use x_deploy_common::event::producer::EventProducer;
 
 #[macro_use]
 extern crate rocket;
@@ -25,6 +36,21 @@ pub mod utils;
 
 lazy_static! {
   pub(crate) static ref CONFIG: Config = Config::from_rocket_config();
+  pub(crate) static ref ENCRYPTION_KEY: EncryptionKey = EncryptionKey::new(
+    CONFIG.encryption_key_id.clone(),
+    &CONFIG.encryption_public_key
+  )
+  .expect("Error while loading the encryption key");
+  pub(crate) static ref JWT_KEYRING: JwtKeyring =
+    JwtKeyring::new(CONFIG.jwt_key_id.clone(), &CONFIG.jwt_keys)
+      .expect("Error while loading the jwt keys");
+  pub(crate) static ref WEBAUTHN: Webauthn = {
+    let origin = Url::parse(&CONFIG.webauthn_rp_origin)
+      .expect("Error while parsing the webauthn origin");
+    WebauthnBuilder::new(&CONFIG.webauthn_rp_id, &origin)
+      .and_then(|builder| builder.rp_name(&CONFIG.app_name).build())
+      .expect("Error while configuring webauthn")
+  };
 }
 
 #[derive(OpenApi)]
@@ -42,23 +68,42 @@ lazy_static! {
     paths(
         // Auth
         route::auth::login,
-        route::auth::login_oauth,
+        route::auth::oauth_authorize,
+        route::auth::oauth_callback,
         route::auth::magic_link,
+        route::auth::magic_link_login,
         route::auth::register,
         route::auth::two_factor,
+        route::auth::two_factor_webauthn,
         route::auth::two_factor_recovery,
+        route::auth::refresh,
         route::auth::forgot_password,
         route::auth::reset_password,
         // Account
         route::account::get_info,
         route::account::verify_email,
+        route::account::resend_email_verification,
         route::account::change_password,
         route::account::change_phone,
+        route::account::confirm_phone,
         route::account::info_2fa,
         route::account::setup_2fa,
         route::account::enable_2fa,
         route::account::disable_2fa,
+        route::account::regenerate_recovery_codes_2fa,
+        route::account::get_webauthn_credentials,
+        route::account::webauthn_register_start,
+        route::account::webauthn_register_finish,
+        route::account::webauthn_rename,
+        route::account::webauthn_delete,
         route::account::upload_profile_picture,
+        route::account::get_sessions,
+        route::account::revoke_session,
+        route::account::revoke_all_sessions,
+        route::account::get_oauth_providers,
+        route::account::oauth_authorize,
+        route::account::oauth_link,
+        route::account::oauth_unlink,
         // Invitation
         route::invitation::get_all,
         route::invitation::response,
@@ -69,6 +114,7 @@ lazy_static! {
         route::organization::update,
         route::organization::update_logo,
         route::organization::delete,
+        route::organization::restore,
         route::organization::transfer,
         // Organization Invitation
         route::organization::invitation::get_all,
@@ -79,6 +125,8 @@ lazy_static! {
         route::organization::api_key::new,
         route::organization::api_key::get,
         route::organization::api_key::get_by_id,
+        route::organization::api_key::update,
+        route::organization::api_key::rotate,
         route::organization::api_key::delete,
         // Organization Members
         route::organization::member::get_all,
@@ -101,6 +149,21 @@ lazy_static! {
         route::organization::project::cluster::new,
         route::organization::project::cluster::get_all,
         route::organization::project::cluster::get,
+        route::organization::project::cluster::update,
+        route::organization::project::cluster::delete,
+        // Organization Project Deployment
+        route::organization::project::deployment::new,
+        route::organization::project::deployment::get_all,
+        route::organization::project::deployment::get,
+        route::organization::project::deployment::update,
+        route::organization::project::deployment::rollout,
+        route::organization::project::deployment::delete,
+        // Organization Project Environment
+        route::organization::project::environment::new,
+        route::organization::project::environment::get_all,
+        route::organization::project::environment::get,
+        route::organization::project::environment::update,
+        route::organization::project::environment::delete,
         // Organization Credentials Docker Hub
         route::organization::credentials::docker_hub::new,
         route::organization::credentials::docker_hub::get,
@@ -124,6 +187,8 @@ lazy_static! {
         // Cloud Provider Aws
         route::cloud_provider::aws::all_region,
         route::cloud_provider::aws::instance_types,
+        // Well Known
+        route::well_known::jwks,
     ),
     components(schemas(
         // Global
@@ -131,25 +196,40 @@ lazy_static! {
         route::ErrorMessage,
         // Auth
         route::auth::dto::LoginRequest,
-        route::auth::dto::LoginOAuthRequest,
         route::auth::dto::OAuthServiceType,
+        route::auth::dto::OAuthAuthorizeResponse,
+        route::auth::dto::OAuthCallbackRequest,
         route::auth::dto::LoginResponse,
         route::auth::dto::MagicLinkRequest,
+        route::auth::dto::MagicLinkLoginRequest,
         route::auth::dto::RegisterRequest,
         route::auth::dto::TwoFactorRecoveryRequest,
         route::auth::dto::TwoFactorCodeRequest,
+        route::auth::dto::TwoFactorWebauthnRequest,
         route::auth::dto::ForgotPasswordRequest,
         route::auth::dto::ResetPasswordRequest,
+        route::auth::dto::RefreshTokenRequest,
         // Account
         route::account::dto::GetAccountInfoResponse,
         route::account::dto::VerifyEmailRequest,
         route::account::dto::ChangePasswordRequest,
         route::account::dto::ChangePhoneRequest,
+        route::account::dto::ConfirmPhoneRequest,
         route::account::dto::TwoFactorSetupRequest,
         route::account::dto::TwoFactorSetupResponse,
         route::account::dto::TwoFactorInfoRequest,
         route::account::dto::TwoFactorInfoResponse,
+        route::account::dto::TwoFactorRecoveryCodesRequest,
+        route::account::dto::TwoFactorRecoveryCodesResponse,
         route::account::dto::TwoFactorCodeRequest,
+        route::account::dto::TwoFactorWebauthnRegisterStartRequest,
+        route::account::dto::TwoFactorWebauthnRegisterFinishRequest,
+        route::account::dto::TwoFactorWebauthnRegisterFinishResponse,
+        route::account::dto::TwoFactorWebauthnRenameRequest,
+        route::account::dto::TwoFactorWebauthnDeleteRequest,
+        route::account::dto::TwoFactorWebauthnInfoResponse,
+        route::account::dto::SessionInfoResponse,
+        route::account::dto::OAuthProviderInfoResponse,
         // Invitation
         route::invitation::dto::InvitationInfoResponse,
         route::invitation::dto::InvitationInfoUser,
@@ -168,6 +248,16 @@ lazy_static! {
         // Organization Project Cluster
         route::organization::project::cluster::dto::CreateClusterRequest,
         route::organization::project::cluster::dto::ClusterInfoResponse,
+        route::organization::project::cluster::dto::UpdateClusterRequest,
+        // Organization Project Deployment
+        route::organization::project::deployment::dto::CreateDeploymentRequest,
+        route::organization::project::deployment::dto::UpdateDeploymentRequest,
+        route::organization::project::deployment::dto::RolloutDeploymentRequest,
+        route::organization::project::deployment::dto::DeploymentInfoResponse,
+        // Organization Project Environment
+        route::organization::project::environment::dto::CreateEnvironmentRequest,
+        route::organization::project::environment::dto::UpdateEnvironmentRequest,
+        route::organization::project::environment::dto::EnvironmentInfoResponse,
         // Organization Invitation
         route::organization::invitation::dto::NewOrganizationInvitationRequest,
         route::organization::invitation::dto::OrganizationInvitationInfoResponse,
@@ -179,11 +269,16 @@ lazy_static! {
         route::organization::role::dto::CustomRoleInfoResponse,
         route::organization::role::dto::CustomRoleClusterPermissionInfo,
         route::organization::role::dto::CustomRoleGeneralPermissionInfo,
+        route::organization::role::dto::CustomRoleEnvironmentAccessInfo,
+        route::organization::role::dto::CustomRoleEnvironmentPermissionInfo,
         route::organization::role::dto::UpdateCustomRoleRequest,
         // Organization Api Keys
         route::organization::api_key::dto::CreateApiKeyRequest,
         route::organization::api_key::dto::ApiKeyInfoResponse,
         route::organization::api_key::dto::ApiKeyRoleInfoResponse,
+        route::organization::api_key::dto::UpdateApiKeyRequest,
+        route::organization::api_key::dto::RotateApiKeyRequest,
+        route::organization::api_key::dto::CreateApiKeyResponse,
         // Organization Credentials Docker Hub
         route::organization::credentials::docker_hub::dto::DockerHubInfoResponse,
         route::organization::credentials::docker_hub::dto::NewDockerHubRequest,
@@ -207,11 +302,10 @@ struct ApiDoc;
 
 #[rocket::main]
 async fn main() -> Result<(), rocket::Error> {
-  let token =
-    "e13c70b28973b2d8b6280649cf2b3b2cae7a948568bbcc48164ae2a46acf2008"
-      .to_string();
-  let user_info = OAuth::get_user(OAuthService::GitLab, token).await;
-  println!("{:?}", user_info);
+  // Fail at startup rather than on the first credential
+  lazy_static::initialize(&ENCRYPTION_KEY);
+  lazy_static::initialize(&JWT_KEYRING);
+  lazy_static::initialize(&WEBAUTHN);
 
   let mongodb_client =
     mongodb::Client::with_uri_str(CONFIG.mongodb_url.as_str())
@@ -219,8 +313,26 @@ async fn main() -> Result<(), rocket::Error> {
       .expect("Failed to connect to mongodb");
   let mongodb_database =
     mongodb_client.database(CONFIG.mongodb_database.as_str());
+  CommonCollection::<OrganizationApiKey>::new(&mongodb_database)
+    .migrate_key_hashes()
+    .await
+    .expect("Failed to migrate the api keys");
+  CommonCollection::<User>::new(&mongodb_database)
+    .migrate_recovery_code_hashes()
+    .await
+    .expect("Failed to migrate the recovery codes");
+  encrypt_plaintext_credentials(&mongodb_database, &ENCRYPTION_KEY)
+    .await
+    .expect("Failed to encrypt the credentials");
   let redis_client = redis::Client::open(CONFIG.redis_url.as_str()).unwrap();
 
+  let sms_provider: Box<dyn SmsProvider> = match CONFIG.sms_provider {
+    SmsProviderType::Kafka => {
+      Box::new(KafkaSmsProvider::new(mongodb_database.clone()))
+    }
+    SmsProviderType::InMemory => Box::new(InMemorySmsProvider::new()),
+  };
+
   // Catchers
 
   let catcher_list = catchers![catcher::default];
@@ -230,22 +342,42 @@ async fn main() -> Result<(), rocket::Error> {
   let routes = routes![
     // Auth
     route::auth::login,
-    route::auth::login_oauth,
+    route::auth::oauth_authorize,
+    route::auth::oauth_callback,
     route::auth::register,
     route::auth::magic_link,
+    route::auth::magic_link_login,
     route::auth::two_factor,
+    route::auth::two_factor_webauthn,
+    route::auth::two_factor_recovery,
+    route::auth::refresh,
     route::auth::reset_password,
     route::auth::forgot_password,
     // Account
     route::account::get_info,
     route::account::verify_email,
+    route::account::resend_email_verification,
     route::account::change_password,
     route::account::change_phone,
+    route::account::confirm_phone,
     route::account::info_2fa,
     route::account::setup_2fa,
     route::account::enable_2fa,
     route::account::disable_2fa,
+    route::account::regenerate_recovery_codes_2fa,
+    route::account::get_webauthn_credentials,
+    route::account::webauthn_register_start,
+    route::account::webauthn_register_finish,
+    route::account::webauthn_rename,
+    route::account::webauthn_delete,
     route::account::upload_profile_picture,
+    route::account::get_sessions,
+    route::account::revoke_session,
+    route::account::revoke_all_sessions,
+    route::account::get_oauth_providers,
+    route::account::oauth_authorize,
+    route::account::oauth_link,
+    route::account::oauth_unlink,
     // Invitation
     route::invitation::get_all,
     route::invitation::response,
@@ -256,11 +388,14 @@ async fn main() -> Result<(), rocket::Error> {
     route::organization::update,
     route::organization::update_logo,
     route::organization::delete,
+    route::organization::restore,
     route::organization::transfer,
     // Organization Api Keys
     route::organization::api_key::new,
     route::organization::api_key::get,
     route::organization::api_key::get_by_id,
+    route::organization::api_key::update,
+    route::organization::api_key::rotate,
     route::organization::api_key::delete,
     // Organization Members
     route::organization::member::get_all,
@@ -288,6 +423,21 @@ async fn main() -> Result<(), rocket::Error> {
     route::organization::project::cluster::new,
     route::organization::project::cluster::get_all,
     route::organization::project::cluster::get,
+    route::organization::project::cluster::update,
+    route::organization::project::cluster::delete,
+    // Organization Project Deployment
+    route::organization::project::deployment::new,
+    route::organization::project::deployment::get_all,
+    route::organization::project::deployment::get,
+    route::organization::project::deployment::update,
+    route::organization::project::deployment::rollout,
+    route::organization::project::deployment::delete,
+    // Organization Project Environment
+    route::organization::project::environment::new,
+    route::organization::project::environment::get_all,
+    route::organization::project::environment::get,
+    route::organization::project::environment::update,
+    route::organization::project::environment::delete,
     // Organization Aws Credentials
     route::organization::credentials::aws::new,
     route::organization::credentials::aws::get,
@@ -309,6 +459,8 @@ async fn main() -> Result<(), rocket::Error> {
     // Cloud Provider Aws
     route::cloud_provider::aws::all_region,
     route::cloud_provider::aws::instance_types,
+    // Well Known
+    route::well_known::jwks,
   ];
 
   let swagger_ui = SwaggerUi::new("/swagger-ui/<_..>")
@@ -316,10 +468,16 @@ async fn main() -> Result<(), rocket::Error> {
 
   let redoc_ui = Redoc::with_url("/redoc", ApiDoc::openapi());
 
-  rocket::build()
+  let event_producer = EventProducer::new(CONFIG.kafka_url.clone());
+
+  rocket::custom(rocket_figment())
     .attach(Cors)
+    .attach(OutboxRelay)
+    .attach(RateLimiter)
     .manage(mongodb_database)
     .manage(redis_client)
+    .manage(sms_provider)
+    .manage(event_producer)
     .register("/", catcher_list)
     .mount("/", swagger_ui)
     .mount("/", redoc_ui)
@@ -20,12 +20,17 @@ impl GitHubEmail {
   }
 }
 
+#[derive(Debug, Deserialize, Serialize)]
 pub struct GitHubUser {
-  email: String,
+  pub id: u64,
+  pub name: Option<String>,
 }
 
-impl Into<OAuthUser> for GitHubEmail {
-  fn into(self) -> OAuthUser {
-    OAuthUser::new(self.email)
+impl GitHubUser {
+  pub fn into_oauth_user(
+    self,
+    email: GitHubEmail,
+  ) -> OAuthUser {
+    OAuthUser::new(self.id.to_string(), email.email, self.name)
   }
 }
@@ -1,8 +1,7 @@
 use crate::error::ApiError;
-use crate::oauth::github::data::GitHubEmail;
+use crate::oauth::github::data::{GitHubEmail, GitHubUser};
 use crate::oauth::OAuthUser;
 use crate::CONFIG;
-use reqwest::header::HeaderName;
 use reqwest::{Method, Url};
 use rocket::http::Status;
 
@@ -12,21 +11,46 @@ const GITHUB_API_URL: &str = "https://api.github.com";
 
 const GITHUB_API_VERSION: &str = "2022-11-28";
 
+pub(crate) const AUTHORIZE_URL: &str =
+  "https://github.com/login/oauth/authorize";
+
+pub(crate) const TOKEN_URL: &str =
+  "https://github.com/login/oauth/access_token";
+
+pub(crate) const SCOPE: &str = "read:user user:email";
+
 pub async fn get_user(access_token: String) -> Result<OAuthUser, ApiError> {
+  let user = send_get_user(access_token.clone()).await?;
   let email = send_get_email(access_token).await?;
-  let user = GitHubEmail::get_primary_email(email);
-  match user {
+  let email = GitHubEmail::get_primary_email(email);
+  match email {
     None => Err(ApiError::new(
       Status::InternalServerError,
       "Could not get primary email from GitHub".to_string(),
     )),
-    Some(user) => {
-      let result: OAuthUser = user.into();
-      Ok(result)
-    }
+    Some(email) => Ok(user.into_oauth_user(email)),
   }
 }
 
+async fn send_get_user(access_token: String) -> Result<GitHubUser, ApiError> {
+  let final_url = format!("{}/user", GITHUB_API_URL);
+  let final_token = format!("Bearer {}", access_token);
+  let url = Url::parse(&final_url).expect("Could not parse URL");
+  let client = reqwest::Client::new();
+  let request = client
+    .request(Method::GET, url)
+    .header("Authorization", final_token)
+    .header("User-Agent", CONFIG.app_name.clone())
+    .header("Accept", "application/vnd.github.v3+json")
+    .header("X-GitHub-Api-Version", GITHUB_API_VERSION)
+    .build()?;
+  let response = client.execute(request).await?;
+  let body_str = response.text().await?;
+  debug!("Response body: {}", body_str);
+  let body: GitHubUser = serde_json::from_str(&body_str)?;
+  Ok(body)
+}
+
 async fn send_get_email(
   access_token: String
 ) -> Result<Vec<GitHubEmail>, ApiError> {
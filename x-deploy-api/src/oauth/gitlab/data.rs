@@ -3,12 +3,14 @@ use serde::Deserialize;
 
 #[derive(Deserialize, Debug)]
 pub struct GitLabUser {
+  pub id: u64,
+  pub name: Option<String>,
   pub email: String,
   pub confirmed_at: Option<String>,
 }
 
 impl Into<OAuthUser> for GitLabUser {
   fn into(self) -> OAuthUser {
-    OAuthUser::new(self.email)
+    OAuthUser::new(self.id.to_string(), self.email, self.name)
   }
 }
@@ -9,6 +9,12 @@ mod data;
 
 const GITLAB_API_URL: &str = "https://gitlab.com/api/v4";
 
+pub(crate) const AUTHORIZE_URL: &str = "https://gitlab.com/oauth/authorize";
+
+pub(crate) const TOKEN_URL: &str = "https://gitlab.com/oauth/token";
+
+pub(crate) const SCOPE: &str = "read_user";
+
 pub async fn get_user(access_token: String) -> Result<OAuthUser, ApiError> {
   let user = send_get_user(access_token).await?;
   if let None = user.confirmed_at {
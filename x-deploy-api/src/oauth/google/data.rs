@@ -2,13 +2,15 @@ use crate::oauth::OAuthUser;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct GoogleTokenInfo {
+pub struct GoogleUserInfo {
+  pub id: String,
   pub email: String,
   pub verified_email: bool,
+  pub name: Option<String>,
 }
 
-impl Into<OAuthUser> for GoogleTokenInfo {
+impl Into<OAuthUser> for GoogleUserInfo {
   fn into(self) -> OAuthUser {
-    OAuthUser::new(self.email)
+    OAuthUser::new(self.id, self.email, self.name)
   }
 }
@@ -1,5 +1,5 @@
 use crate::error::ApiError;
-use crate::oauth::google::data::GoogleTokenInfo;
+use crate::oauth::google::data::GoogleUserInfo;
 use crate::oauth::OAuthUser;
 use crate::CONFIG;
 use reqwest::{Method, Url};
@@ -9,8 +9,15 @@ mod data;
 
 const GOOGLE_API_URL: &str = "https://www.googleapis.com/oauth2/v2";
 
+pub(crate) const AUTHORIZE_URL: &str =
+  "https://accounts.google.com/o/oauth2/v2/auth";
+
+pub(crate) const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+
+pub(crate) const SCOPE: &str = "openid email profile";
+
 pub async fn get_user(access_token: String) -> Result<OAuthUser, ApiError> {
-  let user = send_token_info(access_token).await?;
+  let user = send_user_info(access_token).await?;
   if !user.verified_email {
     return Err(ApiError::new(
       Status::Forbidden,
@@ -21,16 +28,16 @@ pub async fn get_user(access_token: String) -> Result<OAuthUser, ApiError> {
   Ok(result)
 }
 
-async fn send_token_info(
+async fn send_user_info(
   access_token: String
-) -> Result<GoogleTokenInfo, ApiError> {
-  let final_url = format!("{}/tokeninfo", GOOGLE_API_URL);
+) -> Result<GoogleUserInfo, ApiError> {
+  let final_url = format!("{}/userinfo", GOOGLE_API_URL);
   let url = Url::parse(&final_url).expect("Could not parse URL");
 
   let client = reqwest::Client::new();
   let request = client
     .request(Method::GET, url)
-    .query(&[("access_token", access_token)])
+    .header("Authorization", format!("Bearer {}", access_token))
     .header("User-Agent", CONFIG.app_name.as_str())
     .build()?;
   let response = client.execute(request).await?;
@@ -41,6 +48,6 @@ async fn send_token_info(
     code.as_str(),
     body_str
   );
-  let result: GoogleTokenInfo = serde_json::from_str(&body_str)?;
+  let result: GoogleUserInfo = serde_json::from_str(&body_str)?;
   Ok(result)
 }
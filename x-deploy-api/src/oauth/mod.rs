@@ -1,11 +1,27 @@
+use crate::config::OAuthClientConfig;
 use crate::error::ApiError;
+use crate::CONFIG;
+use bson::oid::ObjectId;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use reqwest::{Method, Url};
+use rocket::http::Status;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
+use x_deploy_common::cache::oauth::{
+  save_oauth_state, take_oauth_state, OAuthState,
+};
+use x_deploy_common::crypto::hash::pkce_challenge;
+use x_deploy_common::db::user::OAuthProvider;
 
 pub mod github;
 mod gitlab;
 mod google;
 
+const OAUTH_STATE_LENGTH: usize = 32;
+
+const OAUTH_CODE_VERIFIER_LENGTH: usize = 64;
+
 pub struct OAuth;
 
 impl OAuth {
@@ -19,6 +35,69 @@ impl OAuth {
       OAuthService::GitLab => gitlab::get_user(access_token).await,
     };
   }
+
+  /// Starts an authorization-code flow with PKCE and returns the URL of
+  /// the provider the user must be redirected to.
+  pub async fn authorize(
+    redis: &redis::Client,
+    service: OAuthService,
+    user_id: Option<ObjectId>,
+  ) -> Result<String, ApiError> {
+    let client = service.client_config()?;
+    let state = random_string(OAUTH_STATE_LENGTH);
+    let code_verifier = random_string(OAUTH_CODE_VERIFIER_LENGTH);
+    let oauth_state = OAuthState {
+      provider: service.clone().into(),
+      code_verifier: code_verifier.clone(),
+      user_id,
+    };
+    save_oauth_state(redis, &state, &oauth_state).await?;
+    let url = Url::parse_with_params(
+      service.authorize_url(),
+      &[
+        ("response_type", "code"),
+        ("client_id", client.client_id.as_str()),
+        ("redirect_uri", CONFIG.oauth_redirect_uri.as_str()),
+        ("scope", service.scope()),
+        ("state", state.as_str()),
+        ("code_challenge", pkce_challenge(&code_verifier).as_str()),
+        ("code_challenge_method", "S256"),
+      ],
+    )
+    .expect("Could not parse URL");
+    Ok(url.to_string())
+  }
+
+  /// Completes an authorization started with `authorize`, returning the
+  /// provider account and the user who started a link, if any.
+  pub async fn callback(
+    redis: &redis::Client,
+    service: OAuthService,
+    code: String,
+    state: String,
+  ) -> Result<(OAuthUser, Option<ObjectId>), ApiError> {
+    let oauth_state = match take_oauth_state(redis, &state).await? {
+      Some(oauth_state) => oauth_state,
+      None => {
+        return Err(ApiError::new(
+          Status::BadRequest,
+          "The authorization is unknown or expired, please try again"
+            .to_string(),
+        ))
+      }
+    };
+    let provider: OAuthProvider = service.clone().into();
+    if oauth_state.provider != provider {
+      return Err(ApiError::new(
+        Status::BadRequest,
+        "The authorization was started for another provider".to_string(),
+      ));
+    }
+    let access_token =
+      exchange_code(&service, code, oauth_state.code_verifier).await?;
+    let user = OAuth::get_user(service, access_token).await?;
+    Ok((user, oauth_state.user_id))
+  }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -28,13 +107,155 @@ pub enum OAuthService {
   GitLab,
 }
 
+impl OAuthService {
+  fn client_config(&self) -> Result<&'static OAuthClientConfig, ApiError> {
+    let config = match self {
+      OAuthService::Github => &CONFIG.oauth_github,
+      OAuthService::Google => &CONFIG.oauth_google,
+      OAuthService::GitLab => &CONFIG.oauth_gitlab,
+    };
+    return match config {
+      Some(config) => Ok(config),
+      None => Err(ApiError::new(
+        Status::NotFound,
+        "This provider is not available".to_string(),
+      )),
+    };
+  }
+
+  fn authorize_url(&self) -> &'static str {
+    match self {
+      OAuthService::Github => github::AUTHORIZE_URL,
+      OAuthService::Google => google::AUTHORIZE_URL,
+      OAuthService::GitLab => gitlab::AUTHORIZE_URL,
+    }
+  }
+
+  fn token_url(&self) -> &'static str {
+    match self {
+      OAuthService::Github => github::TOKEN_URL,
+      OAuthService::Google => google::TOKEN_URL,
+      OAuthService::GitLab => gitlab::TOKEN_URL,
+    }
+  }
+
+  fn scope(&self) -> &'static str {
+    match self {
+      OAuthService::Github => github::SCOPE,
+      OAuthService::Google => google::SCOPE,
+      OAuthService::GitLab => gitlab::SCOPE,
+    }
+  }
+}
+
+impl FromStr for OAuthService {
+  type Err = ApiError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    return match s {
+      "github" => Ok(OAuthService::Github),
+      "google" => Ok(OAuthService::Google),
+      "gitlab" => Ok(OAuthService::GitLab),
+      _ => Err(ApiError::new(
+        Status::NotFound,
+        "This provider is not available".to_string(),
+      )),
+    };
+  }
+}
+
+impl Into<OAuthProvider> for OAuthService {
+  fn into(self) -> OAuthProvider {
+    match self {
+      OAuthService::Github => OAuthProvider::Github,
+      OAuthService::Google => OAuthProvider::Google,
+      OAuthService::GitLab => OAuthProvider::GitLab,
+    }
+  }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct OAuthUser {
+  /// The id of the account on the provider, unlike the email it can't
+  /// change
+  pub id: String,
   pub email: String,
+  pub name: Option<String>,
 }
 
 impl OAuthUser {
-  pub fn new(email: String) -> Self {
-    Self { email }
+  pub fn new(
+    id: String,
+    email: String,
+    name: Option<String>,
+  ) -> Self {
+    Self { id, email, name }
   }
+
+  /// Splits the name for the sign up, the part before the `@` of the
+  /// email is used when the provider has no name.
+  pub fn firstname_lastname(&self) -> (String, String) {
+    let name = match &self.name {
+      Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+      _ => self.email.split('@').next().unwrap_or_default().to_string(),
+    };
+    return match name.split_once(' ') {
+      Some((firstname, lastname)) => {
+        (firstname.to_string(), lastname.trim().to_string())
+      }
+      None => (name, String::new()),
+    };
+  }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct OAuthTokenResponse {
+  access_token: Option<String>,
+  error: Option<String>,
+}
+
+async fn exchange_code(
+  service: &OAuthService,
+  code: String,
+  code_verifier: String,
+) -> Result<String, ApiError> {
+  let client_config = service.client_config()?;
+  let url = Url::parse(service.token_url()).expect("Could not parse URL");
+  let client = reqwest::Client::new();
+  let request = client
+    .request(Method::POST, url)
+    .header("Accept", "application/json")
+    .header("User-Agent", CONFIG.app_name.as_str())
+    .form(&[
+      ("grant_type", "authorization_code"),
+      ("client_id", client_config.client_id.as_str()),
+      ("client_secret", client_config.client_secret.as_str()),
+      ("redirect_uri", CONFIG.oauth_redirect_uri.as_str()),
+      ("code", code.as_str()),
+      ("code_verifier", code_verifier.as_str()),
+    ])
+    .build()?;
+  let response = client.execute(request).await?;
+  let code = response.status().clone();
+  let body_str = response.text().await?;
+  debug!("Token response with code {}", code.as_str());
+  let body: OAuthTokenResponse = serde_json::from_str(&body_str)?;
+  return match body.access_token {
+    Some(access_token) => Ok(access_token),
+    None => {
+      debug!("Token exchange failed: {:?}", body.error);
+      Err(ApiError::new(
+        Status::Unauthorized,
+        "The provider refused the authorization code".to_string(),
+      ))
+    }
+  };
+}
+
+fn random_string(length: usize) -> String {
+  rand::thread_rng()
+    .sample_iter(&Alphanumeric)
+    .take(length)
+    .map(char::from)
+    .collect::<String>()
 }
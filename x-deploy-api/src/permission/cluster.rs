@@ -1,11 +1,122 @@
-use x_deploy_common::db::organization_role::{
-  ClusterPermission, OrganizationRole,
-};
-
-#[deprecated]
-pub fn has_cluster_permission(
-  role: OrganizationRole,
-  ask: ClusterPermission,
-) -> bool {
-  todo!()
+use crate::error::ApiError;
+use crate::guard::auth::Auth;
+use crate::permission::get_cluster_level;
+use bson::oid::ObjectId;
+use mongodb::Database;
+use rocket::http::Status;
+use x_deploy_common::db::organization_apikey::OrganizationApiKey;
+use x_deploy_common::db::organization_member::OrganizationMember;
+use x_deploy_common::db::organization_role::ClusterPermission as CommonClusterPermission;
+use x_deploy_common::db::organization_role::OrganizationRole;
+use x_deploy_common::db::query::organization_api_key::OrganizationApiKeyQuery;
+use x_deploy_common::db::query::organization_member::OrganizationMemberQuery;
+use x_deploy_common::db::CommonCollection;
+
+pub struct ClusterPermission;
+
+impl ClusterPermission {
+  pub fn has_cluster_permission(
+    &self,
+    role: &Option<OrganizationRole>,
+    ask: &CommonClusterPermission,
+  ) -> bool {
+    return match role {
+      None => true,
+      Some(role) => {
+        let level = get_cluster_level(&role.cluster_permission);
+        let ask_level = get_cluster_level(ask);
+        return level >= ask_level;
+      }
+    };
+  }
+
+  pub fn verify(
+    &self,
+    role: &Option<OrganizationRole>,
+    ask: &CommonClusterPermission,
+  ) -> Result<(), ApiError> {
+    let result = self.has_cluster_permission(role, ask);
+    if result {
+      return Ok(());
+    }
+    Err(ApiError::new(
+      Status::Forbidden,
+      "You don't have the permission to do this on clusters".to_string(),
+    ))
+  }
+
+  /// The method verifies if the user has the permission to
+  /// do the action on clusters and was a member of the organization.
+  /// # Arguments
+  ///
+  /// * `db`: The database connection
+  /// * `auth`: The authentication method
+  /// * `org_id`: The id of the organization
+  /// * `ask`: The permission to ask for
+  ///
+  /// returns: Result<Option<OrganizationRole>, ApiError>
+  /// The role if the user has a role or None if the user is the owner of the organization
+  pub async fn verify_auth(
+    &self,
+    db: &Database,
+    auth: Auth,
+    org_id: &ObjectId,
+    ask: CommonClusterPermission,
+  ) -> Result<Option<OrganizationRole>, ApiError> {
+    return match auth {
+      Auth::ApiKey(api_key) => {
+        let result = self
+          .verify_key_and_get(db, &api_key.id, &org_id, &ask)
+          .await?;
+        Ok(result.role)
+      }
+      Auth::Bearer(token) => {
+        let user_id = token.parse_id()?;
+        let result = self.verify_and_get(db, &user_id, &org_id, &ask).await?;
+        Ok(result.role)
+      }
+    };
+  }
+
+  pub async fn verify_and_get(
+    &self,
+    db: &Database,
+    user_id: &ObjectId,
+    org_id: &ObjectId,
+    ask: &CommonClusterPermission,
+  ) -> Result<OrganizationMemberQuery, ApiError> {
+    let omc = CommonCollection::<OrganizationMember>::new(db);
+    let org_user = omc.get_user_in_org(org_id, user_id).await?;
+    return match org_user {
+      Some(org_user) => {
+        self.verify(&org_user.role, ask)?;
+        Ok(org_user)
+      }
+      None => Err(ApiError::new(
+        Status::NotFound,
+        "You are not a member of this organization".to_string(),
+      )),
+    };
+  }
+
+  pub async fn verify_key_and_get(
+    &self,
+    db: &Database,
+    api_key_id: &ObjectId,
+    org_id: &ObjectId,
+    ask: &CommonClusterPermission,
+  ) -> Result<OrganizationApiKeyQuery, ApiError> {
+    let oakc = CommonCollection::<OrganizationApiKey>::new(db);
+    let api_key = oakc.get_by_id_of_org(org_id, api_key_id).await?;
+    return match api_key {
+      Some(api_key) => {
+        self.verify(&api_key.role, ask)?;
+        Ok(api_key)
+      }
+      None => Err(ApiError::new(
+        Status::NotFound,
+        "You are not a member of this organization".to_string(),
+      )),
+    };
+  }
 }
@@ -0,0 +1,96 @@
+use crate::error::ApiError;
+use crate::guard::auth::Auth;
+use crate::permission::general::GeneralPermission;
+use crate::permission::get_environment_level;
+use bson::oid::ObjectId;
+use mongodb::Database;
+use rocket::http::Status;
+use x_deploy_common::db::organization_role::EnvironmentPermission as CommonEnvironmentPermission;
+use x_deploy_common::db::organization_role::{
+  OrganizationRole, StandardPermission,
+};
+
+pub struct EnvironmentPermission {
+  pub environment_id: ObjectId,
+}
+
+impl EnvironmentPermission {
+  pub fn new(environment_id: ObjectId) -> Self {
+    Self { environment_id }
+  }
+
+  pub fn has_environment_permission(
+    &self,
+    role: &Option<OrganizationRole>,
+    ask: &CommonEnvironmentPermission,
+  ) -> bool {
+    return match role {
+      None => true,
+      Some(role) => {
+        let permission = self.get_environment_role(role);
+        let level = get_environment_level(&permission);
+        let ask_level = get_environment_level(ask);
+        return level >= ask_level;
+      }
+    };
+  }
+
+  pub fn verify(
+    &self,
+    role: &Option<OrganizationRole>,
+    ask: &CommonEnvironmentPermission,
+  ) -> Result<(), ApiError> {
+    let result = self.has_environment_permission(role, ask);
+    if result {
+      return Ok(());
+    }
+    Err(ApiError::new(
+      Status::Forbidden,
+      "You don't have the permission to do this on this environment"
+        .to_string(),
+    ))
+  }
+
+  /// The method verifies if the user was a member of the organization
+  /// and has the permission to do the action on the environment.
+  /// # Arguments
+  ///
+  /// * `db`: The database connection
+  /// * `auth`: The authentication method
+  /// * `org_id`: The id of the organization
+  /// * `ask`: The permission to ask for
+  ///
+  /// returns: Result<Option<OrganizationRole>, ApiError>
+  /// The role if the user has a role or None if the user is the owner of the organization
+  pub async fn verify_auth(
+    &self,
+    db: &Database,
+    auth: Auth,
+    org_id: &ObjectId,
+    ask: CommonEnvironmentPermission,
+  ) -> Result<Option<OrganizationRole>, ApiError> {
+    let role = GeneralPermission::Project
+      .verify_auth(db, auth, org_id, StandardPermission::None)
+      .await?;
+    self.verify(&role, &ask)?;
+    Ok(role)
+  }
+
+  /// When the role doesn't set a permission for the environment, the access
+  /// is derived from the project permission of the role.
+  fn get_environment_role(
+    &self,
+    role: &OrganizationRole,
+  ) -> CommonEnvironmentPermission {
+    if let Some(permission) =
+      role.get_environment_permission(&self.environment_id)
+    {
+      return permission;
+    }
+    return match role.general_permission.project {
+      StandardPermission::None => CommonEnvironmentPermission::NoAccess,
+      StandardPermission::Read => CommonEnvironmentPermission::Read,
+      StandardPermission::ReadWrite => CommonEnvironmentPermission::FullAccess,
+    };
+  }
+}
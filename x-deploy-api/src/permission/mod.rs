@@ -1,7 +1,10 @@
 pub mod cluster;
+pub mod environment;
 pub mod general;
 
-use x_deploy_common::db::organization_role::StandardPermission;
+use x_deploy_common::db::organization_role::{
+  ClusterPermission, EnvironmentPermission, StandardPermission,
+};
 
 fn get_level(permission: &StandardPermission) -> u8 {
   return match permission {
@@ -10,3 +13,21 @@ fn get_level(permission: &StandardPermission) -> u8 {
     StandardPermission::ReadWrite => 2,
   };
 }
+
+fn get_environment_level(permission: &EnvironmentPermission) -> u8 {
+  return match permission {
+    EnvironmentPermission::NoAccess => 0,
+    EnvironmentPermission::Read => 1,
+    EnvironmentPermission::Deploy => 2,
+    EnvironmentPermission::Manage => 3,
+    EnvironmentPermission::FullAccess => 4,
+  };
+}
+
+fn get_cluster_level(permission: &ClusterPermission) -> u8 {
+  return match permission {
+    ClusterPermission::ReadEnvironment => 1,
+    ClusterPermission::CreateEnvironment => 2,
+    ClusterPermission::FullAccess => 3,
+  };
+}
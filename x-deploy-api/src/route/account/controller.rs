@@ -1,9 +1,18 @@
 use crate::guard::bearer_token::BearerToken;
+use crate::oauth::{OAuth, OAuthService};
 use crate::route::account::dto::{
-  ChangePasswordRequest, ChangePhoneRequest, GetAccountInfoResponse,
+  ChangePasswordRequest, ChangePhoneRequest, ConfirmPhoneRequest,
+  GetAccountInfoResponse, OAuthProviderInfoResponse, SessionInfoResponse,
   TwoFactorCodeRequest, TwoFactorInfoRequest, TwoFactorInfoResponse,
-  TwoFactorSetupRequest, TwoFactorSetupResponse, VerifyEmailRequest,
+  TwoFactorRecoveryCodesRequest, TwoFactorRecoveryCodesResponse,
+  TwoFactorSetupRequest, TwoFactorSetupResponse,
+  TwoFactorWebauthnDeleteRequest, TwoFactorWebauthnInfoResponse,
+  TwoFactorWebauthnRegisterFinishRequest,
+  TwoFactorWebauthnRegisterFinishResponse,
+  TwoFactorWebauthnRegisterStartRequest, TwoFactorWebauthnRenameRequest,
+  VerifyEmailRequest,
 };
+use crate::route::auth::dto::{OAuthAuthorizeResponse, OAuthCallbackRequest};
 use crate::route::{
   custom_error, custom_message, custom_response, ApiResult, SuccessMessage,
 };
@@ -11,21 +20,55 @@ use crate::utils::password::{
   hash_password, is_strong_password, verify_password,
 };
 use crate::utils::profile_picture::ProfilePicture;
+use crate::utils::session::revoke_access_tokens;
+use crate::utils::sms::SmsProvider;
 use crate::utils::two_factor::{
-  generate_recovery_code, new_2fa, verify_2fa_code,
+  generate_recovery_codes, new_2fa, verify_2fa_code,
 };
+use crate::utils::verification::{
+  check_verification_code, generate_verification_code, send_email_verification,
+  CodeError,
+};
+use crate::utils::webauthn::{finish_registration, start_registration};
 use crate::CONFIG;
+use bson::oid::ObjectId;
+use bson::DateTime;
 use mongodb::Database;
 use rocket::http::{ContentType, Status};
 use rocket::serde::json::Json;
 use rocket::{Data, State};
-use x_deploy_common::db::user::{TwoFactor, User};
-use x_deploy_common::db::CommonCollection;
+use std::str::FromStr;
+use validator::Validate;
+use webauthn_rs::prelude::CreationChallengeResponse;
+use x_deploy_common::cache::lockout::{
+  clear_failures, locked_for, record_failure, LockoutPolicy,
+};
+use x_deploy_common::cache::rate_limit::hit;
+use x_deploy_common::db::user::{
+  OAuthProvider, TwoFactor, User, UserOAuthProvider,
+};
+use x_deploy_common::db::user_session::UserSession;
+use x_deploy_common::db::{
+  commit_transaction, start_transaction, CommonCollection,
+};
 use x_deploy_common::s3::bucket::CommonS3Bucket;
 use x_deploy_common::s3::config::CommonS3Config;
 use x_deploy_common::s3::file_type::CommonS3BucketType;
 use CommonS3BucketType::UserProfilePicture;
 
+// Validity of a code sent to verify a phone
+const PHONE_CODE_DURATION_IN_MINUTES: i64 = 10;
+
+// Time to wait before sending another code to verify the email
+const EMAIL_RESEND_COOLDOWN_IN_SECONDS: u64 = 60;
+
+const VERIFICATION_CODE_LOCKOUT: LockoutPolicy = LockoutPolicy {
+  max_failures: 5,
+  failure_window_in_seconds: 3600,
+  lock_in_seconds: 60,
+  max_lock_in_seconds: 3600,
+};
+
 pub(crate) async fn get_info(
   token: BearerToken,
   db: &State<Database>,
@@ -56,6 +99,7 @@ pub(crate) async fn get_info(
 
 pub(crate) async fn verify_email(
   db: &State<Database>,
+  redis: &State<redis::Client>,
   token: BearerToken,
   body: Json<VerifyEmailRequest>,
 ) -> ApiResult<SuccessMessage> {
@@ -69,29 +113,76 @@ pub(crate) async fn verify_email(
   if user.email.verified {
     return custom_error(Status::BadRequest, "Email is already verified");
   }
-  let body_code = body.code.clone();
-  let user_code = match user.email.code.clone() {
-    None => {
+  // The code is short, so the attempts are limited
+  let subject = format!("email:{}", id);
+  if locked_for(redis, &subject).await?.is_some() {
+    return custom_error(
+      Status::TooManyRequests,
+      "Too many failed attempts, please try again later",
+    );
+  }
+  let checked = check_verification_code(
+    &user.email.code,
+    &user.email.code_expires_at,
+    &body.code,
+  );
+  match checked {
+    Ok(_) => {}
+    Err(CodeError::Missing) => {
+      return custom_error(
+        Status::BadRequest,
+        "No code was sent to your email, please ask for a new one",
+      )
+    }
+    Err(CodeError::Expired) => {
       return custom_error(
-        Status::InternalServerError,
-        "Code for verify email is empty in database",
+        Status::BadRequest,
+        "Code is expired, please ask for a new one",
       )
     }
-    Some(code) => code,
+    Err(CodeError::Invalid) => {
+      record_failure(redis, &subject, &VERIFICATION_CODE_LOCKOUT).await?;
+      return custom_error(
+        Status::BadRequest,
+        "Code for verify email is invalid",
+      );
+    }
+  }
+  clear_failures(redis, &subject).await?;
+  user_collection.email_confirm(&id).await?;
+  custom_message(Status::Ok, "Your email is now verified")
+}
+
+pub(crate) async fn resend_email_verification(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+) -> ApiResult<SuccessMessage> {
+  let id = token.parse_id()?;
+  let user = match CommonCollection::<User>::new(db).get_by_id(&id).await? {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
   };
-  // Verify if code is correct
-  if body_code != user_code {
+  if user.email.verified {
+    return custom_error(Status::BadRequest, "Email is already verified");
+  }
+  let key = format!("email-verification:{}", id);
+  let hit = hit(redis, &key, EMAIL_RESEND_COOLDOWN_IN_SECONDS).await?;
+  if hit.count > 1 {
     return custom_error(
-      Status::BadRequest,
-      "Code for verify email is invalid",
+      Status::TooManyRequests,
+      "A code was just sent, please wait before asking for a new one",
     );
   }
-  user_collection.email_confirm(&id).await?;
-  custom_message(Status::Ok, "Your email is now verified")
+  let mut session = start_transaction(db.client()).await?;
+  send_email_verification(db, &mut session, &user).await?;
+  commit_transaction(&mut session).await?;
+  custom_message(Status::Ok, "You will receive a new code in your email")
 }
 
 pub(crate) async fn change_password(
   db: &State<Database>,
+  redis: &State<redis::Client>,
   token: BearerToken,
   body: Json<ChangePasswordRequest>,
 ) -> ApiResult<SuccessMessage> {
@@ -129,15 +220,109 @@ pub(crate) async fn change_password(
       "Password is not updated in database",
     );
   }
+  // Sign out the other devices, which may be the ones of an attacker
+  let session_id = token.parse_session_id()?;
+  let session_ids = CommonCollection::<UserSession>::new(db)
+    .revoke_all_of_user_except(&id, session_id.as_ref())
+    .await?;
+  revoke_access_tokens(redis, &session_ids).await?;
   custom_message(Status::Ok, "Your password is now updated")
 }
 
 pub(crate) async fn change_phone(
   db: &State<Database>,
+  sms: &State<Box<dyn SmsProvider>>,
+  token: BearerToken,
   body: Json<ChangePhoneRequest>,
 ) -> ApiResult<SuccessMessage> {
+  body.validate()?;
+  let id = token.parse_id()?;
+  let user_collection = CommonCollection::<User>::new(db);
+  let user = match user_collection.get_by_id(&id).await? {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
   let new_phone = body.new_phone.clone();
-  todo!("Change phone")
+  // The same phone can be sent again to get a new code
+  if user.phone.verified && user.phone.phone == new_phone {
+    return custom_error(Status::BadRequest, "This phone is already verified");
+  }
+  let code = generate_verification_code();
+  let code_expires_at = DateTime::from_chrono(
+    chrono::Utc::now()
+      + chrono::Duration::minutes(PHONE_CODE_DURATION_IN_MINUTES),
+  );
+  // The code is only sent if it is stored
+  let mut session = start_transaction(db.client()).await?;
+  user_collection
+    .change_phone_with_session(
+      &id,
+      &new_phone,
+      &code,
+      code_expires_at,
+      &mut session,
+    )
+    .await?;
+  sms
+    .send_phone_verification(&user, &new_phone, &code, &mut session)
+    .await?;
+  commit_transaction(&mut session).await?;
+  custom_message(
+    Status::Ok,
+    "You will receive a code by SMS to verify your phone",
+  )
+}
+
+pub(crate) async fn confirm_phone(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  body: Json<ConfirmPhoneRequest>,
+) -> ApiResult<SuccessMessage> {
+  let id = token.parse_id()?;
+  let user_collection = CommonCollection::<User>::new(db);
+  let user = match user_collection.get_by_id(&id).await? {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
+  if user.phone.verified {
+    return custom_error(Status::BadRequest, "Phone is already verified");
+  }
+  // The code is short, so the attempts are limited
+  let subject = format!("phone:{}", id);
+  if locked_for(redis, &subject).await?.is_some() {
+    return custom_error(
+      Status::TooManyRequests,
+      "Too many failed attempts, please try again later",
+    );
+  }
+  let checked = check_verification_code(
+    &user.phone.code,
+    &user.phone.code_expires_at,
+    &body.code,
+  );
+  match checked {
+    Ok(_) => {}
+    Err(CodeError::Missing) => {
+      return custom_error(
+        Status::BadRequest,
+        "No code was sent to your phone, please change it first",
+      )
+    }
+    Err(CodeError::Expired) => {
+      return custom_error(
+        Status::BadRequest,
+        "Code is expired, please ask for a new one",
+      )
+    }
+    Err(CodeError::Invalid) => {
+      record_failure(redis, &subject, &VERIFICATION_CODE_LOCKOUT).await?;
+      return custom_error(Status::BadRequest, "Code is invalid");
+    }
+  }
+  clear_failures(redis, &subject).await?;
+  user_collection.phone_confirm(&id).await?;
+  custom_message(Status::Ok, "Your phone is now verified")
 }
 
 // 2FA
@@ -178,6 +363,7 @@ pub(crate) async fn info_2fa(
     secret: totp.get_secret_base32(),
     enabled: two_factor.is_enabled(),
     qr_code: totp.get_qr_base64().unwrap(),
+    recovery_codes_left: two_factor.recovery_codes.len(),
   };
   return custom_response(Status::Ok, response);
 }
@@ -209,13 +395,18 @@ pub(crate) async fn setup_2fa(
         "2FA is already enabled for this account",
       ),
       false => {
-        // 2FA is already generated, return the secret
+        // 2FA is already generated, return the secret with new recovery
+        // codes as only their hashes are kept
         let totp = crate::utils::two_factor::from_two_factor(
           &two_factor,
           user.email.email.clone(),
         )?;
+        let (recovery_codes, recovery_code_hashes) = generate_recovery_codes();
+        user_collection
+          .two_factor_recovery_codes_update(&user_id, &recovery_code_hashes)
+          .await?;
         let response: TwoFactorSetupResponse = TwoFactorSetupResponse {
-          recovery_code: two_factor.recovery_code,
+          recovery_codes,
           qr_code: totp.get_qr_base64().unwrap(),
         };
         return custom_response(Status::Ok, response);
@@ -224,10 +415,10 @@ pub(crate) async fn setup_2fa(
   }
   // Setup the 2FA in database
   let new_two_factor = new_2fa(user.email.email.clone())?;
-  let recovery_code = generate_recovery_code();
+  let (recovery_codes, recovery_code_hashes) = generate_recovery_codes();
   user.two_factor = Some(TwoFactor {
     setup: None,
-    recovery_code: recovery_code.clone(),
+    recovery_codes: recovery_code_hashes,
     secret_base32: new_two_factor.get_secret_base32(),
   });
   let update = user_collection
@@ -241,7 +432,7 @@ pub(crate) async fn setup_2fa(
   }
   // Return the 2FA secret
   let response: TwoFactorSetupResponse = TwoFactorSetupResponse {
-    recovery_code,
+    recovery_codes,
     qr_code: new_two_factor.get_qr_base64().unwrap(),
   };
   custom_response(Status::Ok, response)
@@ -339,6 +530,198 @@ pub(crate) async fn disable_2fa(
   custom_message(Status::Ok, "Your 2FA is now disabled")
 }
 
+pub(crate) async fn regenerate_recovery_codes_2fa(
+  db: &State<Database>,
+  token: BearerToken,
+  body: Json<TwoFactorRecoveryCodesRequest>,
+) -> ApiResult<TwoFactorRecoveryCodesResponse> {
+  let user_id = token.parse_id()?;
+  let user_collection = CommonCollection::<User>::new(db);
+  let user = match user_collection.get_by_id(&user_id).await? {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
+  let valid =
+    verify_password(body.password.as_str(), user.password.password.as_str())?;
+  if !valid {
+    return custom_error(
+      Status::Unauthorized,
+      "The password provided for regenerating recovery codes is invalid",
+    );
+  }
+  // Verify 2FA is enabled, with a TOTP or only passkeys
+  if !user.requires_two_factor() {
+    return custom_error(
+      Status::BadRequest,
+      "2FA is not enabled for this account",
+    );
+  }
+  let totp_enabled = match &user.two_factor {
+    Some(two_factor) => two_factor.is_enabled(),
+    None => false,
+  };
+  // The previous codes are replaced, so they can't be used anymore
+  let (recovery_codes, recovery_code_hashes) = generate_recovery_codes();
+  let update = match totp_enabled {
+    true => {
+      user_collection
+        .two_factor_recovery_codes_update(&user_id, &recovery_code_hashes)
+        .await?
+    }
+    false => {
+      user_collection
+        .webauthn_recovery_codes_update(&user_id, &recovery_code_hashes)
+        .await?
+    }
+  };
+  if update.matched_count == 0 {
+    return custom_error(
+      Status::InternalServerError,
+      "Failed to update 2FA in database",
+    );
+  }
+  let response = TwoFactorRecoveryCodesResponse { recovery_codes };
+  custom_response(Status::Ok, response)
+}
+
+pub(crate) async fn get_webauthn_credentials(
+  db: &State<Database>,
+  token: BearerToken,
+) -> ApiResult<Vec<TwoFactorWebauthnInfoResponse>> {
+  let user_id = token.parse_id()?;
+  let user = match CommonCollection::<User>::new(db)
+    .get_by_id(&user_id)
+    .await?
+  {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
+  let response = user
+    .webauthn_credentials
+    .into_iter()
+    .map(|credential| TwoFactorWebauthnInfoResponse {
+      id: credential.id.to_hex(),
+      name: credential.name,
+      created_at: to_rfc3339(credential.created_at),
+      last_used_at: credential.last_used_at.map(to_rfc3339),
+    })
+    .collect();
+  custom_response(Status::Ok, response)
+}
+
+pub(crate) async fn webauthn_register_start(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  body: Json<TwoFactorWebauthnRegisterStartRequest>,
+) -> ApiResult<CreationChallengeResponse> {
+  body.validate()?;
+  let user_id = token.parse_id()?;
+  let user = match CommonCollection::<User>::new(db)
+    .get_by_id(&user_id)
+    .await?
+  {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
+  let valid_password =
+    verify_password(body.password.as_str(), user.password.password.as_str())?;
+  if !valid_password {
+    return custom_error(
+      Status::Unauthorized,
+      "The password provided for adding a passkey is invalid",
+    );
+  }
+  let challenge = start_registration(redis, &user, body.name.clone()).await?;
+  custom_response(Status::Ok, challenge)
+}
+
+pub(crate) async fn webauthn_register_finish(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  body: Json<TwoFactorWebauthnRegisterFinishRequest>,
+) -> ApiResult<TwoFactorWebauthnRegisterFinishResponse> {
+  let user_id = token.parse_id()?;
+  let user_collection = CommonCollection::<User>::new(db);
+  let user = match user_collection.get_by_id(&user_id).await? {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
+  let credential = finish_registration(redis, &user, &body.credential).await?;
+  user_collection
+    .webauthn_credential_add(&user_id, &credential)
+    .await?;
+  // The first second factor comes with recovery codes, in case every
+  // passkey is lost
+  let recovery_codes = match user.requires_two_factor() {
+    true => None,
+    false => {
+      let (recovery_codes, recovery_code_hashes) = generate_recovery_codes();
+      user_collection
+        .webauthn_recovery_codes_update(&user_id, &recovery_code_hashes)
+        .await?;
+      Some(recovery_codes)
+    }
+  };
+  let response = TwoFactorWebauthnRegisterFinishResponse { recovery_codes };
+  custom_response(Status::Created, response)
+}
+
+pub(crate) async fn webauthn_rename(
+  db: &State<Database>,
+  token: BearerToken,
+  credential_id: &str,
+  body: Json<TwoFactorWebauthnRenameRequest>,
+) -> ApiResult<SuccessMessage> {
+  body.validate()?;
+  let user_id = token.parse_id()?;
+  let credential_id = ObjectId::from_str(credential_id)?;
+  let result = CommonCollection::<User>::new(db)
+    .webauthn_credential_rename(&user_id, &credential_id, &body.name)
+    .await?;
+  if result.matched_count == 0 {
+    return custom_error(Status::NotFound, "Passkey not found");
+  }
+  custom_message(Status::Ok, "Your passkey is now renamed")
+}
+
+pub(crate) async fn webauthn_delete(
+  db: &State<Database>,
+  token: BearerToken,
+  credential_id: &str,
+  body: Json<TwoFactorWebauthnDeleteRequest>,
+) -> ApiResult<SuccessMessage> {
+  let user_id = token.parse_id()?;
+  let credential_id = ObjectId::from_str(credential_id)?;
+  let user_collection = CommonCollection::<User>::new(db);
+  let user = match user_collection.get_by_id(&user_id).await? {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
+  let valid_password =
+    verify_password(body.password.as_str(), user.password.password.as_str())?;
+  if !valid_password {
+    return custom_error(
+      Status::Unauthorized,
+      "The password provided for deleting a passkey is invalid",
+    );
+  }
+  let result = user_collection
+    .webauthn_credential_delete(&user_id, &credential_id)
+    .await?;
+  if result.modified_count == 0 {
+    return custom_error(Status::NotFound, "Passkey not found");
+  }
+  // The recovery codes of the passkeys go with the last of them
+  if user.webauthn_credentials.len() == 1 {
+    user_collection
+      .webauthn_recovery_codes_update(&user_id, &Vec::new())
+      .await?;
+  }
+  custom_message(Status::Ok, "Your passkey is now deleted")
+}
+
 pub(crate) async fn upload_profile_picture(
   db: &State<Database>,
   content_type: &ContentType,
@@ -375,3 +758,182 @@ pub(crate) async fn upload_profile_picture(
     .await?;
   custom_message(Status::Ok, "Your profile picture is now updated")
 }
+
+pub(crate) async fn get_sessions(
+  db: &State<Database>,
+  token: BearerToken,
+) -> ApiResult<Vec<SessionInfoResponse>> {
+  let user_id = token.parse_id()?;
+  let current_session_id = token.parse_session_id()?;
+  let sessions = CommonCollection::<UserSession>::new(db)
+    .get_active_of_user(&user_id)
+    .await?;
+  let response = sessions
+    .into_iter()
+    .map(|session| SessionInfoResponse {
+      id: session.id.to_string(),
+      user_agent: session.user_agent,
+      ip: session.ip,
+      created_at: to_rfc3339(session.created_at),
+      last_used_at: to_rfc3339(session.last_used_at),
+      expires_at: to_rfc3339(session.expires_at),
+      current: Some(session.id) == current_session_id,
+    })
+    .collect();
+  custom_response(Status::Ok, response)
+}
+
+pub(crate) async fn revoke_session(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  session_id: &str,
+) -> ApiResult<SuccessMessage> {
+  let user_id = token.parse_id()?;
+  let session_id = ObjectId::from_str(session_id)?;
+  let result = CommonCollection::<UserSession>::new(db)
+    .revoke_of_user(&user_id, &session_id)
+    .await?;
+  if result.modified_count == 0 {
+    return custom_error(Status::NotFound, "Session not found");
+  }
+  revoke_access_tokens(redis, &vec![session_id]).await?;
+  custom_message(Status::Ok, "The session was revoked")
+}
+
+pub(crate) async fn revoke_all_sessions(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+) -> ApiResult<SuccessMessage> {
+  let user_id = token.parse_id()?;
+  let session_ids = CommonCollection::<UserSession>::new(db)
+    .revoke_all_of_user(&user_id)
+    .await?;
+  revoke_access_tokens(redis, &session_ids).await?;
+  custom_message(Status::Ok, "All your sessions were revoked")
+}
+
+pub(crate) async fn get_oauth_providers(
+  db: &State<Database>,
+  token: BearerToken,
+) -> ApiResult<Vec<OAuthProviderInfoResponse>> {
+  let user_id = token.parse_id()?;
+  let user = match CommonCollection::<User>::new(db)
+    .get_by_id(&user_id)
+    .await?
+  {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
+  let response = user
+    .oauth_providers
+    .into_iter()
+    .map(|oauth_provider| OAuthProviderInfoResponse {
+      provider: oauth_provider.provider.into(),
+      email: oauth_provider.email,
+      linked_at: to_rfc3339(oauth_provider.linked_at),
+    })
+    .collect();
+  custom_response(Status::Ok, response)
+}
+
+pub(crate) async fn oauth_authorize(
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  provider: &str,
+) -> ApiResult<OAuthAuthorizeResponse> {
+  let user_id = token.parse_id()?;
+  let service = OAuthService::from_str(provider)?;
+  let authorization_url =
+    OAuth::authorize(redis, service, Some(user_id)).await?;
+  let response = OAuthAuthorizeResponse { authorization_url };
+  custom_response(Status::Ok, response)
+}
+
+pub(crate) async fn oauth_link(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  provider: &str,
+  body: Json<OAuthCallbackRequest>,
+) -> ApiResult<SuccessMessage> {
+  let user_id = token.parse_id()?;
+  let body = body.into_inner();
+  let service = OAuthService::from_str(provider)?;
+  let (oauth_user, link_user_id) =
+    OAuth::callback(redis, service.clone(), body.code, body.state).await?;
+  if link_user_id != Some(user_id) {
+    return custom_error(
+      Status::BadRequest,
+      "This authorization was not started to link your account",
+    );
+  }
+  let provider: OAuthProvider = service.into();
+  let user_collection = CommonCollection::<User>::new(db);
+  if let Some(user) = user_collection
+    .find_with_oauth_provider(provider, &oauth_user.id)
+    .await?
+  {
+    if user.id != user_id {
+      return custom_error(
+        Status::Conflict,
+        "This provider account is already linked to another user",
+      );
+    }
+    return custom_error(
+      Status::Conflict,
+      "This provider account is already linked to your account",
+    );
+  }
+  let oauth_provider =
+    UserOAuthProvider::new(provider, oauth_user.id, oauth_user.email);
+  let update = user_collection
+    .oauth_provider_link(&user_id, &oauth_provider)
+    .await?;
+  if update.modified_count == 0 {
+    return custom_error(
+      Status::Conflict,
+      "Another account of this provider is linked, please unlink it before",
+    );
+  }
+  custom_message(Status::Ok, "The provider was linked to your account")
+}
+
+pub(crate) async fn oauth_unlink(
+  db: &State<Database>,
+  token: BearerToken,
+  provider: &str,
+) -> ApiResult<SuccessMessage> {
+  let user_id = token.parse_id()?;
+  let provider: OAuthProvider = OAuthService::from_str(provider)?.into();
+  let user_collection = CommonCollection::<User>::new(db);
+  let user = match user_collection.get_by_id(&user_id).await? {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
+  if user.get_oauth_provider(provider).is_none() {
+    return custom_error(
+      Status::NotFound,
+      "This provider is not linked to your account",
+    );
+  }
+  // Keep a way to login
+  if user.password.generated && user.oauth_providers.len() == 1 {
+    return custom_error(
+      Status::BadRequest,
+      "This provider is your only way to login, please set a password \
+       before unlinking it",
+    );
+  }
+  user_collection
+    .oauth_provider_unlink(&user_id, provider)
+    .await?;
+  custom_message(Status::Ok, "The provider was unlinked from your account")
+}
+
+fn to_rfc3339(date: bson::DateTime) -> String {
+  date
+    .to_chrono()
+    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
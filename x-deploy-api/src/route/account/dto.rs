@@ -1,6 +1,10 @@
+use crate::route::auth::dto::OAuthServiceType;
+use crate::utils::phone::validate_e164_phone;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use utoipa::ToSchema;
+use validator::Validate;
+use webauthn_rs::prelude::RegisterPublicKeyCredential;
 
 #[derive(Deserialize, Serialize, Debug, ToSchema)]
 #[schema(example = json!({
@@ -46,15 +50,28 @@ pub(crate) struct VerifyEmailRequest {
   pub(crate) code: String,
 }
 
-#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
 #[schema(example = json!({
     "newPhone": "+34612345678",
 }))]
 pub(crate) struct ChangePhoneRequest {
+  #[validate(custom(
+    function = "validate_e164_phone",
+    message = "Your phone must be in the E.164 format, e.g. +34612345678"
+  ))]
   #[serde(rename = "newPhone")]
   pub(crate) new_phone: String,
 }
 
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[schema(example = json!({
+  "code": "768905"
+}))]
+pub(crate) struct ConfirmPhoneRequest {
+  #[serde(rename = "code")]
+  pub(crate) code: String,
+}
+
 // =======================
 // 2FA
 // =======================
@@ -72,12 +89,13 @@ pub(crate) struct TwoFactorSetupRequest {
 
 #[derive(Deserialize, Serialize, Debug, ToSchema)]
 #[schema(example = json!({
-    "secret": "BA766BJBGJU...",
+    "recoveryCodes": ["K7P2D-9XQ4M", "TB3ZR-HW8NA"],
     "qrCode": "Ipofsjbkl9875UJIKkfds..."
 }))]
 pub(crate) struct TwoFactorSetupResponse {
-  #[serde(rename = "recoveryCode")]
-  pub(crate) recovery_code: String,
+  /// Shown only once, each code can be used one time instead of a code
+  #[serde(rename = "recoveryCodes")]
+  pub(crate) recovery_codes: Vec<String>,
 
   #[serde(rename = "qrCode")]
   pub(crate) qr_code: String,
@@ -99,6 +117,7 @@ pub(crate) struct TwoFactorInfoRequest {
     "enabled": true,
     "secret": "BA766BJBGJU...",
     "qrCode": "Ipofsjbkl9875UJIKkfds...",
+    "recoveryCodesLeft": 8,
 }))]
 pub(crate) struct TwoFactorInfoResponse {
   #[serde(rename = "enabled")]
@@ -109,6 +128,30 @@ pub(crate) struct TwoFactorInfoResponse {
 
   #[serde(rename = "qrCode")]
   pub(crate) qr_code: String,
+
+  #[serde(rename = "recoveryCodesLeft")]
+  pub(crate) recovery_codes_left: usize,
+}
+
+// => Recovery codes
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[schema(example = json!({
+    "password": "MyActualStrongPassword123!",
+}))]
+pub(crate) struct TwoFactorRecoveryCodesRequest {
+  #[serde(rename = "password")]
+  pub(crate) password: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[schema(example = json!({
+    "recoveryCodes": ["K7P2D-9XQ4M", "TB3ZR-HW8NA"],
+}))]
+pub(crate) struct TwoFactorRecoveryCodesResponse {
+  /// Shown only once, the previous codes are no longer valid
+  #[serde(rename = "recoveryCodes")]
+  pub(crate) recovery_codes: Vec<String>,
 }
 
 // => Code
@@ -121,3 +164,114 @@ pub(crate) struct TwoFactorCodeRequest {
   #[serde(rename = "code")]
   pub(crate) code: String,
 }
+
+// => WebAuthn
+
+#[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
+#[schema(example = json!({
+    "password": "MyActualStrongPassword123!",
+    "name": "My security key",
+}))]
+pub(crate) struct TwoFactorWebauthnRegisterStartRequest {
+  #[serde(rename = "password")]
+  pub(crate) password: String,
+
+  #[serde(rename = "name")]
+  #[validate(length(min = 1, max = 64))]
+  pub(crate) name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+pub(crate) struct TwoFactorWebauthnRegisterFinishRequest {
+  /// The answer of the authenticator to the registration challenge
+  #[serde(rename = "credential")]
+  #[schema(value_type = Object)]
+  pub(crate) credential: RegisterPublicKeyCredential,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[schema(example = json!({
+    "recoveryCodes": ["K7P2D-9XQ4M", "TB3ZR-HW8NA"],
+}))]
+pub(crate) struct TwoFactorWebauthnRegisterFinishResponse {
+  /// Only given with the first second factor of the account, shown once
+  #[serde(rename = "recoveryCodes")]
+  pub(crate) recovery_codes: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
+#[schema(example = json!({
+    "name": "My security key",
+}))]
+pub(crate) struct TwoFactorWebauthnRenameRequest {
+  #[serde(rename = "name")]
+  #[validate(length(min = 1, max = 64))]
+  pub(crate) name: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[schema(example = json!({
+    "password": "MyActualStrongPassword123!",
+}))]
+pub(crate) struct TwoFactorWebauthnDeleteRequest {
+  #[serde(rename = "password")]
+  pub(crate) password: String,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[schema(example = json!({
+    "id": "65a1b2c3d4e5f6a7b8c9d0e1",
+    "name": "My security key",
+    "createdAt": "2024-01-01T00:00:00Z",
+    "lastUsedAt": "2024-01-02T00:00:00Z",
+}))]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TwoFactorWebauthnInfoResponse {
+  pub(crate) id: String,
+  pub(crate) name: String,
+  pub(crate) created_at: String,
+  pub(crate) last_used_at: Option<String>,
+}
+
+// =======================
+// Sessions
+// =======================
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[schema(example = json!({
+    "id": "65a1b2c3d4e5f6a7b8c9d0e1",
+    "userAgent": "Mozilla/5.0 (X11; Linux x86_64)...",
+    "ip": "203.0.113.42",
+    "createdAt": "2024-01-01T00:00:00Z",
+    "lastUsedAt": "2024-01-02T00:00:00Z",
+    "expiresAt": "2024-01-31T00:00:00Z",
+    "current": true,
+}))]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SessionInfoResponse {
+  pub(crate) id: String,
+  pub(crate) user_agent: Option<String>,
+  pub(crate) ip: Option<String>,
+  pub(crate) created_at: String,
+  pub(crate) last_used_at: String,
+  pub(crate) expires_at: String,
+  /// Whether the session is the one of the request
+  pub(crate) current: bool,
+}
+
+// =======================
+// OAuth
+// =======================
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[schema(example = json!({
+    "provider": "GITHUB",
+    "email": "john@doe.net",
+    "linkedAt": "2024-01-01T00:00:00Z",
+}))]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OAuthProviderInfoResponse {
+  pub(crate) provider: OAuthServiceType,
+  pub(crate) email: String,
+  pub(crate) linked_at: String,
+}
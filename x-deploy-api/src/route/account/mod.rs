@@ -1,15 +1,23 @@
 use crate::guard::bearer_token::BearerToken;
 use crate::route::account::dto::{
-  ChangePasswordRequest, GetAccountInfoResponse, TwoFactorCodeRequest,
-  TwoFactorInfoRequest, TwoFactorInfoResponse, TwoFactorSetupRequest,
-  TwoFactorSetupResponse,
+  ChangePasswordRequest, GetAccountInfoResponse, OAuthProviderInfoResponse,
+  SessionInfoResponse, TwoFactorCodeRequest, TwoFactorInfoRequest,
+  TwoFactorInfoResponse, TwoFactorRecoveryCodesRequest,
+  TwoFactorRecoveryCodesResponse, TwoFactorSetupRequest,
+  TwoFactorSetupResponse, TwoFactorWebauthnDeleteRequest,
+  TwoFactorWebauthnInfoResponse, TwoFactorWebauthnRegisterFinishRequest,
+  TwoFactorWebauthnRegisterFinishResponse,
+  TwoFactorWebauthnRegisterStartRequest, TwoFactorWebauthnRenameRequest,
 };
+use crate::route::auth::dto::{OAuthAuthorizeResponse, OAuthCallbackRequest};
 use crate::route::{ApiResult, SuccessMessage};
+use crate::utils::sms::SmsProvider;
 use bson::doc;
 use mongodb::Database;
 use rocket::http::ContentType;
 use rocket::serde::json::Json;
 use rocket::{Data, State};
+use webauthn_rs::prelude::CreationChallengeResponse;
 
 mod controller;
 pub(crate) mod dto;
@@ -50,10 +58,32 @@ pub(crate) async fn get_info(
 #[post("/account/verify-email", format = "application/json", data = "<body>")]
 pub(crate) async fn verify_email(
   db: &State<Database>,
+  redis: &State<redis::Client>,
   token: BearerToken,
   body: Json<dto::VerifyEmailRequest>,
 ) -> ApiResult<SuccessMessage> {
-  return controller::verify_email(db, token, body).await;
+  return controller::verify_email(db, redis, token, body).await;
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "Resend Account Email Verification",
+    path = "/account/verify-email/resend",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "A new code was sent", body = SuccessMessage),
+    ),
+)]
+#[post("/account/verify-email/resend")]
+pub(crate) async fn resend_email_verification(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+) -> ApiResult<SuccessMessage> {
+  return controller::resend_email_verification(db, redis, token).await;
 }
 
 #[deprecated]
@@ -77,13 +107,13 @@ pub(crate) async fn verify_email(
 )]
 pub(crate) async fn change_password(
   db: &State<Database>,
+  redis: &State<redis::Client>,
   token: BearerToken,
   body: Json<ChangePasswordRequest>,
 ) -> ApiResult<SuccessMessage> {
-  return controller::change_password(db, token, body).await;
+  return controller::change_password(db, redis, token, body).await;
 }
 
-#[deprecated]
 #[utoipa::path(
     post,
     operation_id = "Change Phone",
@@ -93,16 +123,41 @@ pub(crate) async fn change_password(
       ("bearer" = []),
     ),
     responses(
-        (status = 200, description = "Change phone", body = SuccessMessage),
+        (status = 200, description = "A code was sent to verify the phone", body = SuccessMessage),
     ),
     request_body = ChangePhoneRequest,
 )]
 #[post("/account/change-phone", format = "application/json", data = "<body>")]
 pub(crate) async fn change_phone(
   db: &State<Database>,
+  sms: &State<Box<dyn SmsProvider>>,
+  token: BearerToken,
   body: Json<dto::ChangePhoneRequest>,
 ) -> ApiResult<SuccessMessage> {
-  return controller::change_phone(db, body).await;
+  return controller::change_phone(db, sms, token, body).await;
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "Confirm Phone",
+    path = "/account/phone/confirm",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "The phone is verified", body = SuccessMessage),
+    ),
+    request_body = ConfirmPhoneRequest,
+)]
+#[post("/account/phone/confirm", format = "application/json", data = "<body>")]
+pub(crate) async fn confirm_phone(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  body: Json<dto::ConfirmPhoneRequest>,
+) -> ApiResult<SuccessMessage> {
+  return controller::confirm_phone(db, redis, token, body).await;
 }
 
 // 2FA
@@ -199,6 +254,162 @@ pub(crate) async fn disable_2fa(
   return controller::disable_2fa(db, token, body).await;
 }
 
+#[utoipa::path(
+    post,
+    operation_id = "Regenerate 2FA Recovery Codes",
+    path = "/account/2fa/recovery-codes",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "Your new recovery codes", body = TwoFactorRecoveryCodesResponse),
+    ),
+    request_body = TwoFactorRecoveryCodesRequest,
+)]
+#[post(
+  "/account/2fa/recovery-codes",
+  format = "application/json",
+  data = "<body>"
+)]
+pub(crate) async fn regenerate_recovery_codes_2fa(
+  db: &State<Database>,
+  token: BearerToken,
+  body: Json<TwoFactorRecoveryCodesRequest>,
+) -> ApiResult<TwoFactorRecoveryCodesResponse> {
+  return controller::regenerate_recovery_codes_2fa(db, token, body).await;
+}
+
+// 2FA WebAuthn
+
+#[utoipa::path(
+    get,
+    operation_id = "Get 2FA Passkeys",
+    path = "/account/2fa/webauthn",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "The passkeys of your account", body = Vec<TwoFactorWebauthnInfoResponse>),
+    ),
+)]
+#[get("/account/2fa/webauthn", format = "application/json")]
+pub(crate) async fn get_webauthn_credentials(
+  db: &State<Database>,
+  token: BearerToken,
+) -> ApiResult<Vec<TwoFactorWebauthnInfoResponse>> {
+  return controller::get_webauthn_credentials(db, token).await;
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "Start 2FA Passkey Registration",
+    path = "/account/2fa/webauthn/register/start",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "The challenge to answer with your authenticator"),
+    ),
+    request_body = TwoFactorWebauthnRegisterStartRequest,
+)]
+#[post(
+  "/account/2fa/webauthn/register/start",
+  format = "application/json",
+  data = "<body>"
+)]
+pub(crate) async fn webauthn_register_start(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  body: Json<TwoFactorWebauthnRegisterStartRequest>,
+) -> ApiResult<CreationChallengeResponse> {
+  return controller::webauthn_register_start(db, redis, token, body).await;
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "Finish 2FA Passkey Registration",
+    path = "/account/2fa/webauthn/register/finish",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 201, description = "The passkey was registered, with recovery codes when it is the first second factor", body = TwoFactorWebauthnRegisterFinishResponse),
+    ),
+    request_body = TwoFactorWebauthnRegisterFinishRequest,
+)]
+#[post(
+  "/account/2fa/webauthn/register/finish",
+  format = "application/json",
+  data = "<body>"
+)]
+pub(crate) async fn webauthn_register_finish(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  body: Json<TwoFactorWebauthnRegisterFinishRequest>,
+) -> ApiResult<TwoFactorWebauthnRegisterFinishResponse> {
+  return controller::webauthn_register_finish(db, redis, token, body).await;
+}
+
+#[utoipa::path(
+    put,
+    operation_id = "Rename 2FA Passkey",
+    path = "/account/2fa/webauthn/<credential_id>",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "The passkey was renamed", body = SuccessMessage),
+    ),
+    request_body = TwoFactorWebauthnRenameRequest,
+)]
+#[put(
+  "/account/2fa/webauthn/<credential_id>",
+  format = "application/json",
+  data = "<body>"
+)]
+pub(crate) async fn webauthn_rename(
+  db: &State<Database>,
+  token: BearerToken,
+  credential_id: &str,
+  body: Json<TwoFactorWebauthnRenameRequest>,
+) -> ApiResult<SuccessMessage> {
+  return controller::webauthn_rename(db, token, credential_id, body).await;
+}
+
+#[utoipa::path(
+    delete,
+    operation_id = "Delete 2FA Passkey",
+    path = "/account/2fa/webauthn/<credential_id>",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "The passkey was deleted", body = SuccessMessage),
+    ),
+    request_body = TwoFactorWebauthnDeleteRequest,
+)]
+#[delete(
+  "/account/2fa/webauthn/<credential_id>",
+  format = "application/json",
+  data = "<body>"
+)]
+pub(crate) async fn webauthn_delete(
+  db: &State<Database>,
+  token: BearerToken,
+  credential_id: &str,
+  body: Json<TwoFactorWebauthnDeleteRequest>,
+) -> ApiResult<SuccessMessage> {
+  return controller::webauthn_delete(db, token, credential_id, body).await;
+}
+
 #[utoipa::path(
     post,
     operation_id = "Upload Profile Picture",
@@ -222,3 +433,160 @@ pub(crate) async fn upload_profile_picture(
   return controller::upload_profile_picture(db, content_type, token, data)
     .await;
 }
+
+// Sessions
+
+#[utoipa::path(
+    get,
+    operation_id = "Get Account Sessions",
+    path = "/account/sessions",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "Your active sessions", body = Vec<SessionInfoResponse>),
+    ),
+)]
+#[get("/account/sessions", format = "application/json")]
+pub(crate) async fn get_sessions(
+  db: &State<Database>,
+  token: BearerToken,
+) -> ApiResult<Vec<SessionInfoResponse>> {
+  return controller::get_sessions(db, token).await;
+}
+
+#[utoipa::path(
+    delete,
+    operation_id = "Revoke Account Session",
+    path = "/account/sessions/<session_id>",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "The session was revoked", body = SuccessMessage),
+    ),
+)]
+#[delete("/account/sessions/<session_id>", format = "application/json")]
+pub(crate) async fn revoke_session(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  session_id: &str,
+) -> ApiResult<SuccessMessage> {
+  return controller::revoke_session(db, redis, token, session_id).await;
+}
+
+#[utoipa::path(
+    delete,
+    operation_id = "Revoke All Account Sessions",
+    path = "/account/sessions",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "All your sessions were revoked", body = SuccessMessage),
+    ),
+)]
+#[delete("/account/sessions", format = "application/json")]
+pub(crate) async fn revoke_all_sessions(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+) -> ApiResult<SuccessMessage> {
+  return controller::revoke_all_sessions(db, redis, token).await;
+}
+
+// OAuth
+
+#[utoipa::path(
+    get,
+    operation_id = "Get Account OAuth Providers",
+    path = "/account/oauth",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "The providers linked to your account", body = Vec<OAuthProviderInfoResponse>),
+    ),
+)]
+#[get("/account/oauth", format = "application/json")]
+pub(crate) async fn get_oauth_providers(
+  db: &State<Database>,
+  token: BearerToken,
+) -> ApiResult<Vec<OAuthProviderInfoResponse>> {
+  return controller::get_oauth_providers(db, token).await;
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "Authorize Account OAuth Provider",
+    path = "/account/oauth/<provider>/authorize",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "The page of the provider to redirect to", body = OAuthAuthorizeResponse),
+    ),
+)]
+#[post("/account/oauth/<provider>/authorize")]
+pub(crate) async fn oauth_authorize(
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  provider: &str,
+) -> ApiResult<OAuthAuthorizeResponse> {
+  return controller::oauth_authorize(redis, token, provider).await;
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "Link Account OAuth Provider",
+    path = "/account/oauth/<provider>/link",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "The provider was linked", body = SuccessMessage),
+    ),
+    request_body = OAuthCallbackRequest,
+)]
+#[post(
+  "/account/oauth/<provider>/link",
+  format = "application/json",
+  data = "<body>"
+)]
+pub(crate) async fn oauth_link(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  token: BearerToken,
+  provider: &str,
+  body: Json<OAuthCallbackRequest>,
+) -> ApiResult<SuccessMessage> {
+  return controller::oauth_link(db, redis, token, provider, body).await;
+}
+
+#[utoipa::path(
+    delete,
+    operation_id = "Unlink Account OAuth Provider",
+    path = "/account/oauth/<provider>",
+    tag = "Account",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "The provider was unlinked", body = SuccessMessage),
+    ),
+)]
+#[delete("/account/oauth/<provider>", format = "application/json")]
+pub(crate) async fn oauth_unlink(
+  db: &State<Database>,
+  token: BearerToken,
+  provider: &str,
+) -> ApiResult<SuccessMessage> {
+  return controller::oauth_unlink(db, token, provider).await;
+}
@@ -1,97 +1,299 @@
+use crate::error::ApiError;
 use crate::guard::bearer_token::BearerToken;
+use crate::guard::client_info::ClientInfo;
 use crate::oauth::{OAuth, OAuthService, OAuthUser};
 use crate::route::auth::dto::{
-  ForgotPasswordRequest, LoginOAuthRequest, LoginRequest, LoginResponse,
-  MagicLinkRequest, RegisterRequest, ResetPasswordRequest,
-  TwoFactorCodeRequest, TwoFactorRecoveryRequest,
+  ForgotPasswordRequest, LoginRequest, LoginResponse, MagicLinkLoginRequest,
+  MagicLinkRequest, OAuthAuthorizeResponse, OAuthCallbackRequest,
+  RefreshTokenRequest, RegisterRequest, ResetPasswordRequest,
+  TwoFactorCodeRequest, TwoFactorRecoveryRequest, TwoFactorWebauthnRequest,
 };
 use crate::route::{
   custom_error, custom_message, custom_response, ApiResult, SuccessMessage,
 };
 use crate::utils::password::{
   generate_forgot_password_token, hash_password, is_strong_password,
-  verify_password,
+  verify_dummy_password, verify_password,
+};
+use crate::utils::session::{
+  new_magic_link_token, new_refresh_token, revoke_access_tokens,
 };
 use crate::utils::two_factor::verify_2fa_code;
+use crate::utils::verification::send_email_verification;
+use crate::utils::webauthn::{finish_authentication, start_authentication};
 use crate::CONFIG;
+use bson::oid::ObjectId;
+use bson::DateTime;
 use mongodb::Database;
 use rocket::http::Status;
 use rocket::serde::json::Json;
-use rocket::{tokio, State};
+use rocket::State;
 use std::str::FromStr;
 use validator::Validate;
-use x_deploy_common::db::user::User;
-use x_deploy_common::db::CommonCollection;
+use webauthn_rs::prelude::RequestChallengeResponse;
+use x_deploy_common::cache::lockout::{
+  clear_failures, locked_for, record_failure, LockoutPolicy,
+};
+use x_deploy_common::cache::magic_link::{save_magic_link, take_magic_link};
+use x_deploy_common::cache::rate_limit::hit;
+use x_deploy_common::db::event_outbox::EventOutbox;
+use x_deploy_common::db::user::{
+  OAuthProvider, TwoFactor, User, UserOAuthProvider,
+};
+use x_deploy_common::db::user_session::UserSession;
+use x_deploy_common::db::{
+  commit_transaction, start_transaction, CommonCollection,
+};
 use x_deploy_common::event::user::{
-  UserForgotPasswordEvent, UserMagicLinkEvent, UserPasswordResetEvent,
-  UserRegisteredEvent,
+  UserAccountExistsEvent, UserForgotPasswordEvent, UserMagicLinkEvent,
+  UserPasswordResetEvent, UserRegisteredEvent,
+};
+
+// Logins of an email are locked after 5 failures, for 1 minute doubled on
+// every new failure up to 1 hour
+const LOGIN_LOCKOUT: LockoutPolicy = LockoutPolicy {
+  max_failures: 5,
+  failure_window_in_seconds: 3600,
+  lock_in_seconds: 60,
+  max_lock_in_seconds: 3600,
+};
+
+const TWO_FACTOR_LOCKOUT: LockoutPolicy = LockoutPolicy {
+  max_failures: 5,
+  failure_window_in_seconds: 3600,
+  lock_in_seconds: 60,
+  max_lock_in_seconds: 3600,
 };
-use x_deploy_common::event::CommonEvent;
+
+// Emails sent to the same address, e.g. magic links, by window
+const EMAIL_SEND_LIMIT: u64 = 3;
+const EMAIL_SEND_WINDOW_IN_SECONDS: u64 = 900;
+
+// The responses don't tell whether an account exists for the email
+const MAGIC_LINK_SENT_MESSAGE: &str =
+  "If an account exists for this email, you will receive a magic link";
+const FORGOT_PASSWORD_SENT_MESSAGE: &str =
+  "If an account exists for this email, you will receive a link to reset \
+   your password";
+
+fn login_lockout_subject(email: &String) -> String {
+  format!("login:{}", email.to_lowercase())
+}
+
+fn two_factor_lockout_subject(user_id: &ObjectId) -> String {
+  format!("2fa:{}", user_id)
+}
+
+/// Refuses the attempt while the subject is locked out.
+async fn check_lockout(
+  redis: &redis::Client,
+  subject: &String,
+) -> Result<(), ApiError> {
+  return match locked_for(redis, subject).await? {
+    Some(_) => Err(ApiError::new(
+      Status::TooManyRequests,
+      "Too many failed attempts, please try again later".to_string(),
+    )),
+    None => Ok(()),
+  };
+}
+
+/// Whether another email of this kind can be sent to the address, so that
+/// it can't be flooded.
+async fn can_send_email(
+  redis: &redis::Client,
+  kind: &str,
+  email: &String,
+) -> Result<bool, ApiError> {
+  let key = format!("email:{}:{}", kind, email.to_lowercase());
+  let hit = hit(redis, &key, EMAIL_SEND_WINDOW_IN_SECONDS).await?;
+  Ok(hit.count <= EMAIL_SEND_LIMIT)
+}
+
+/// Opens a session for the user and returns its first tokens.
+async fn start_session(
+  db: &State<Database>,
+  user_id: &ObjectId,
+  otp: Option<bool>,
+  client: &ClientInfo,
+) -> ApiResult<LoginResponse> {
+  let duration_days = CONFIG.refresh_token_duration_in_days as i64;
+  let expires_at = chrono::Utc::now() + chrono::Duration::days(duration_days);
+  let refresh_token = new_refresh_token();
+  let session = UserSession::new(
+    user_id.clone(),
+    &refresh_token,
+    client.user_agent.clone(),
+    client.ip.clone(),
+    DateTime::from_chrono(expires_at),
+  );
+  CommonCollection::<UserSession>::new(db)
+    .insert_one(&session)
+    .await?;
+  let mut token = BearerToken::new(user_id.clone(), otp)?;
+  token.with_session(session.id);
+  let response = LoginResponse {
+    token: token.to_jwt()?,
+    refresh_token: Some(refresh_token),
+  };
+  custom_response(Status::Ok, response)
+}
+
+/// Returns a token only usable to send the 2FA code, the session is
+/// opened once the code is verified.
+fn two_factor_pending(user_id: &ObjectId) -> ApiResult<LoginResponse> {
+  let token = BearerToken::new(user_id.clone(), Some(false))?;
+  let response = LoginResponse {
+    token: token.to_jwt()?,
+    refresh_token: None,
+  };
+  custom_response(Status::Ok, response)
+}
 
 pub(crate) async fn login(
   db: &State<Database>,
+  redis: &State<redis::Client>,
+  client: ClientInfo,
   body: Json<LoginRequest>,
 ) -> ApiResult<LoginResponse> {
   body.validate()?;
-  tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-  // Verify if email exists for an user
+  let subject = login_lockout_subject(&body.email);
+  check_lockout(redis, &subject).await?;
+  // An unknown email and a wrong password get the same response
   let user_collection = CommonCollection::<User>::new(db);
-  let user = match user_collection.find_with_email(&body.email).await? {
-    Some(user) => user,
-    None => {
-      return custom_error(Status::NotFound, "User not found with this email")
+  let user = user_collection.find_with_email(&body.email).await?;
+  let valid_password = match &user {
+    Some(user) => {
+      verify_password(&body.password, user.password.password.as_str())?
     }
+    None => verify_dummy_password(&body.password)?,
   };
-  // Verify if password is correct
-  let valid_password =
-    verify_password(&body.password, user.password.password.as_str())?;
-  if !valid_password {
-    return custom_error(
-      Status::Unauthorized,
-      "Email or password is incorrect for this email",
-    );
-  }
-  let two_factor: Option<bool> = if let None = user.two_factor.clone() {
-    None
-  } else {
-    Some(false)
+  let user = match user {
+    Some(user) if valid_password => user,
+    _ => {
+      record_failure(redis, &subject, &LOGIN_LOCKOUT).await?;
+      return custom_error(
+        Status::Unauthorized,
+        "Email or password is incorrect",
+      );
+    }
   };
-  let token = BearerToken::new(user.id.clone(), two_factor)?;
-  let jwt = token.to_jwt()?;
-  let response = LoginResponse { token: jwt };
+  clear_failures(redis, &subject).await?;
+  if user.requires_two_factor() {
+    return two_factor_pending(&user.id);
+  }
+  start_session(db, &user.id, None, &client).await
+}
+
+pub(crate) async fn oauth_authorize(
+  redis: &State<redis::Client>,
+  provider: &str,
+) -> ApiResult<OAuthAuthorizeResponse> {
+  let service = OAuthService::from_str(provider)?;
+  let authorization_url = OAuth::authorize(redis, service, None).await?;
+  let response = OAuthAuthorizeResponse { authorization_url };
   custom_response(Status::Ok, response)
 }
 
-pub(crate) async fn login_oauth(
+pub(crate) async fn oauth_callback(
   db: &State<Database>,
-  body: Json<LoginOAuthRequest>,
+  redis: &State<redis::Client>,
+  client: ClientInfo,
+  provider: &str,
+  body: Json<OAuthCallbackRequest>,
 ) -> ApiResult<LoginResponse> {
+  body.validate()?;
   let body = body.into_inner();
-  let service: OAuthService = body.service.into();
-  let result: OAuthUser = OAuth::get_user(service, body.access_token).await?;
-  let collection = CommonCollection::<User>::new(db);
-  let user = match collection.find_with_email(&result.email).await? {
+  let service = OAuthService::from_str(provider)?;
+  let (oauth_user, link_user_id) =
+    OAuth::callback(redis, service.clone(), body.code, body.state).await?;
+  if link_user_id.is_some() {
+    return custom_error(
+      Status::BadRequest,
+      "This authorization was started to link a provider to your account",
+    );
+  }
+  let provider: OAuthProvider = service.into();
+  let user_collection = CommonCollection::<User>::new(db);
+  let user = match user_collection
+    .find_with_oauth_provider(provider, &oauth_user.id)
+    .await?
+  {
     Some(user) => user,
-    None => {
-      return custom_error(
-        Status::NotFound,
-        "User not found with this email, please register before",
-      )
-    }
-  };
-  let two_factor: Option<bool> = if let None = user.two_factor.clone() {
-    None
-  } else {
-    Some(false)
+    None => match user_collection.find_with_email(&oauth_user.email).await? {
+      Some(user) => {
+        // The provider verified the email, but the account may have been
+        // registered by someone else if it never verified it
+        if !user.email.verified {
+          return custom_error(
+            Status::Conflict,
+            "An account already exists with this email, please login and \
+             link this provider from your account",
+          );
+        }
+        let oauth_provider = UserOAuthProvider::new(
+          provider,
+          oauth_user.id.clone(),
+          oauth_user.email.clone(),
+        );
+        let update = user_collection
+          .oauth_provider_link(&user.id, &oauth_provider)
+          .await?;
+        if update.modified_count == 0 {
+          return custom_error(
+            Status::Conflict,
+            "Another account of this provider is linked to your account",
+          );
+        }
+        user
+      }
+      None => register_from_oauth(db, provider, &oauth_user).await?,
+    },
   };
-  let token = BearerToken::new(user.id.clone(), two_factor)?;
-  let jwt = token.to_jwt()?;
-  let response = LoginResponse { token: jwt };
-  custom_response(Status::Ok, response)
+  if user.requires_two_factor() {
+    return two_factor_pending(&user.id);
+  }
+  start_session(db, &user.id, None, &client).await
+}
+
+/// Signs up a user from its provider account, the password is generated
+/// and can be replaced with the forgot password flow.
+async fn register_from_oauth(
+  db: &State<Database>,
+  provider: OAuthProvider,
+  oauth_user: &OAuthUser,
+) -> Result<User, ApiError> {
+  let (firstname, lastname) = oauth_user.firstname_lastname();
+  let password_hash = hash_password(&generate_forgot_password_token())?;
+  let oauth_provider = UserOAuthProvider::new(
+    provider,
+    oauth_user.id.clone(),
+    oauth_user.email.clone(),
+  );
+  let new_user =
+    User::new_from_oauth(firstname, lastname, password_hash, oauth_provider);
+  let mut session = start_transaction(db.client()).await?;
+  CommonCollection::<User>::new(db)
+    .insert_one_with_session(&new_user, &mut session)
+    .await?;
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &UserRegisteredEvent {
+        id: new_user.id.clone(),
+        firstname: new_user.firstname.clone(),
+        lastname: new_user.lastname.clone(),
+        email: new_user.email.email.clone(),
+      },
+      &mut session,
+    )
+    .await?;
+  commit_transaction(&mut session).await?;
+  Ok(new_user)
 }
 
 pub(crate) async fn magic_link(
   db: &State<Database>,
+  redis: &State<redis::Client>,
   body: Json<MagicLinkRequest>,
 ) -> ApiResult<SuccessMessage> {
   body.validate()?;
@@ -99,26 +301,53 @@ pub(crate) async fn magic_link(
   let user_collection = CommonCollection::<User>::new(db);
   let user = match user_collection.find_with_email(&email).await? {
     Some(user) => user,
+    None => return custom_message(Status::Ok, MAGIC_LINK_SENT_MESSAGE),
+  };
+  if !can_send_email(redis, "magic-link", &email).await? {
+    return custom_message(Status::Ok, MAGIC_LINK_SENT_MESSAGE);
+  }
+  // The link only carries a single use token, the session is opened when
+  // it is exchanged
+  let token = new_magic_link_token();
+  save_magic_link(redis, &token, &user.id).await?;
+  CommonCollection::<EventOutbox>::new(db)
+    .add(&UserMagicLinkEvent {
+      id: user.id.clone(),
+      firstname: user.firstname.clone(),
+      lastname: user.lastname.clone(),
+      email: user.email.email.clone(),
+      token,
+    })
+    .await?;
+  custom_message(Status::Ok, MAGIC_LINK_SENT_MESSAGE)
+}
+
+pub(crate) async fn magic_link_login(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  client: ClientInfo,
+  body: Json<MagicLinkLoginRequest>,
+) -> ApiResult<LoginResponse> {
+  let user_id = match take_magic_link(redis, &body.token).await? {
+    Some(user_id) => user_id,
     None => {
-      return custom_error(Status::NotFound, "User not found with this email")
+      return custom_error(
+        Status::Unauthorized,
+        "Magic link is invalid or expired, please ask for a new one",
+      )
     }
   };
-  let two_factor: Option<bool> = if let None = user.two_factor.clone() {
-    None
-  } else {
-    Some(false)
+  let user = match CommonCollection::<User>::new(db)
+    .get_by_id(&user_id)
+    .await?
+  {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
   };
-  let token = BearerToken::new(user.id.clone(), two_factor)?;
-  let jwt = token.to_jwt()?;
-
-  CommonEvent::new(CONFIG.kafka_url.clone()).send(UserMagicLinkEvent {
-    id: user.id.clone(),
-    firstname: user.firstname.clone(),
-    lastname: user.lastname.clone(),
-    email: user.email.email.clone(),
-    jwt: jwt.clone(),
-  })?;
-  custom_message(Status::Ok, "You will receive a magic link in your email")
+  if user.requires_two_factor() {
+    return two_factor_pending(&user.id);
+  }
+  start_session(db, &user.id, None, &client).await
 }
 
 pub(crate) async fn register(
@@ -127,11 +356,6 @@ pub(crate) async fn register(
 ) -> ApiResult<SuccessMessage> {
   body.validate()?;
   let body = body.into_inner();
-  let user_collection = CommonCollection::<User>::new(db);
-  // Verify if email exists for an user
-  if let Some(_) = user_collection.find_with_email(&body.email).await? {
-    return custom_error(Status::Conflict, "Email already exists");
-  };
   // Verify if password is strong
   let strong = is_strong_password(&body.password.clone())?;
   if !strong {
@@ -140,31 +364,81 @@ pub(crate) async fn register(
       "Password is not strong enough, please use a stronger password",
     );
   }
+  // Hashed before looking for the email, so both answers take as long
   let password_hash = hash_password(body.password.as_str())?;
-  let new_user: User = User::new(
+  let user_collection = CommonCollection::<User>::new(db);
+  // The answer doesn't tell whether the email exists, its owner is told
+  // by email instead
+  if let Some(user) = user_collection.find_with_email(&body.email).await? {
+    CommonCollection::<EventOutbox>::new(db)
+      .add(&UserAccountExistsEvent {
+        id: user.id,
+        firstname: user.firstname,
+        lastname: user.lastname,
+        email: user.email.email,
+        attempted_at: chrono::Utc::now(),
+      })
+      .await?;
+    return custom_message(Status::Created, "You are now registered");
+  };
+  let mut new_user: User = User::new(
     body.firstname.clone(),
     body.lastname.clone(),
     password_hash,
     body.email.clone(),
-    body.password.clone(),
+    body.phone.clone(),
   );
+  new_user.locale = body.locale.clone();
   let id = new_user.id.clone();
-  user_collection.insert_one(&new_user).await?;
-  CommonEvent::new(CONFIG.kafka_url.clone()).send(UserRegisteredEvent {
-    id: id.clone(),
-    firstname: body.firstname.clone(),
-    lastname: body.lastname.clone(),
-    email: body.email.clone(),
-  })?;
+  let mut session = start_transaction(db.client()).await?;
+  user_collection
+    .insert_one_with_session(&new_user, &mut session)
+    .await?;
+  send_email_verification(db, &mut session, &new_user).await?;
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &UserRegisteredEvent {
+        id: id.clone(),
+        firstname: body.firstname.clone(),
+        lastname: body.lastname.clone(),
+        email: body.email.clone(),
+      },
+      &mut session,
+    )
+    .await?;
+  commit_transaction(&mut session).await?;
   return custom_message(Status::Created, "You are now registered");
 }
 
+pub(crate) async fn two_factor_webauthn(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  body: Json<TwoFactorWebauthnRequest>,
+) -> ApiResult<RequestChallengeResponse> {
+  body.validate()?;
+  let token = BearerToken::parse_jwt(&body.token)?;
+  if token.is_expired() {
+    return custom_error(Status::Unauthorized, "Token is expired");
+  }
+  let user_id = token.parse_id()?;
+  let user_collection = CommonCollection::<User>::new(db);
+  let user = match user_collection.get_by_id(&user_id).await? {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
+  let challenge = start_authentication(redis, &user).await?;
+  custom_response(Status::Ok, challenge)
+}
+
 pub(crate) async fn two_factor(
   db: &State<Database>,
+  redis: &State<redis::Client>,
+  client: ClientInfo,
   body: Json<TwoFactorCodeRequest>,
 ) -> ApiResult<LoginResponse> {
   body.validate()?;
-  let mut token = BearerToken::parse_jwt(&body.token)?;
+  let body = body.into_inner();
+  let token = BearerToken::parse_jwt(&body.token)?;
   if token.is_expired() {
     return custom_error(Status::Unauthorized, "Token is expired");
   }
@@ -174,7 +448,28 @@ pub(crate) async fn two_factor(
     Some(user) => user,
     None => return custom_error(Status::NotFound, "User not found"),
   };
+  let subject = two_factor_lockout_subject(&user_id);
+  check_lockout(redis, &subject).await?;
 
+  // Verify the passkey, or the code of the TOTP
+  if let Some(credential) = &body.credential {
+    let result = finish_authentication(db, redis, &user, credential).await;
+    if let Err(err) = result {
+      record_failure(redis, &subject, &TWO_FACTOR_LOCKOUT).await?;
+      return Err(err);
+    }
+    clear_failures(redis, &subject).await?;
+    return start_session(db, &user_id, Some(true), &client).await;
+  }
+  let code = match body.code {
+    Some(code) => code,
+    None => {
+      return custom_error(
+        Status::BadRequest,
+        "A 2 factor code or a passkey credential is required",
+      )
+    }
+  };
   // Verify if 2 factor exist and are enabled for user
   if user.two_factor.clone().is_none() {
     return custom_error(Status::Unauthorized, "2 factor is not setup");
@@ -183,26 +478,24 @@ pub(crate) async fn two_factor(
   if !user_two_factor.is_enabled() {
     return custom_error(Status::Unauthorized, "2 factor is not enabled");
   }
-  let result = verify_2fa_code(
-    user.email.email.clone(),
-    &user_two_factor,
-    body.code.clone(),
-  )?;
+  let result =
+    verify_2fa_code(user.email.email.clone(), &user_two_factor, code)?;
   if !result {
+    record_failure(redis, &subject, &TWO_FACTOR_LOCKOUT).await?;
     return custom_error(Status::Unauthorized, "2 factor code is invalid");
   }
-  token.with_otp(Some(true));
-  let new_token = token.to_jwt()?;
-  let response: LoginResponse = LoginResponse { token: new_token };
-  return custom_response(Status::Ok, response);
+  clear_failures(redis, &subject).await?;
+  return start_session(db, &user_id, Some(true), &client).await;
 }
 
 pub(crate) async fn two_factor_recovery(
   db: &State<Database>,
+  redis: &State<redis::Client>,
+  client: ClientInfo,
   body: Json<TwoFactorRecoveryRequest>,
 ) -> ApiResult<LoginResponse> {
   body.validate()?;
-  let mut token = BearerToken::parse_jwt(&body.token)?;
+  let token = BearerToken::parse_jwt(&body.token)?;
   if token.is_expired() {
     return custom_error(Status::Unauthorized, "Token is expired");
   }
@@ -212,48 +505,96 @@ pub(crate) async fn two_factor_recovery(
     Some(user) => user,
     None => return custom_error(Status::NotFound, "User not found"),
   };
-  // Verify if 2 factor exist and are enabled for user
-  return match user.two_factor {
-    Some(two_factor) => {
-      // Verify if 2 factor is enabled
-      if !two_factor.is_enabled() {
-        return custom_error(
-          Status::Unauthorized,
-          "2 factor is not enabled for this account",
-        );
-      }
-      // Verify if the code is valid
-      let code = body.recovery_code.clone().replace(" ", "");
-      let recovery_code = two_factor.recovery_code.clone();
-      if !recovery_code.eq(&code) {
-        return custom_error(
-          Status::Unauthorized,
-          "Recovery code is invalid for this account",
-        );
-      }
-      // Disable 2 factor
-      let update = user_collection.two_factor_update(&user_id, &None).await?;
-      if update.modified_count == 0 {
-        return custom_error(
-          Status::InternalServerError,
-          "Failed to update user in database",
-        );
-      }
-      // Generate a new token with jwt ans send the jwt
-      let token = BearerToken::new(user_id, None)?;
-      let jwt = token.to_jwt()?;
-      let response = LoginResponse { token: jwt };
-      custom_response(Status::Ok, response)
+  let subject = two_factor_lockout_subject(&user_id);
+  check_lockout(redis, &subject).await?;
+  // Verify if 2 factor is enabled, with a TOTP or only passkeys
+  if !user.requires_two_factor() {
+    return custom_error(
+      Status::Unauthorized,
+      "2 factor is not enabled for this account",
+    );
+  }
+  // Consume the code, it can't be used again. The codes of a TOTP which
+  // is only setup aren't valid yet.
+  let recovery_code = TwoFactor::hash_recovery_code(&body.recovery_code);
+  let totp_enabled = match &user.two_factor {
+    Some(two_factor) => two_factor.is_enabled(),
+    None => false,
+  };
+  let update = match totp_enabled {
+    true => {
+      user_collection
+        .two_factor_recovery_code_use(&user_id, &recovery_code)
+        .await?
+    }
+    false => {
+      user_collection
+        .webauthn_recovery_code_use(&user_id, &recovery_code)
+        .await?
+    }
+  };
+  if update.modified_count == 0 {
+    record_failure(redis, &subject, &TWO_FACTOR_LOCKOUT).await?;
+    return custom_error(
+      Status::Unauthorized,
+      "Recovery code is invalid for this account",
+    );
+  }
+  clear_failures(redis, &subject).await?;
+  start_session(db, &user_id, Some(true), &client).await
+}
+
+pub(crate) async fn refresh(
+  db: &State<Database>,
+  client: ClientInfo,
+  body: Json<RefreshTokenRequest>,
+) -> ApiResult<LoginResponse> {
+  body.validate()?;
+  let session_collection = CommonCollection::<UserSession>::new(db);
+  let session = match session_collection
+    .get_active_by_refresh_token(&body.refresh_token)
+    .await?
+  {
+    Some(session) => session,
+    None => {
+      return custom_error(
+        Status::Unauthorized,
+        "Refresh token is invalid or expired, please login again",
+      )
     }
-    None => custom_error(
+  };
+  let user_collection = CommonCollection::<User>::new(db);
+  let user = match user_collection.get_by_id(&session.user_id).await? {
+    Some(user) => user,
+    None => return custom_error(Status::Unauthorized, "User not found"),
+  };
+  // The session was opened after the 2FA when it is required
+  let otp = match user.requires_two_factor() {
+    true => Some(true),
+    false => None,
+  };
+  let refresh_token = new_refresh_token();
+  let update = session_collection
+    .refresh(&session.id, &body.refresh_token, &refresh_token, client.ip)
+    .await?;
+  if update.modified_count == 0 {
+    return custom_error(
       Status::Unauthorized,
-      "2 factor is not setup for this account",
-    ),
+      "Refresh token is invalid or expired, please login again",
+    );
+  }
+  let mut token = BearerToken::new(user.id, otp)?;
+  token.with_session(session.id);
+  let response = LoginResponse {
+    token: token.to_jwt()?,
+    refresh_token: Some(refresh_token),
   };
+  custom_response(Status::Ok, response)
 }
 
 pub(crate) async fn forgot_password(
   db: &State<Database>,
+  redis: &State<redis::Client>,
   body: Json<ForgotPasswordRequest>,
 ) -> ApiResult<SuccessMessage> {
   body.validate()?;
@@ -261,29 +602,37 @@ pub(crate) async fn forgot_password(
   let user_collection = CommonCollection::<User>::new(db);
   let user = match user_collection.find_with_email(&email).await? {
     Some(user) => user,
-    None => return custom_error(Status::NotFound, "User not found"),
+    None => return custom_message(Status::Ok, FORGOT_PASSWORD_SENT_MESSAGE),
   };
+  if !can_send_email(redis, "forgot-password", &email).await? {
+    return custom_message(Status::Ok, FORGOT_PASSWORD_SENT_MESSAGE);
+  }
   // Update forgot token in database
   let token = generate_forgot_password_token();
+  let mut session = start_transaction(db.client()).await?;
   user_collection
-    .password_update_forgot_token(&user.id, Some(&token))
+    .password_update_forgot_token_with_session(&user.id, &token, &mut session)
     .await?;
   // Send event to kafka
-  CommonEvent::new(CONFIG.kafka_url.clone()).send(UserForgotPasswordEvent {
-    id: user.id.clone(),
-    firstname: user.firstname.clone(),
-    lastname: user.lastname.clone(),
-    email: user.email.email.clone(),
-    token: token.clone(),
-  })?;
-  custom_message(
-    Status::Ok,
-    "You will receive a link to reset your password in your email",
-  )
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &UserForgotPasswordEvent {
+        id: user.id.clone(),
+        firstname: user.firstname.clone(),
+        lastname: user.lastname.clone(),
+        email: user.email.email.clone(),
+        token: token.clone(),
+      },
+      &mut session,
+    )
+    .await?;
+  commit_transaction(&mut session).await?;
+  custom_message(Status::Ok, FORGOT_PASSWORD_SENT_MESSAGE)
 }
 
 pub(crate) async fn reset_password(
   db: &State<Database>,
+  redis: &State<redis::Client>,
   body: Json<ResetPasswordRequest>,
 ) -> ApiResult<SuccessMessage> {
   body.validate()?;
@@ -305,19 +654,28 @@ pub(crate) async fn reset_password(
     );
   }
   let password_hash = hash_password(body.new_password.as_str())?;
-  // Update data in database
+  // Update data in database and send event to kafka
+  let mut session = start_transaction(db.client()).await?;
   user_collection
-    .password_update_forgot_token(&user.id, None)
+    .password_reset_with_session(&user.id, &password_hash, &mut session)
     .await?;
-  user_collection
-    .password_update_hash(&user.id, &password_hash)
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &UserPasswordResetEvent {
+        id: user.id.clone(),
+        firstname: user.firstname.clone(),
+        lastname: user.lastname.clone(),
+        email: user.email.email.clone(),
+        reset_at: chrono::Utc::now(),
+      },
+      &mut session,
+    )
     .await?;
-  // Send event to kafka
-  CommonEvent::new(CONFIG.kafka_url.clone()).send(UserPasswordResetEvent {
-    id: user.id.clone(),
-    firstname: user.firstname.clone(),
-    lastname: user.lastname.clone(),
-    email: user.email.email.clone(),
-  })?;
+  commit_transaction(&mut session).await?;
+  // Logout every device, the password may have been compromised
+  let session_ids = CommonCollection::<UserSession>::new(db)
+    .revoke_all_of_user(&user.id)
+    .await?;
+  revoke_access_tokens(redis, &session_ids).await?;
   custom_message(Status::Ok, "Your password was reset")
 }
@@ -1,8 +1,10 @@
-use crate::oauth::OAuthService;
+use crate::utils::phone::validate_e164_phone;
 use rocket::serde::json::serde_json::json;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::Validate;
+use webauthn_rs::prelude::PublicKeyCredential;
+use x_deploy_common::db::user::OAuthProvider;
 
 #[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
 #[schema(example = json!({
@@ -18,15 +20,26 @@ pub(crate) struct LoginRequest {
   pub(crate) password: String,
 }
 
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+#[schema(example = json!({
+  "authorizationUrl": "https://github.com/login/oauth/authorize?client_id=...",
+}))]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct OAuthAuthorizeResponse {
+  /// The page of the provider the user must be redirected to
+  pub(crate) authorization_url: String,
+}
+
 #[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
 #[schema(example = json!({
-  "service": "GITHUB",
-  "accessToken": "gh_ey78...",
+  "code": "4c3b2a1...",
+  "state": "Jk8Lp2Qr...",
 }))]
 #[serde(rename_all = "camelCase")]
-pub(crate) struct LoginOAuthRequest {
-  pub(crate) service: OAuthServiceType,
-  pub(crate) access_token: String,
+pub(crate) struct OAuthCallbackRequest {
+  /// The parameters given by the provider on the redirect URI
+  pub(crate) code: String,
+  pub(crate) state: String,
 }
 
 #[derive(Deserialize, Serialize, Debug, ToSchema)]
@@ -49,6 +62,15 @@ pub(crate) struct MagicLinkRequest {
   pub(crate) email: String,
 }
 
+#[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
+#[schema(example = json!({
+    "token": "Xk2bP0QmW7tLr9..."
+}))]
+pub(crate) struct MagicLinkLoginRequest {
+  #[serde(rename = "token")]
+  pub(crate) token: String,
+}
+
 #[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
 #[schema(example = json!({
     "token": "ey6b0pm7hk87bJB...",
@@ -58,14 +80,29 @@ pub(crate) struct TwoFactorCodeRequest {
   #[serde(rename = "token")]
   pub(crate) token: String,
 
+  /// The code of the TOTP application
   #[serde(rename = "code")]
-  pub(crate) code: String,
+  pub(crate) code: Option<String>,
+
+  /// The answer of a passkey to the challenge of `/auth/2fa/webauthn`
+  #[serde(rename = "credential")]
+  #[schema(value_type = Option<Object>)]
+  pub(crate) credential: Option<PublicKeyCredential>,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
+#[schema(example = json!({
+    "token": "ey6b0pm7hk87bJB..."
+}))]
+pub(crate) struct TwoFactorWebauthnRequest {
+  #[serde(rename = "token")]
+  pub(crate) token: String,
 }
 
 #[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
 #[schema(example = json!({
     "token": "ey6b0pm7hk87bJB...",
-    "recoveryCode": "123678JFDF86FDSF786Y..."
+    "recoveryCode": "K7P2D-9XQ4M"
 }))]
 pub(crate) struct TwoFactorRecoveryRequest {
   #[serde(rename = "token")]
@@ -77,11 +114,25 @@ pub(crate) struct TwoFactorRecoveryRequest {
 
 #[derive(Deserialize, Serialize, Debug, ToSchema)]
 #[schema(example = json!({
-    "token": "ey6b0pm7hk87bJB..."
+    "token": "ey6b0pm7hk87bJB...",
+    "refreshToken": "Xk2bP0QmW7tLr9..."
 }))]
 pub(crate) struct LoginResponse {
   #[serde(rename = "token")]
   pub(crate) token: String,
+
+  /// Absent while the 2FA code is still expected
+  #[serde(rename = "refreshToken", skip_serializing_if = "Option::is_none")]
+  pub(crate) refresh_token: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
+#[schema(example = json!({
+    "refreshToken": "Xk2bP0QmW7tLr9..."
+}))]
+pub(crate) struct RefreshTokenRequest {
+  #[serde(rename = "refreshToken")]
+  pub(crate) refresh_token: String,
 }
 
 #[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
@@ -90,7 +141,8 @@ pub(crate) struct LoginResponse {
     "lastname": "DOE",
     "email": "john@doe.net",
     "phone": "+1234567890",
-    "password": "myAmazingStringPassword123!"
+    "password": "myAmazingStringPassword123!",
+    "locale": "en-US"
 }))]
 pub(crate) struct RegisterRequest {
   #[validate(length(
@@ -113,12 +165,20 @@ pub(crate) struct RegisterRequest {
   #[serde(rename = "email")]
   pub(crate) email: String,
 
-  #[validate(phone(message = "Your phone is in a wrong format"))]
+  #[validate(custom(
+    function = "validate_e164_phone",
+    message = "Your phone must be in the E.164 format, e.g. +34612345678"
+  ))]
   #[serde(rename = "phone")]
   pub(crate) phone: String,
 
   #[serde(rename = "password")]
   pub(crate) password: String,
+
+  /// Language of the emails, the default one is used when missing
+  #[validate(length(max = 35, message = "Your locale is too long"))]
+  #[serde(rename = "locale", default)]
+  pub(crate) locale: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, ToSchema, Validate)]
@@ -144,12 +204,12 @@ pub(crate) struct ResetPasswordRequest {
   pub(crate) new_password: String,
 }
 
-impl Into<OAuthService> for OAuthServiceType {
-  fn into(self) -> OAuthService {
-    match self {
-      OAuthServiceType::Github => OAuthService::Github,
-      OAuthServiceType::Google => OAuthService::Google,
-      OAuthServiceType::GitLab => OAuthService::GitLab,
+impl From<OAuthProvider> for OAuthServiceType {
+  fn from(value: OAuthProvider) -> Self {
+    match value {
+      OAuthProvider::Github => OAuthServiceType::Github,
+      OAuthProvider::Google => OAuthServiceType::Google,
+      OAuthProvider::GitLab => OAuthServiceType::GitLab,
     }
   }
 }
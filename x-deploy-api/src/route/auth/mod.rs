@@ -1,7 +1,9 @@
+use crate::guard::client_info::ClientInfo;
 use crate::route::auth::dto::{
-  ForgotPasswordRequest, LoginOAuthRequest, LoginRequest, LoginResponse,
-  MagicLinkRequest, RegisterRequest, ResetPasswordRequest,
-  TwoFactorCodeRequest, TwoFactorRecoveryRequest,
+  ForgotPasswordRequest, LoginRequest, LoginResponse, MagicLinkLoginRequest,
+  MagicLinkRequest, OAuthAuthorizeResponse, OAuthCallbackRequest,
+  RefreshTokenRequest, RegisterRequest, ResetPasswordRequest,
+  TwoFactorCodeRequest, TwoFactorRecoveryRequest, TwoFactorWebauthnRequest,
 };
 use crate::route::{ApiResult, SuccessMessage};
 use bson::doc;
@@ -9,6 +11,7 @@ use mongodb::Database;
 use rocket::serde::json::Json;
 use rocket::State;
 use std::str::FromStr;
+use webauthn_rs::prelude::RequestChallengeResponse;
 
 mod controller;
 pub mod dto;
@@ -30,27 +33,53 @@ pub mod dto;
 )]
 pub(crate) async fn login(
   db: &State<Database>,
+  redis: &State<redis::Client>,
+  client: ClientInfo,
   body: Json<LoginRequest>,
 ) -> ApiResult<LoginResponse> {
-  return controller::login(db, body).await;
+  return controller::login(db, redis, client, body).await;
 }
 
 #[utoipa::path(
     post,
-    operation_id = "Login OAuth",
-    path = "/auth/login/oauth",
+    operation_id = "OAuth Authorize",
+    path = "/auth/oauth/<provider>/authorize",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "The page of the provider to redirect to", body = OAuthAuthorizeResponse),
+    ),
+)]
+#[post("/auth/oauth/<provider>/authorize")]
+pub(crate) async fn oauth_authorize(
+  redis: &State<redis::Client>,
+  provider: &str,
+) -> ApiResult<OAuthAuthorizeResponse> {
+  return controller::oauth_authorize(redis, provider).await;
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "OAuth Callback",
+    path = "/auth/oauth/<provider>/callback",
     tag = "Auth",
     responses(
         (status = 200, description = "You're now logged in", body = LoginResponse),
     ),
-    request_body = LoginOAuthRequest,
+    request_body = OAuthCallbackRequest,
+)]
+#[post(
+  "/auth/oauth/<provider>/callback",
+  format = "application/json",
+  data = "<body>"
 )]
-#[post("/auth/login/oauth", format = "application/json", data = "<body>")]
-pub async fn login_oauth(
+pub(crate) async fn oauth_callback(
   db: &State<Database>,
-  body: Json<LoginOAuthRequest>,
+  redis: &State<redis::Client>,
+  client: ClientInfo,
+  provider: &str,
+  body: Json<OAuthCallbackRequest>,
 ) -> ApiResult<LoginResponse> {
-  return controller::login_oauth(db, body).await;
+  return controller::oauth_callback(db, redis, client, provider, body).await;
 }
 
 #[utoipa::path(
@@ -66,9 +95,30 @@ pub async fn login_oauth(
 #[post("/auth/magic-link", format = "application/json", data = "<body>")]
 pub(crate) async fn magic_link(
   db: &State<Database>,
+  redis: &State<redis::Client>,
   body: Json<MagicLinkRequest>,
 ) -> ApiResult<SuccessMessage> {
-  return controller::magic_link(db, body).await;
+  return controller::magic_link(db, redis, body).await;
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "Magic Link Login",
+    path = "/auth/login/magic-link",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "You're now logged in", body = LoginResponse),
+    ),
+    request_body = MagicLinkLoginRequest,
+)]
+#[post("/auth/login/magic-link", format = "application/json", data = "<body>")]
+pub(crate) async fn magic_link_login(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  client: ClientInfo,
+  body: Json<MagicLinkLoginRequest>,
+) -> ApiResult<LoginResponse> {
+  return controller::magic_link_login(db, redis, client, body).await;
 }
 
 #[utoipa::path(
@@ -95,16 +145,37 @@ pub(crate) async fn register(
     path = "/auth/2fa",
     tag = "Auth",
     responses(
-        (status = 200, description = "You're now logged in", body = SuccessMessage),
+        (status = 200, description = "You're now logged in", body = LoginResponse),
     ),
     request_body = TwoFactorCodeRequest,
 )]
 #[post("/auth/2fa", format = "application/json", data = "<body>")]
 pub(crate) async fn two_factor(
   db: &State<Database>,
+  redis: &State<redis::Client>,
+  client: ClientInfo,
   body: Json<TwoFactorCodeRequest>,
 ) -> ApiResult<LoginResponse> {
-  return controller::two_factor(db, body).await;
+  return controller::two_factor(db, redis, client, body).await;
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "Login 2FA WebAuthn Challenge",
+    path = "/auth/2fa/webauthn",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "The challenge to sign with a passkey"),
+    ),
+    request_body = TwoFactorWebauthnRequest,
+)]
+#[post("/auth/2fa/webauthn", format = "application/json", data = "<body>")]
+pub(crate) async fn two_factor_webauthn(
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  body: Json<TwoFactorWebauthnRequest>,
+) -> ApiResult<RequestChallengeResponse> {
+  return controller::two_factor_webauthn(db, redis, body).await;
 }
 
 #[utoipa::path(
@@ -120,9 +191,30 @@ pub(crate) async fn two_factor(
 #[post("/auth/2fa/recovery", format = "application/json", data = "<body>")]
 pub(crate) async fn two_factor_recovery(
   db: &State<Database>,
+  redis: &State<redis::Client>,
+  client: ClientInfo,
   body: Json<TwoFactorRecoveryRequest>,
 ) -> ApiResult<LoginResponse> {
-  return controller::two_factor_recovery(db, body).await;
+  return controller::two_factor_recovery(db, redis, client, body).await;
+}
+
+#[utoipa::path(
+    post,
+    operation_id = "Refresh Token",
+    path = "/auth/refresh",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "Your session was extended", body = LoginResponse),
+    ),
+    request_body = RefreshTokenRequest,
+)]
+#[post("/auth/refresh", format = "application/json", data = "<body>")]
+pub(crate) async fn refresh(
+  db: &State<Database>,
+  client: ClientInfo,
+  body: Json<RefreshTokenRequest>,
+) -> ApiResult<LoginResponse> {
+  controller::refresh(db, client, body).await
 }
 
 #[utoipa::path(
@@ -131,16 +223,17 @@ pub(crate) async fn two_factor_recovery(
     path = "/auth/password/forgot",
     tag = "Auth",
     responses(
-        (status = 200, description = "The magic was sent", body = SuccessMessage),
+        (status = 200, description = "The link was sent if an account exists", body = SuccessMessage),
     ),
     request_body = ForgotPasswordRequest,
 )]
 #[post("/auth/password/forgot", format = "application/json", data = "<body>")]
 pub(crate) async fn forgot_password(
   db: &State<Database>,
+  redis: &State<redis::Client>,
   body: Json<ForgotPasswordRequest>,
 ) -> ApiResult<SuccessMessage> {
-  controller::forgot_password(db, body).await
+  controller::forgot_password(db, redis, body).await
 }
 
 #[utoipa::path(
@@ -156,7 +249,8 @@ pub(crate) async fn forgot_password(
 #[post("/auth/password/reset", format = "application/json", data = "<body>")]
 pub(crate) async fn reset_password(
   db: &State<Database>,
+  redis: &State<redis::Client>,
   body: Json<ResetPasswordRequest>,
 ) -> ApiResult<SuccessMessage> {
-  controller::reset_password(db, body).await
+  controller::reset_password(db, redis, body).await
 }
@@ -14,6 +14,7 @@ pub mod auth;
 pub mod cloud_provider;
 pub mod invitation;
 pub mod organization;
+pub mod well_known;
 
 pub type ApiResult<T> = Result<Custom<Json<T>>, ApiError>;
 
@@ -1,8 +1,8 @@
 use crate::guard::bearer_token::BearerToken;
 use crate::permission::general::GeneralPermission;
 use crate::route::organization::api_key::dto::{
-  ApiKeyInfoResponse, ApiKeyRoleInfoResponse, CreateApiKeyRequest,
-  CreateApiKeyResponse, UpdateApiKeyRequest,
+  ApiKeyInfoResponse, CreateApiKeyRequest, CreateApiKeyResponse,
+  RotateApiKeyRequest, UpdateApiKeyRequest,
 };
 use crate::route::{
   custom_error, custom_message, custom_response, ApiResult, SuccessMessage,
@@ -15,12 +15,17 @@ use rocket::http::Status;
 use rocket::serde::json::Json;
 use rocket::State;
 use std::str::FromStr;
+use x_deploy_common::data::ip_rule::is_valid_ip_rule;
 use x_deploy_common::db::organization_apikey::OrganizationApiKey;
 use x_deploy_common::db::organization_role::{
   OrganizationRole, StandardPermission,
 };
 use x_deploy_common::db::CommonCollection;
 
+const DEFAULT_ROTATION_GRACE_PERIOD_IN_MINUTES: u64 = 60;
+
+const MAX_ROTATION_GRACE_PERIOD_IN_MINUTES: u64 = 7 * 24 * 60;
+
 pub(crate) async fn new(
   db: &State<Database>,
   token: BearerToken,
@@ -66,19 +71,26 @@ pub(crate) async fn new(
     }
     None => None,
   };
+  if let Some(allowed_ips) = &body.allowed_ips {
+    if let Some(rule) = find_invalid_ip_rule(allowed_ips) {
+      let message = format!("{} is not a valid IP address or range", rule);
+      return custom_error(Status::BadRequest, message.as_str());
+    }
+  }
   let ak_value = new_key_value();
   let new_api_key = OrganizationApiKey::new(
     body.name.clone(),
     body.description.clone(),
-    ak_value.clone(),
+    &ak_value,
     org_id,
     role_id,
     chrono_expired,
+    body.allowed_ips.clone(),
   );
   let akc = CommonCollection::<OrganizationApiKey>::new(db);
   akc.insert_one(&new_api_key).await?;
 
-  // Return success
+  // The key is only returned once, only its hash is stored
   let response = CreateApiKeyResponse {
     id: new_api_key.id.to_string(),
     key: ak_value,
@@ -101,39 +113,8 @@ pub(crate) async fn get(
   let akc = CommonCollection::<OrganizationApiKey>::new(db);
   let api_keys = akc.get_all_of_org(&org_id).await?;
 
-  let mut result: Vec<ApiKeyInfoResponse> = Vec::new();
-  for api_key in api_keys {
-    let role: Option<ApiKeyRoleInfoResponse> = match api_key.role {
-      Some(role) => Some(ApiKeyRoleInfoResponse {
-        id: role.id.to_string(),
-        name: role.name,
-        description: role.description,
-      }),
-      None => None,
-    };
-    let expires_at: Option<String> = match api_key.expires_at {
-      Some(expires_at) => Some(
-        expires_at
-          .to_chrono()
-          .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-      ),
-      None => None,
-    };
-    let api_key_info = ApiKeyInfoResponse {
-      id: api_key.id.to_string(),
-      name: api_key.name,
-      description: api_key.description,
-      created_at: api_key
-        .id
-        .timestamp()
-        .to_chrono()
-        .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-      expires_at,
-      role,
-      organization_id: org_id.to_string(),
-    };
-    result.push(api_key_info);
-  }
+  let result: Vec<ApiKeyInfoResponse> =
+    api_keys.into_iter().map(|api_key| api_key.into()).collect();
   custom_response(Status::Ok, result)
 }
 
@@ -142,18 +123,23 @@ pub(crate) async fn get_by_id(
   token: BearerToken,
   org_id: String,
   key_id: String,
-) -> ApiResult<SuccessMessage> {
+) -> ApiResult<ApiKeyInfoResponse> {
   let user_id = token.parse_id()?;
   let org_id = ObjectId::from_str(&org_id)?;
+  let key_id = ObjectId::from_str(&key_id)?;
 
   GeneralPermission::ApiKeys
     .verify_and_get(db, &user_id, &org_id, &StandardPermission::Read)
     .await?;
 
-  return custom_message(Status::NotImplemented, "Not implemented");
+  let akc = CommonCollection::<OrganizationApiKey>::new(db);
+  return match akc.get_by_id_of_org(&org_id, &key_id).await? {
+    Some(api_key) => custom_response(Status::Ok, api_key.into()),
+    None => custom_error(Status::NotFound, "Api key not found"),
+  };
 }
 
-pub async fn update(
+pub(crate) async fn update(
   db: &State<Database>,
   token: BearerToken,
   org_id: String,
@@ -168,7 +154,69 @@ pub async fn update(
     .verify_and_get(db, &user_id, &org_id, &StandardPermission::ReadWrite)
     .await?;
 
-  return custom_message(Status::NotImplemented, "Not implemented");
+  if let Some(rule) = find_invalid_ip_rule(&body.allowed_ips) {
+    let message = format!("{} is not a valid IP address or range", rule);
+    return custom_error(Status::BadRequest, message.as_str());
+  }
+  let akc = CommonCollection::<OrganizationApiKey>::new(db);
+  if let None = akc.get_by_id_of_org(&org_id, &key_id).await? {
+    return custom_error(Status::NotFound, "Api key not found");
+  }
+  akc
+    .update_info(&key_id, &body.name, &body.description, &body.allowed_ips)
+    .await?;
+  custom_message(Status::Ok, "Api key updated")
+}
+
+pub(crate) async fn rotate(
+  db: &State<Database>,
+  token: BearerToken,
+  org_id: String,
+  key_id: String,
+  body: Json<RotateApiKeyRequest>,
+) -> ApiResult<CreateApiKeyResponse> {
+  let user_id = token.parse_id()?;
+  let org_id = ObjectId::from_str(&org_id)?;
+  let key_id = ObjectId::from_str(&key_id)?;
+
+  GeneralPermission::ApiKeys
+    .verify_and_get(db, &user_id, &org_id, &StandardPermission::ReadWrite)
+    .await?;
+
+  let grace_period_minutes = body
+    .grace_period_minutes
+    .unwrap_or(DEFAULT_ROTATION_GRACE_PERIOD_IN_MINUTES);
+  if grace_period_minutes > MAX_ROTATION_GRACE_PERIOD_IN_MINUTES {
+    let message = format!(
+      "The grace period can't be longer than {} minutes",
+      MAX_ROTATION_GRACE_PERIOD_IN_MINUTES
+    );
+    return custom_error(Status::BadRequest, message.as_str());
+  }
+  let akc = CommonCollection::<OrganizationApiKey>::new(db);
+  let api_key = match akc.get_by_id(&key_id).await? {
+    Some(api_key) if api_key.organization_id == org_id => api_key,
+    _ => return custom_error(Status::NotFound, "Api key not found"),
+  };
+  let grace_period = chrono::Duration::minutes(grace_period_minutes as i64);
+  let previous_key_expires_at =
+    bson::DateTime::from_chrono(Utc::now() + grace_period);
+  let ak_value = new_key_value();
+  akc
+    .rotate(
+      &api_key.id,
+      &ak_value,
+      &api_key.key_hash,
+      previous_key_expires_at,
+    )
+    .await?;
+
+  // The key is only returned once, only its hash is stored
+  let response = CreateApiKeyResponse {
+    id: api_key.id.to_string(),
+    key: ak_value,
+  };
+  custom_response(Status::Ok, response)
 }
 
 pub(crate) async fn delete(
@@ -185,5 +233,14 @@ pub(crate) async fn delete(
     .verify_and_get(db, &user_id, &org_id, &StandardPermission::ReadWrite)
     .await?;
 
-  return custom_message(Status::NotImplemented, "Not implemented");
+  let akc = CommonCollection::<OrganizationApiKey>::new(db);
+  let result = akc.delete_by_id_of_org(&org_id, &key_id).await?;
+  if result.deleted_count == 0 {
+    return custom_error(Status::NotFound, "Api key not found");
+  }
+  custom_message(Status::Ok, "Api key deleted")
+}
+
+fn find_invalid_ip_rule(rules: &Vec<String>) -> Option<&String> {
+  rules.iter().find(|rule| !is_valid_ip_rule(rule))
 }
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use x_deploy_common::db::query::organization_api_key::OrganizationApiKeyQuery;
 
 #[derive(Deserialize, Serialize, Debug, ToSchema)]
 pub struct CreateApiKeyRequest {
@@ -16,6 +17,10 @@ pub struct CreateApiKeyRequest {
   /// If null, key will never expire
   #[serde(rename = "expiresAt")]
   pub expires_at: Option<String>,
+
+  /// IP addresses or CIDR ranges allowed to use the key, empty for any
+  #[serde(rename = "allowedIps", default)]
+  pub allowed_ips: Vec<String>,
 }
 
 #[derive(Deserialize, Serialize, Debug, ToSchema)]
@@ -23,6 +28,7 @@ pub struct CreateApiKeyResponse {
   #[serde(rename = "id")]
   pub id: String,
 
+  /// Only returned at creation, it can't be retrieved afterwards
   #[serde(rename = "key")]
   pub key: String,
 }
@@ -41,6 +47,10 @@ pub struct ApiKeyInfoResponse {
   #[serde(rename = "description")]
   pub description: Option<String>,
 
+  /// The first characters of the key
+  #[serde(rename = "prefix")]
+  pub prefix: String,
+
   #[serde(rename = "role")]
   pub role: Option<ApiKeyRoleInfoResponse>,
 
@@ -49,6 +59,52 @@ pub struct ApiKeyInfoResponse {
 
   #[serde(rename = "expiresAt")]
   pub expires_at: Option<String>,
+
+  /// When the key replaced by the last rotation stops working
+  #[serde(rename = "previousKeyExpiresAt")]
+  pub previous_key_expires_at: Option<String>,
+
+  #[serde(rename = "lastUsedAt")]
+  pub last_used_at: Option<String>,
+
+  #[serde(rename = "lastUsedIp")]
+  pub last_used_ip: Option<String>,
+
+  #[serde(rename = "allowedIps")]
+  pub allowed_ips: Vec<String>,
+}
+
+impl From<OrganizationApiKeyQuery> for ApiKeyInfoResponse {
+  fn from(api_key: OrganizationApiKeyQuery) -> Self {
+    let role = match api_key.role {
+      Some(role) => Some(ApiKeyRoleInfoResponse {
+        id: role.id.to_string(),
+        name: role.name,
+        description: role.description,
+      }),
+      None => None,
+    };
+    Self {
+      id: api_key.id.to_string(),
+      organization_id: api_key.organization_id.to_string(),
+      name: api_key.name,
+      description: api_key.description,
+      prefix: api_key.key_prefix,
+      role,
+      created_at: to_rfc3339(api_key.id.timestamp()),
+      expires_at: api_key.expires_at.map(to_rfc3339),
+      previous_key_expires_at: api_key.previous_key_expires_at.map(to_rfc3339),
+      last_used_at: api_key.last_used_at.map(to_rfc3339),
+      last_used_ip: api_key.last_used_ip,
+      allowed_ips: api_key.allowed_ips,
+    }
+  }
+}
+
+fn to_rfc3339(date: bson::DateTime) -> String {
+  date
+    .to_chrono()
+    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
 }
 
 #[derive(Deserialize, Serialize, Debug, ToSchema)]
@@ -79,4 +135,16 @@ pub struct UpdateApiKeyRequest {
 
   #[serde(rename = "description")]
   pub description: Option<String>,
+
+  /// IP addresses or CIDR ranges allowed to use the key, empty for any,
+  /// kept as they are if null
+  #[serde(rename = "allowedIps", default)]
+  pub allowed_ips: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, ToSchema)]
+pub struct RotateApiKeyRequest {
+  /// Minutes during which the current key keeps working, 60 if null
+  #[serde(rename = "gracePeriodMinutes")]
+  pub grace_period_minutes: Option<u64>,
 }
@@ -4,6 +4,7 @@ pub(crate) mod dto;
 use crate::guard::bearer_token::BearerToken;
 use crate::route::organization::api_key::dto::{
   ApiKeyInfoResponse, CreateApiKeyRequest, CreateApiKeyResponse,
+  RotateApiKeyRequest,
 };
 use crate::route::{ApiResult, SuccessMessage};
 use bson::doc;
@@ -70,7 +71,7 @@ pub(crate) async fn get(
       ("bearer" = []),
     ),
     responses(
-        (status = 200, description = "Specific api key retrieved", body = SuccessMessage),
+        (status = 200, description = "Specific api key retrieved", body = ApiKeyInfoResponse),
     )
 )]
 #[get("/organization/<id>/api-key/<key_id>", format = "application/json")]
@@ -79,12 +80,12 @@ pub(crate) async fn get_by_id(
   token: BearerToken,
   id: String,
   key_id: String,
-) -> ApiResult<SuccessMessage> {
+) -> ApiResult<ApiKeyInfoResponse> {
   controller::get_by_id(db, token, id, key_id).await
 }
 
 #[utoipa::path(
-    get,
+    patch,
     operation_id = "Update ApiKey Info",
     path = "/organization/<id>/api-key/<key_id>",
     tag = "Organization ApiKey",
@@ -93,7 +94,8 @@ pub(crate) async fn get_by_id(
     ),
     responses(
         (status = 200, description = "Api key info updated", body = SuccessMessage),
-    )
+    ),
+    request_body = UpdateApiKeyRequest,
 )]
 #[patch(
   "/organization/<org_id>/api-key/<key_id>",
@@ -110,6 +112,34 @@ pub async fn update(
   controller::update(db, token, org_id, key_id, body).await
 }
 
+#[utoipa::path(
+    post,
+    operation_id = "Rotate ApiKey",
+    path = "/organization/<id>/api-key/<key_id>/rotate",
+    tag = "Organization ApiKey",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "A new key was issued, the previous one works until the end of the grace period", body = CreateApiKeyResponse),
+    ),
+    request_body = RotateApiKeyRequest,
+)]
+#[post(
+  "/organization/<org_id>/api-key/<key_id>/rotate",
+  format = "application/json",
+  data = "<body>"
+)]
+pub(crate) async fn rotate(
+  db: &State<Database>,
+  token: BearerToken,
+  org_id: String,
+  key_id: String,
+  body: Json<RotateApiKeyRequest>,
+) -> ApiResult<CreateApiKeyResponse> {
+  controller::rotate(db, token, org_id, key_id, body).await
+}
+
 #[utoipa::path(
     delete,
     operation_id = "Delete ApiKey",
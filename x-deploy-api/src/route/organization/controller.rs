@@ -14,20 +14,26 @@ use crate::route::{
 use crate::utils::password::verify_password;
 use crate::utils::profile_picture::ProfilePicture;
 use crate::CONFIG;
-use bson::oid;
 use bson::Bson::ObjectId;
+use bson::{oid, DateTime};
 use mongodb::Database;
 use rocket::http::{ContentType, Status};
 use rocket::serde::json::Json;
 use rocket::{Data, State};
 use std::str::FromStr;
+use x_deploy_common::db::event_outbox::EventOutbox;
 use x_deploy_common::db::organization::Organization;
 use x_deploy_common::db::organization_member::OrganizationMember;
-use x_deploy_common::db::organization_role::StandardPermission;
+use x_deploy_common::db::organization_role::{
+  OrganizationRole, StandardPermission,
+};
 use x_deploy_common::db::user::User;
-use x_deploy_common::db::CommonCollection;
-use x_deploy_common::event::organization::OrganizationCreatedEvent;
-use x_deploy_common::event::CommonEvent;
+use x_deploy_common::db::{
+  commit_transaction, start_transaction, CommonCollection,
+};
+use x_deploy_common::event::organization::{
+  OrganizationCreatedEvent, OrganizationTransferOwnershipEvent,
+};
 use x_deploy_common::s3::bucket::CommonS3Bucket;
 use x_deploy_common::s3::config::CommonS3Config;
 use x_deploy_common::s3::file_type::CommonS3BucketType::OrganizationLogo;
@@ -65,6 +71,12 @@ pub(crate) async fn new(
     Some(user) => user,
     None => return custom_error(Status::NotFound, "User not found"),
   };
+  if CONFIG.require_verified_email_for_organization && !user.email.verified {
+    return custom_error(
+      Status::Forbidden,
+      "Please verify your email before creating an organization",
+    );
+  }
   // Insert Organization in database
   let org_collection = CommonCollection::<Organization>::new(db);
   let new_organization = Organization::new(
@@ -73,24 +85,33 @@ pub(crate) async fn new(
     body.website.clone(),
     body.contact_email.clone(),
   );
-  org_collection.insert_one(&new_organization).await?;
+  let mut session = start_transaction(db.client()).await?;
+  org_collection
+    .insert_one_with_session(&new_organization, &mut session)
+    .await?;
   let inserted_id = new_organization.id.clone();
   // Insert Organization member as owner
   let org_member_collection = CommonCollection::<OrganizationMember>::new(db);
   let owner = OrganizationMember::new(inserted_id.clone(), user_id, true, None);
-  org_member_collection.insert_one(&owner).await?;
+  org_member_collection
+    .insert_one_with_session(&owner, &mut session)
+    .await?;
 
-  CommonEvent::new(CONFIG.kafka_url.clone()).send(
-    OrganizationCreatedEvent {
-      id: inserted_id.clone().to_string(),
-      name: body.name.clone(),
-      description: body.description.clone(),
-      creator_id: user.id.to_string(),
-      creator_firstname: user.firstname.clone(),
-      creator_lastname: user.lastname.clone(),
-      creator_email: user.email.email.clone(),
-    },
-  )?;
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &OrganizationCreatedEvent {
+        id: inserted_id.clone().to_string(),
+        name: body.name.clone(),
+        description: body.description.clone(),
+        creator_id: user.id.to_string(),
+        creator_firstname: user.firstname.clone(),
+        creator_lastname: user.lastname.clone(),
+        creator_email: user.email.email.clone(),
+      },
+      &mut session,
+    )
+    .await?;
+  commit_transaction(&mut session).await?;
 
   info!("Inserted new organization with id: {}", inserted_id);
   custom_message(Status::Ok, "Organization created successfully")
@@ -185,23 +206,81 @@ pub(crate) async fn delete(
     }
   };
   let org_member_coll = CommonCollection::<OrganizationMember>::new(db);
-  let organization = org_member_coll.get_user_in_org(&org_id, &user_id).await?;
+  let member = match org_member_coll.get_user_in_org(&org_id, &user_id).await? {
+    Some(member) => member,
+    None => return custom_error(Status::NotFound, "Organization not found"),
+  };
+  if !member.is_owner {
+    return custom_error(
+      Status::Forbidden,
+      "Only the owner can delete the organization",
+    );
+  }
+  // Everything the organization owns is purged by the daemon once the
+  // restore window is over
+  let restore_window_in_millis =
+    CONFIG.organization_restore_window_in_days as i64 * 24 * 60 * 60 * 1000;
+  let purge_at = DateTime::from_millis(
+    DateTime::now().timestamp_millis() + restore_window_in_millis,
+  );
+  let org_collection = CommonCollection::<Organization>::new(db);
+  let result = org_collection
+    .soft_delete(&org_id, &user_id, purge_at)
+    .await?;
+  if result.modified_count == 0 {
+    return custom_error(
+      Status::InternalServerError,
+      "Failed to delete organization",
+    );
+  }
+  info!("Organization {} deleted, purged at {}", org_id, purge_at);
+  custom_message(
+    Status::Ok,
+    format!(
+      "Organization deleted successfully, it can be restored for {} days",
+      CONFIG.organization_restore_window_in_days
+    )
+    .as_str(),
+  )
+}
 
-  return match organization {
-    None => custom_error(Status::NotFound, "Organization not found"),
-    Some(organization) => {
-      let org_collection = CommonCollection::<Organization>::new(db);
-      let result = org_collection.delete_by_id(&organization.id).await?;
-      if result.deleted_count == 0 {
-        return custom_error(
-          Status::InternalServerError,
-          "Failed to delete organization",
-        );
-      }
-      // TODO: Delete member, custom role... etc
-      return custom_message(Status::Ok, "Organization deleted successfully");
+pub(crate) async fn restore(
+  db: &State<Database>,
+  token: BearerToken,
+  id: String,
+) -> ApiResult<SuccessMessage> {
+  let user_id = token.parse_id()?;
+  let org_id = match oid::ObjectId::parse_str(&id) {
+    Ok(id) => id,
+    Err(_) => {
+      return custom_error(Status::BadRequest, "Invalid organization id")
     }
   };
+  // Members of a deleted organization are hidden, so it is read directly
+  let org_member_coll = CommonCollection::<OrganizationMember>::new(db);
+  let member = match org_member_coll
+    .get_by_user_and_org(&org_id, &user_id)
+    .await?
+  {
+    Some(member) => member,
+    None => return custom_error(Status::NotFound, "Organization not found"),
+  };
+  if !member.is_owner {
+    return custom_error(
+      Status::Forbidden,
+      "Only the owner can restore the organization",
+    );
+  }
+  let org_collection = CommonCollection::<Organization>::new(db);
+  let result = org_collection.restore(&org_id).await?;
+  if result.modified_count == 0 {
+    return custom_error(
+      Status::Conflict,
+      "The organization isn't deleted or can no longer be restored",
+    );
+  }
+  info!("Organization {} restored", org_id);
+  custom_message(Status::Ok, "Organization restored successfully")
 }
 
 pub(crate) async fn transfer(
@@ -210,7 +289,107 @@ pub(crate) async fn transfer(
   id: String,
   body: Json<TransferOrganizationRequest>,
 ) -> ApiResult<SuccessMessage> {
-  return custom_message(Status::NotImplemented, "Not implemented");
+  let user_id = token.parse_id()?;
+  let user_collection = CommonCollection::<User>::new(db);
+  let user = match user_collection.get_by_id(&user_id).await? {
+    Some(user) => user,
+    None => return custom_error(Status::NotFound, "User not found"),
+  };
+  let verify_password =
+    verify_password(body.password.as_str(), user.password.password.as_str())?;
+  if !verify_password {
+    return custom_error(
+      Status::Forbidden,
+      "Invalid password for transfer organization",
+    );
+  }
+  let org_id = match oid::ObjectId::parse_str(&id) {
+    Ok(id) => id,
+    Err(_) => {
+      return custom_error(Status::BadRequest, "Invalid organization id")
+    }
+  };
+  let role_id = match oid::ObjectId::parse_str(&body.old_owner_role_id) {
+    Ok(id) => id,
+    Err(_) => return custom_error(Status::BadRequest, "Invalid role id"),
+  };
+  let org_member_coll = CommonCollection::<OrganizationMember>::new(db);
+  let owner = match org_member_coll.get_user_in_org(&org_id, &user_id).await? {
+    Some(owner) => owner,
+    None => return custom_error(Status::NotFound, "Organization not found"),
+  };
+  if !owner.is_owner {
+    return custom_error(
+      Status::Forbidden,
+      "Only the owner can transfer the organization",
+    );
+  }
+  // The new owner must already be a member
+  let new_owner = match user_collection
+    .find_with_email(&body.new_owner_email)
+    .await?
+  {
+    Some(new_owner) => new_owner,
+    None => return custom_error(Status::NotFound, "Member not found"),
+  };
+  if new_owner.id == user_id {
+    return custom_error(
+      Status::BadRequest,
+      "You are already the owner of this organization",
+    );
+  }
+  let new_owner_member = org_member_coll
+    .get_user_in_org(&org_id, &new_owner.id)
+    .await?;
+  if new_owner_member.is_none() {
+    return custom_error(Status::NotFound, "Member not found");
+  }
+  let role = CommonCollection::<OrganizationRole>::new(db)
+    .get_with_id_and_org(&org_id, &role_id)
+    .await?;
+  if role.is_none() {
+    return custom_error(Status::NotFound, "Role not found");
+  }
+  let mut session = start_transaction(db.client()).await?;
+  let transferred = org_member_coll
+    .transfer_ownership(
+      &mut session,
+      &org_id,
+      &user_id,
+      &new_owner.id,
+      &role_id,
+    )
+    .await?;
+  if !transferred {
+    // Dropping the session aborts the transaction
+    return custom_error(
+      Status::Conflict,
+      "The members of the organization changed, please try again",
+    );
+  }
+  let organization = owner.organization;
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &OrganizationTransferOwnershipEvent {
+        id: organization.id.to_string(),
+        name: organization.name.clone(),
+        description: organization.description.clone().unwrap_or_default(),
+        old_owner_id: user.id.to_string(),
+        old_owner_firstname: user.firstname.clone(),
+        old_owner_lastname: user.lastname.clone(),
+        new_owner_id: new_owner.id.to_string(),
+        new_owner_firstname: new_owner.firstname.clone(),
+        new_owner_lastname: new_owner.lastname.clone(),
+      },
+      &mut session,
+    )
+    .await?;
+  commit_transaction(&mut session).await?;
+  info!(
+    "Transferred organization {} from {} to {}",
+    org_id, user_id, new_owner.id
+  );
+  custom_message(Status::Ok, "Organization transferred successfully")
 }
 
 pub(crate) async fn update_logo(
@@ -8,6 +8,7 @@ use crate::route::organization::credentials::aws::dto::{
 use crate::route::{
   custom_error, custom_message, custom_response, ApiResult, SuccessMessage,
 };
+use crate::ENCRYPTION_KEY;
 use bson::oid::ObjectId;
 use mongodb::Database;
 use rocket::http::Status;
@@ -37,7 +38,7 @@ pub(crate) async fn new(
     body.name.clone(),
     body.description.clone(),
     body.access_key.clone(),
-    body.secret_key.clone(),
+    ENCRYPTION_KEY.encrypt(&body.secret_key)?,
   );
   org_cred_aws.insert_one(&to_insert).await?;
   // Return success
@@ -6,6 +6,7 @@ use crate::route::organization::credentials::docker_hub::dto::{
 use crate::route::{
   custom_error, custom_message, custom_response, ApiResult, SuccessMessage,
 };
+use crate::ENCRYPTION_KEY;
 use mongodb::Database;
 use rocket::http::Status;
 use rocket::serde::json::Json;
@@ -34,7 +35,7 @@ pub(crate) async fn new(
     org_id,
     body.name.clone(),
     body.description.clone(),
-    body.access_token.clone(),
+    ENCRYPTION_KEY.encrypt(&body.access_token)?,
   );
   docker_hub_coll.insert_one(&to_insert).await?;
   // Return success
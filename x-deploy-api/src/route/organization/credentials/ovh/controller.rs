@@ -8,6 +8,7 @@ use crate::route::organization::credentials::ovh::dto::{
 use crate::route::{
   custom_error, custom_message, custom_response, ApiResult, SuccessMessage,
 };
+use crate::ENCRYPTION_KEY;
 use bson::oid::ObjectId;
 use mongodb::Database;
 use rocket::http::Status;
@@ -37,8 +38,9 @@ pub async fn new(
     body.name.clone(),
     body.description.clone(),
     body.application_key.clone(),
-    body.application_secret.clone(),
-    body.consumer_key.clone(),
+    ENCRYPTION_KEY.encrypt(&body.application_secret)?,
+    ENCRYPTION_KEY.encrypt(&body.consumer_key)?,
+    body.project_id.clone(),
   );
   org_cred_ovh.insert_one(&to_insert).await?;
   // Return success
@@ -69,6 +71,7 @@ pub async fn get(
         id: credential_db.id.to_string(),
         name: credential_db.name,
         description: credential_db.description,
+        project_id: credential_db.project_id,
         created_at,
       };
       custom_response(Status::Ok, credential_info)
@@ -99,6 +102,7 @@ pub async fn get_all(
       id: credential.id.to_string(),
       name: credential.name,
       description: credential.description,
+      project_id: credential.project_id,
       created_at,
     };
     result.push(credential_info);
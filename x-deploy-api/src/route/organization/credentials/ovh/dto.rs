@@ -10,6 +10,7 @@ use utoipa::ToSchema;
   "applicationKey": "7kbG7Bk7S9Nt7ZSV",
   "applicationSecret": "EXEgWIz07P0HYwtQDs7cNIqCiQaWSuHF",
   "consumerKey": "MtSwSrPpNjqfVSmJhLbPyr2i45lSwPU1",
+  "projectId": "5e8e2b3a1c7d4f6a9b0c1d2e3f4a5b6c",
 }))]
 pub struct NewOvhCredentialsRequest {
   pub name: String,
@@ -17,6 +18,8 @@ pub struct NewOvhCredentialsRequest {
   pub application_key: String,
   pub application_secret: String,
   pub consumer_key: String,
+  /// The public cloud project the clusters are created in
+  pub project_id: String,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
@@ -25,12 +28,14 @@ pub struct NewOvhCredentialsRequest {
   "id": "5f9b3b2b9d3f6c0007f7e7b1",
   "name": "My Ovh Credentials",
   "description": "My Ovh Credentials description",
+  "projectId": "5e8e2b3a1c7d4f6a9b0c1d2e3f4a5b6c",
   "created_at": "2030-10-30T14:00:00.000Z",
 }))]
 pub struct OvhCredentialsInfoResponse {
   pub id: String,
   pub name: String,
   pub description: Option<String>,
+  pub project_id: Option<String>,
   pub created_at: String,
 }
 
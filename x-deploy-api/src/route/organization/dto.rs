@@ -51,13 +51,19 @@ pub(crate) struct UpdateOrganizationRequest {
 
 #[derive(Deserialize, Serialize, Debug, Clone, ToSchema)]
 #[schema(example = json!({
-    "new_owner_email": "john@doe.net",
+    "newOwnerEmail": "john@doe.net",
+    "oldOwnerRoleId": "5f9b3b3b3b3b3b3b3b3b3b3b",
     "password": "myAmazingStringPassword123!"
 }))]
 pub(crate) struct TransferOrganizationRequest {
+  /// Email of a member of the organization
   #[serde(rename = "newOwnerEmail")]
   pub(crate) new_owner_email: String,
 
+  /// Role of the current owner once the organization is transferred
+  #[serde(rename = "oldOwnerRoleId")]
+  pub(crate) old_owner_role_id: String,
+
   #[serde(rename = "password")]
   pub(crate) password: String,
 }
@@ -13,6 +13,7 @@ use rocket::http::Status;
 use rocket::serde::json::Json;
 use rocket::State;
 use std::str::FromStr;
+use x_deploy_common::db::event_outbox::EventOutbox;
 use x_deploy_common::db::organization_invitation::{
   InvitationStatus, OrganizationInvitation,
 };
@@ -21,7 +22,10 @@ use x_deploy_common::db::organization_role::{
   OrganizationRole, StandardPermission,
 };
 use x_deploy_common::db::user::User;
-use x_deploy_common::db::CommonCollection;
+use x_deploy_common::db::{
+  commit_transaction, start_transaction, CommonCollection,
+};
+use x_deploy_common::event::organization::OrganizationInvitationCreatedEvent;
 
 pub async fn get_all(
   db: &State<Database>,
@@ -111,7 +115,22 @@ pub async fn invite_user(
   // Add invitation to database
   let org_invitation =
     OrganizationInvitation::new(org_id, user_id, user_target.id, org_role.id);
-  org_invitation_coll.insert_one(&org_invitation).await?;
+  let mut session = start_transaction(db.client()).await?;
+  org_invitation_coll
+    .insert_one_with_session(&org_invitation, &mut session)
+    .await?;
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &OrganizationInvitationCreatedEvent {
+        id: org_invitation.id.to_string(),
+        organization_id: org_id.to_string(),
+        sender_id: user_id.to_string(),
+        receiver_id: user_target.id.to_string(),
+      },
+      &mut session,
+    )
+    .await?;
+  commit_transaction(&mut session).await?;
   custom_message(Status::Created, "Invitation sent")
 }
 
@@ -156,7 +156,27 @@ pub(crate) async fn delete(
   controller::delete(db, token, id, body).await
 }
 
-#[deprecated]
+#[utoipa::path(
+    post,
+    operation_id = "Restore Organization",
+    path = "/organization/<id>/restore",
+    tag = "Organization",
+    security(
+      ("bearer" = []),
+    ),
+    responses(
+        (status = 200, description = "Restore a deleted organization during its restore window", body = SuccessMessage),
+    ),
+)]
+#[post("/organization/<id>/restore")]
+pub(crate) async fn restore(
+  db: &State<Database>,
+  token: BearerToken,
+  id: String,
+) -> ApiResult<SuccessMessage> {
+  controller::restore(db, token, id).await
+}
+
 #[utoipa::path(
     post,
     operation_id = "Transfer Organization",
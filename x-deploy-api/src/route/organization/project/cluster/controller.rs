@@ -1,6 +1,7 @@
 use crate::guard::auth::Auth;
+use crate::permission::cluster::ClusterPermission;
 use crate::route::organization::project::cluster::dto::{
-  ClusterInfoResponse, CreateClusterRequest,
+  ClusterInfoResponse, CreateClusterRequest, UpdateClusterRequest,
 };
 use crate::route::{
   custom_error, custom_message, custom_response, ApiResult, SuccessMessage,
@@ -11,12 +12,23 @@ use rocket::http::Status;
 use rocket::serde::json::Json;
 use rocket::State;
 use std::str::FromStr;
+use validator::Validate;
 use x_deploy_common::data::cloud_provider::CloudProviderType;
+use x_deploy_common::db::event_outbox::EventOutbox;
+use x_deploy_common::db::organization_credential_aws::OrganizationCredentialAws;
 use x_deploy_common::db::organization_credential_ovh::OrganizationCredentialOvh;
+use x_deploy_common::db::organization_project::OrganizationProject;
 use x_deploy_common::db::organization_project_cluster::{
   ClusterStatus, OrganizationProjectCluster,
 };
-use x_deploy_common::db::CommonCollection;
+use x_deploy_common::db::organization_project_environment::OrganizationProjectEnvironment;
+use x_deploy_common::db::organization_role::ClusterPermission as CommonClusterPermission;
+use x_deploy_common::db::{
+  commit_transaction, start_transaction, CommonCollection,
+};
+use x_deploy_common::event::cluster::{
+  ClusterCreatedEvent, ClusterDeleteRequestedEvent, ClusterStatusChangedEvent,
+};
 
 pub(crate) async fn new(
   db: &State<Database>,
@@ -25,18 +37,45 @@ pub(crate) async fn new(
   project_id: &str,
   body: Json<CreateClusterRequest>,
 ) -> ApiResult<SuccessMessage> {
-  // TODO: Add permission check
   let body = body.into_inner();
   let org_id = ObjectId::from_str(org_id)?;
   let cred_id = ObjectId::from_str(&body.credential_id)?;
   let project_id = ObjectId::from_str(project_id)?;
+
+  ClusterPermission
+    .verify_auth(db, auth, &org_id, CommonClusterPermission::FullAccess)
+    .await?;
+
   let cp_type = CloudProviderType::from_str(&body.cloud_provider)?;
-  if cp_type == CloudProviderType::Aws {
-    return custom_error(Status::BadRequest, "AWS is not supported yet");
+  // Verify project exists in organization
+  let project_coll = CommonCollection::<OrganizationProject>::new(db);
+  if let None = project_coll
+    .get_with_id_of_org(&project_id, &org_id)
+    .await?
+  {
+    return custom_error(Status::NotFound, "Project not found");
   }
-  let ovh_cred_coll = CommonCollection::<OrganizationCredentialOvh>::new(db);
-  let ovh_cred = match ovh_cred_coll.get_by_id(&cred_id).await? {
-    Some(ovh_cred) => ovh_cred,
+  // Verify credential exists for the cloud provider
+  let credential_id = match cp_type {
+    CloudProviderType::Aws => {
+      let aws_cred_coll =
+        CommonCollection::<OrganizationCredentialAws>::new(db);
+      aws_cred_coll
+        .get_by_id_and_org_id(&cred_id, &org_id)
+        .await?
+        .map(|aws_cred| aws_cred.id)
+    }
+    CloudProviderType::Ovh => {
+      let ovh_cred_coll =
+        CommonCollection::<OrganizationCredentialOvh>::new(db);
+      ovh_cred_coll
+        .get_by_id_and_org_id(&cred_id, &org_id)
+        .await?
+        .map(|ovh_cred| ovh_cred.id)
+    }
+  };
+  let credential_id = match credential_id {
+    Some(credential_id) => credential_id,
     None => {
       return custom_error(
         Status::NotFound,
@@ -50,11 +89,30 @@ pub(crate) async fn new(
     body.name,
     body.description,
     cp_type.to_string(),
-    ovh_cred.id,
+    credential_id,
     ClusterStatus::Creating,
   );
   let cluster_coll = CommonCollection::<OrganizationProjectCluster>::new(db);
-  cluster_coll.insert_one(&new_cluster).await?;
+  let mut session = start_transaction(db.client()).await?;
+  cluster_coll
+    .insert_one_with_session(&new_cluster, &mut session)
+    .await?;
+
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &ClusterCreatedEvent {
+        id: new_cluster.id.to_string(),
+        organization_id: org_id.to_string(),
+        project_id: project_id.to_string(),
+        name: new_cluster.name.clone(),
+        cloud_provider: new_cluster.cloud_provider.clone(),
+        credential_id: new_cluster.credential_id.to_string(),
+      },
+      &mut session,
+    )
+    .await?;
+  commit_transaction(&mut session).await?;
+
   custom_message(Status::Created, "Your cluster is being created")
 }
 
@@ -64,9 +122,13 @@ pub(crate) async fn get_all(
   org_id: &str,
   project_id: &str,
 ) -> ApiResult<Vec<ClusterInfoResponse>> {
-  // TODO: Add permission check
   let org_id = ObjectId::from_str(org_id)?;
   let project_id = ObjectId::from_str(project_id)?;
+
+  ClusterPermission
+    .verify_auth(db, auth, &org_id, CommonClusterPermission::ReadEnvironment)
+    .await?;
+
   let cluster_coll = CommonCollection::<OrganizationProjectCluster>::new(db);
   let clusters = cluster_coll
     .get_of_org_and_project(&org_id, &project_id)
@@ -86,11 +148,14 @@ pub(crate) async fn get(
   project_id: &str,
   cluster_id: &str,
 ) -> ApiResult<ClusterInfoResponse> {
-  // TODO: Add permission check
   let org_id = ObjectId::from_str(org_id)?;
   let project_id = ObjectId::from_str(project_id)?;
   let cluster_id = ObjectId::from_str(cluster_id)?;
 
+  ClusterPermission
+    .verify_auth(db, auth, &org_id, CommonClusterPermission::ReadEnvironment)
+    .await?;
+
   let cluster_coll = CommonCollection::<OrganizationProjectCluster>::new(db);
   let cluster = cluster_coll
     .get_with_id_of_project(&org_id, &project_id, &cluster_id)
@@ -105,3 +170,132 @@ pub(crate) async fn get(
     }
   };
 }
+
+pub(crate) async fn update(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  cluster_id: &str,
+  body: Json<UpdateClusterRequest>,
+) -> ApiResult<SuccessMessage> {
+  body.validate()?;
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let cluster_id = ObjectId::from_str(cluster_id)?;
+
+  ClusterPermission
+    .verify_auth(db, auth, &org_id, CommonClusterPermission::FullAccess)
+    .await?;
+
+  let cluster_coll = CommonCollection::<OrganizationProjectCluster>::new(db);
+  let cluster = match cluster_coll
+    .get_with_id_of_project(&org_id, &project_id, &cluster_id)
+    .await?
+  {
+    Some(cluster) => cluster,
+    None => {
+      return custom_error(
+        Status::NotFound,
+        "The cluster you requested does not exist",
+      )
+    }
+  };
+  cluster_coll
+    .update_info(&cluster.id, &body.name, &body.description)
+    .await?;
+  custom_message(Status::Ok, "Your cluster was successfully updated")
+}
+
+pub(crate) async fn delete(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  cluster_id: &str,
+) -> ApiResult<SuccessMessage> {
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let cluster_id = ObjectId::from_str(cluster_id)?;
+
+  ClusterPermission
+    .verify_auth(db, auth, &org_id, CommonClusterPermission::FullAccess)
+    .await?;
+
+  let cluster_coll = CommonCollection::<OrganizationProjectCluster>::new(db);
+  let cluster = match cluster_coll
+    .get_with_id_of_project(&org_id, &project_id, &cluster_id)
+    .await?
+  {
+    Some(cluster) => cluster,
+    None => {
+      return custom_error(
+        Status::NotFound,
+        "The cluster you requested does not exist",
+      )
+    }
+  };
+  match cluster.status {
+    ClusterStatus::Creating => {
+      return custom_error(
+        Status::Conflict,
+        "The cluster is still being created, retry once it is running",
+      )
+    }
+    ClusterStatus::Deleting => {
+      return custom_error(
+        Status::Conflict,
+        "The cluster is already being deleted",
+      )
+    }
+    ClusterStatus::Running | ClusterStatus::Error => {}
+  }
+  // Verify no environment is still bound to the cluster
+  let env_coll = CommonCollection::<OrganizationProjectEnvironment>::new(db);
+  let environments = env_coll.count_of_cluster(&cluster.id).await?;
+  if environments > 0 {
+    let error_message = format!(
+      "Cannot delete cluster because it still holds {} environments",
+      environments
+    );
+    return custom_error(Status::BadRequest, error_message.as_str());
+  }
+  // The daemon removes the document once the cloud resources are gone
+  let mut session = start_transaction(db.client()).await?;
+  cluster_coll
+    .update_status_with_session(
+      &cluster.id,
+      ClusterStatus::Deleting,
+      &mut session,
+    )
+    .await?;
+
+  let outbox_coll = CommonCollection::<EventOutbox>::new(db);
+  outbox_coll
+    .add_with_session(
+      &ClusterStatusChangedEvent {
+        id: cluster.id.to_string(),
+        organization_id: org_id.to_string(),
+        project_id: project_id.to_string(),
+        status: ClusterStatus::Deleting.to_string(),
+      },
+      &mut session,
+    )
+    .await?;
+  outbox_coll
+    .add_with_session(
+      &ClusterDeleteRequestedEvent {
+        id: cluster.id.to_string(),
+        organization_id: org_id.to_string(),
+        project_id: project_id.to_string(),
+        name: cluster.name.clone(),
+        cloud_provider: cluster.cloud_provider.clone(),
+        credential_id: cluster.credential_id.to_string(),
+      },
+      &mut session,
+    )
+    .await?;
+  commit_transaction(&mut session).await?;
+
+  custom_message(Status::Accepted, "Your cluster is being deleted")
+}
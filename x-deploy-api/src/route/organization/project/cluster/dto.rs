@@ -20,6 +20,18 @@ pub struct CreateClusterRequest {
   pub credential_id: String,
 }
 
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "name": "My Cluster",
+    "description": "My Cluster Description"
+}))]
+pub struct UpdateClusterRequest {
+  #[validate(length(min = 1, max = 64, message = "Your name is invalid"))]
+  pub name: String,
+  pub description: Option<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 #[schema(example = json!({
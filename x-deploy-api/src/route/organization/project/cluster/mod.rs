@@ -1,6 +1,6 @@
 use crate::guard::auth::Auth;
 use crate::route::organization::project::cluster::dto::{
-  ClusterInfoResponse, CreateClusterRequest,
+  ClusterInfoResponse, CreateClusterRequest, UpdateClusterRequest,
 };
 use crate::route::{ApiResult, SuccessMessage};
 use mongodb::Database;
@@ -10,7 +10,6 @@ use rocket::State;
 mod controller;
 pub mod dto;
 
-#[deprecated]
 #[utoipa::path(
   post,
   operation_id = "Create a new cluster",
@@ -36,7 +35,6 @@ pub async fn new(
   controller::new(db, auth, org_id, project_id, body).await
 }
 
-#[deprecated]
 #[utoipa::path(
   get,
   operation_id = "Get all clusters of a project",
@@ -63,7 +61,6 @@ pub async fn get_all(
   controller::get_all(db, auth, org_id, project_id).await
 }
 
-#[deprecated]
 #[utoipa::path(
   get,
   operation_id = "Get a cluster of a project",
@@ -90,3 +87,60 @@ pub async fn get(
 ) -> ApiResult<ClusterInfoResponse> {
   controller::get(db, auth, org_id, project_id, cluster_id).await
 }
+
+#[utoipa::path(
+  patch,
+  operation_id = "Update a cluster of a project",
+  path = "/organization/<org_id>/project/<project_id>/cluster/<cluster_id>",
+  tag = "Organization Project Clusters",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 200, description = "Successfully updated cluster", body = SuccessMessage),
+  ),
+  request_body = UpdateClusterRequest,
+)]
+#[patch(
+  "/organization/<org_id>/project/<project_id>/cluster/<cluster_id>",
+  format = "application/json",
+  data = "<body>"
+)]
+pub async fn update(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  cluster_id: &str,
+  body: Json<UpdateClusterRequest>,
+) -> ApiResult<SuccessMessage> {
+  controller::update(db, auth, org_id, project_id, cluster_id, body).await
+}
+
+#[utoipa::path(
+  delete,
+  operation_id = "Delete a cluster of a project",
+  path = "/organization/<org_id>/project/<project_id>/cluster/<cluster_id>",
+  tag = "Organization Project Clusters",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 202, description = "The cluster is being deleted", body = SuccessMessage),
+  ),
+)]
+#[delete(
+  "/organization/<org_id>/project/<project_id>/cluster/<cluster_id>",
+  format = "application/json"
+)]
+pub async fn delete(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  cluster_id: &str,
+) -> ApiResult<SuccessMessage> {
+  controller::delete(db, auth, org_id, project_id, cluster_id).await
+}
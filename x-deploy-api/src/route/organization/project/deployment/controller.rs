@@ -0,0 +1,292 @@
+use crate::guard::auth::Auth;
+use crate::permission::environment::EnvironmentPermission;
+use crate::permission::general::GeneralPermission;
+use crate::route::organization::project::deployment::dto::{
+  CreateDeploymentRequest, DeploymentInfoResponse, RolloutDeploymentRequest,
+  UpdateDeploymentRequest,
+};
+use crate::route::{
+  custom_error, custom_message, custom_response, ApiResult, SuccessMessage,
+};
+use bson::oid::ObjectId;
+use mongodb::Database;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+use std::str::FromStr;
+use validator::Validate;
+use x_deploy_common::db::event_outbox::EventOutbox;
+use x_deploy_common::db::organization_project::OrganizationProject;
+use x_deploy_common::db::organization_project_deployment::OrganizationProjectDeployment;
+use x_deploy_common::db::organization_project_environment::OrganizationProjectEnvironment;
+use x_deploy_common::db::organization_role::EnvironmentPermission as CommonEnvironmentPermission;
+use x_deploy_common::db::organization_role::StandardPermission;
+use x_deploy_common::db::{
+  commit_transaction, start_transaction, CommonCollection,
+};
+use x_deploy_common::event::deployment::DeploymentRolloutRequestedEvent;
+
+pub(crate) async fn new(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  body: Json<CreateDeploymentRequest>,
+) -> ApiResult<SuccessMessage> {
+  body.validate()?;
+  let body = body.into_inner();
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let environment_id = ObjectId::from_str(&body.environment_id)?;
+
+  EnvironmentPermission::new(environment_id)
+    .verify_auth(db, auth, &org_id, CommonEnvironmentPermission::Deploy)
+    .await?;
+
+  // Verify project exists in organization
+  let project_coll = CommonCollection::<OrganizationProject>::new(db);
+  if let None = project_coll
+    .get_with_id_of_org(&project_id, &org_id)
+    .await?
+  {
+    return custom_error(Status::NotFound, "Project not found");
+  }
+  // Verify environment belongs to the project
+  let env_coll = CommonCollection::<OrganizationProjectEnvironment>::new(db);
+  let environment = match env_coll
+    .get_with_id_of_project(&org_id, &project_id, &environment_id)
+    .await?
+  {
+    Some(environment) => environment,
+    None => {
+      return custom_error(
+        Status::NotFound,
+        "The environment you provided does not exist in this project",
+      )
+    }
+  };
+  let new_deployment = OrganizationProjectDeployment::new(
+    org_id,
+    project_id,
+    environment.id,
+    environment.cluster_id,
+    body.name,
+    body.description,
+    body.image_name,
+    body.image_tag,
+  );
+  let deployment_coll =
+    CommonCollection::<OrganizationProjectDeployment>::new(db);
+  deployment_coll.insert_one(&new_deployment).await?;
+  custom_message(Status::Created, "Your deployment was successfully created")
+}
+
+pub(crate) async fn get_all(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+) -> ApiResult<Vec<DeploymentInfoResponse>> {
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+
+  let role = GeneralPermission::Project
+    .verify_auth(db, auth, &org_id, StandardPermission::None)
+    .await?;
+
+  let deployment_coll =
+    CommonCollection::<OrganizationProjectDeployment>::new(db);
+  let deployments =
+    deployment_coll.get_of_project(&org_id, &project_id).await?;
+  let mut response: Vec<DeploymentInfoResponse> = Vec::new();
+  for deployment in deployments {
+    // Only list deployments of environments the role can read
+    if !EnvironmentPermission::new(deployment.environment_id)
+      .has_environment_permission(&role, &CommonEnvironmentPermission::Read)
+    {
+      continue;
+    }
+    let deployment_info: DeploymentInfoResponse = deployment.into();
+    response.push(deployment_info);
+  }
+  custom_response(Status::Ok, response)
+}
+
+pub(crate) async fn get(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  deployment_id: &str,
+) -> ApiResult<DeploymentInfoResponse> {
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let deployment_id = ObjectId::from_str(deployment_id)?;
+
+  let role = GeneralPermission::Project
+    .verify_auth(db, auth, &org_id, StandardPermission::None)
+    .await?;
+
+  let deployment_coll =
+    CommonCollection::<OrganizationProjectDeployment>::new(db);
+  let deployment = deployment_coll
+    .get_with_id_of_project(&org_id, &project_id, &deployment_id)
+    .await?;
+  return match deployment {
+    Some(deployment) => {
+      EnvironmentPermission::new(deployment.environment_id)
+        .verify(&role, &CommonEnvironmentPermission::Read)?;
+      let deployment_info: DeploymentInfoResponse = deployment.into();
+      custom_response(Status::Ok, deployment_info)
+    }
+    None => custom_error(
+      Status::NotFound,
+      "The deployment you requested does not exist",
+    ),
+  };
+}
+
+pub(crate) async fn update(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  deployment_id: &str,
+  body: Json<UpdateDeploymentRequest>,
+) -> ApiResult<SuccessMessage> {
+  body.validate()?;
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let deployment_id = ObjectId::from_str(deployment_id)?;
+
+  let role = GeneralPermission::Project
+    .verify_auth(db, auth, &org_id, StandardPermission::None)
+    .await?;
+
+  let deployment_coll =
+    CommonCollection::<OrganizationProjectDeployment>::new(db);
+  let deployment = match deployment_coll
+    .get_with_id_of_project(&org_id, &project_id, &deployment_id)
+    .await?
+  {
+    Some(deployment) => deployment,
+    None => {
+      return custom_error(
+        Status::NotFound,
+        "The deployment you requested does not exist",
+      )
+    }
+  };
+  EnvironmentPermission::new(deployment.environment_id)
+    .verify(&role, &CommonEnvironmentPermission::Manage)?;
+  deployment_coll
+    .update_info(&deployment.id, &body.name, &body.description)
+    .await?;
+  custom_message(Status::Ok, "Your deployment was successfully updated")
+}
+
+pub(crate) async fn rollout(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  deployment_id: &str,
+  body: Json<RolloutDeploymentRequest>,
+) -> ApiResult<SuccessMessage> {
+  body.validate()?;
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let deployment_id = ObjectId::from_str(deployment_id)?;
+
+  let role = GeneralPermission::Project
+    .verify_auth(db, auth, &org_id, StandardPermission::None)
+    .await?;
+
+  let deployment_coll =
+    CommonCollection::<OrganizationProjectDeployment>::new(db);
+  let deployment = match deployment_coll
+    .get_with_id_of_project(&org_id, &project_id, &deployment_id)
+    .await?
+  {
+    Some(deployment) => deployment,
+    None => {
+      return custom_error(
+        Status::NotFound,
+        "The deployment you requested does not exist",
+      )
+    }
+  };
+  EnvironmentPermission::new(deployment.environment_id)
+    .verify(&role, &CommonEnvironmentPermission::Deploy)?;
+  if deployment.image_name == body.image_name
+    && deployment.image_tag == body.image_tag
+  {
+    return custom_error(
+      Status::BadRequest,
+      "This image is already deployed for this deployment",
+    );
+  }
+  let mut session = start_transaction(db.client()).await?;
+  deployment_coll
+    .update_image_with_session(
+      &deployment.id,
+      &body.image_name,
+      &body.image_tag,
+      &mut session,
+    )
+    .await?;
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &DeploymentRolloutRequestedEvent {
+        id: deployment.id.to_string(),
+        organization_id: org_id.to_string(),
+        project_id: project_id.to_string(),
+        environment_id: deployment.environment_id.to_string(),
+        cluster_id: deployment.cluster_id.to_string(),
+        image_name: body.image_name.clone(),
+        image_tag: body.image_tag.clone(),
+      },
+      &mut session,
+    )
+    .await?;
+  commit_transaction(&mut session).await?;
+  custom_message(Status::Accepted, "Your new image is being rolled out")
+}
+
+pub(crate) async fn delete(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  deployment_id: &str,
+) -> ApiResult<SuccessMessage> {
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let deployment_id = ObjectId::from_str(deployment_id)?;
+
+  let role = GeneralPermission::Project
+    .verify_auth(db, auth, &org_id, StandardPermission::None)
+    .await?;
+
+  let deployment_coll =
+    CommonCollection::<OrganizationProjectDeployment>::new(db);
+  let deployment = match deployment_coll
+    .get_with_id_of_project(&org_id, &project_id, &deployment_id)
+    .await?
+  {
+    Some(deployment) => deployment,
+    None => {
+      return custom_error(
+        Status::NotFound,
+        "The deployment you requested does not exist",
+      )
+    }
+  };
+  EnvironmentPermission::new(deployment.environment_id)
+    .verify(&role, &CommonEnvironmentPermission::Manage)?;
+  let result = deployment_coll.delete_by_id(&deployment.id).await?;
+  if result.deleted_count == 0 {
+    return custom_error(Status::InternalServerError, "Deployment not deleted");
+  }
+  custom_message(Status::Ok, "Your deployment was successfully deleted")
+}
@@ -0,0 +1,97 @@
+use chrono::SecondsFormat;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+use validator::Validate;
+use x_deploy_common::db::organization_project_deployment::OrganizationProjectDeployment;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "name": "My Deployment",
+    "description": "My Deployment Description",
+    "imageName": "nginx",
+    "imageTag": "1.25.3",
+    "environmentId": "5f9b3b3b9c6d2b0007f1b3b3"
+}))]
+pub struct CreateDeploymentRequest {
+  #[validate(length(min = 1, max = 64, message = "Your name is invalid"))]
+  pub name: String,
+  pub description: Option<String>,
+  #[validate(length(min = 1, message = "Your image name is invalid"))]
+  pub image_name: String,
+  #[validate(length(min = 1, message = "Your image tag is invalid"))]
+  pub image_tag: String,
+  pub environment_id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "name": "My Deployment",
+    "description": "My Deployment Description",
+}))]
+pub struct UpdateDeploymentRequest {
+  #[validate(length(min = 1, max = 64, message = "Your name is invalid"))]
+  pub name: String,
+  pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "imageName": "nginx",
+    "imageTag": "1.25.4",
+}))]
+pub struct RolloutDeploymentRequest {
+  #[validate(length(min = 1, message = "Your image name is invalid"))]
+  pub image_name: String,
+  #[validate(length(min = 1, message = "Your image tag is invalid"))]
+  pub image_tag: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "id": "5f9b3b3b9c6d2b0007f1b3b3",
+    "name": "My Deployment",
+    "description": "My Deployment Description",
+    "imageName": "nginx",
+    "imageTag": "1.25.3",
+    "environmentId": "5f9b3b3b9c6d2b0007f1b3b3",
+    "clusterId": "5f9b3b3b9c6d2b0007f1b3b3",
+    "projectId": "5f9b3b3b9c6d2b0007f1b3b3",
+    "createdAt": "2020-10-29T15:00:00Z",
+}))]
+pub struct DeploymentInfoResponse {
+  pub id: String,
+  pub name: String,
+  pub description: Option<String>,
+  pub image_name: String,
+  pub image_tag: String,
+  pub environment_id: String,
+  pub cluster_id: String,
+  pub project_id: String,
+  pub created_at: String,
+}
+
+impl Into<DeploymentInfoResponse> for OrganizationProjectDeployment {
+  fn into(self) -> DeploymentInfoResponse {
+    let created_at_str = self
+      .id
+      .timestamp()
+      .to_chrono()
+      .to_rfc3339_opts(SecondsFormat::Secs, true);
+    DeploymentInfoResponse {
+      id: self.id.to_string(),
+      name: self.name,
+      description: self.description,
+      image_name: self.image_name,
+      image_tag: self.image_tag,
+      environment_id: self.environment_id.to_string(),
+      cluster_id: self.cluster_id.to_string(),
+      project_id: self.project_id.to_string(),
+      created_at: created_at_str,
+    }
+  }
+}
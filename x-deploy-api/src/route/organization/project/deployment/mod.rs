@@ -1,2 +1,181 @@
+use crate::guard::auth::Auth;
+use crate::route::organization::project::deployment::dto::{
+  CreateDeploymentRequest, DeploymentInfoResponse, RolloutDeploymentRequest,
+  UpdateDeploymentRequest,
+};
+use crate::route::{ApiResult, SuccessMessage};
+use mongodb::Database;
+use rocket::serde::json::Json;
+use rocket::State;
+
 mod controller;
-pub mod dto;
\ No newline at end of file
+pub mod dto;
+
+#[utoipa::path(
+  post,
+  operation_id = "Create a new deployment",
+  path = "/organization/<org_id>/project/<project_id>/deployment",
+  tag = "Organization Project Deployments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 201, description = "Create a new deployment", body = SuccessMessage),
+  ),
+  request_body = CreateDeploymentRequest,
+)]
+#[post(
+  "/organization/<org_id>/project/<project_id>/deployment",
+  format = "application/json",
+  data = "<body>"
+)]
+pub async fn new(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  body: Json<CreateDeploymentRequest>,
+) -> ApiResult<SuccessMessage> {
+  controller::new(db, auth, org_id, project_id, body).await
+}
+
+#[utoipa::path(
+  get,
+  operation_id = "Get all deployments of a project",
+  path = "/organization/<org_id>/project/<project_id>/deployment",
+  tag = "Organization Project Deployments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 200, description = "Get all deployments of a project", body = Vec<DeploymentInfoResponse>),
+  ),
+)]
+#[get(
+  "/organization/<org_id>/project/<project_id>/deployment",
+  format = "application/json"
+)]
+pub async fn get_all(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+) -> ApiResult<Vec<DeploymentInfoResponse>> {
+  controller::get_all(db, auth, org_id, project_id).await
+}
+
+#[utoipa::path(
+  get,
+  operation_id = "Get a deployment of a project",
+  path = "/organization/<org_id>/project/<project_id>/deployment/<deployment_id>",
+  tag = "Organization Project Deployments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 200, description = "Get a deployment of a project", body = DeploymentInfoResponse),
+  ),
+)]
+#[get(
+  "/organization/<org_id>/project/<project_id>/deployment/<deployment_id>",
+  format = "application/json"
+)]
+pub async fn get(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  deployment_id: &str,
+) -> ApiResult<DeploymentInfoResponse> {
+  controller::get(db, auth, org_id, project_id, deployment_id).await
+}
+
+#[utoipa::path(
+  patch,
+  operation_id = "Update a deployment of a project",
+  path = "/organization/<org_id>/project/<project_id>/deployment/<deployment_id>",
+  tag = "Organization Project Deployments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 200, description = "Successfully updated deployment", body = SuccessMessage),
+  ),
+  request_body = UpdateDeploymentRequest,
+)]
+#[patch(
+  "/organization/<org_id>/project/<project_id>/deployment/<deployment_id>",
+  format = "application/json",
+  data = "<body>"
+)]
+pub async fn update(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  deployment_id: &str,
+  body: Json<UpdateDeploymentRequest>,
+) -> ApiResult<SuccessMessage> {
+  controller::update(db, auth, org_id, project_id, deployment_id, body).await
+}
+
+#[utoipa::path(
+  post,
+  operation_id = "Rollout a new image for a deployment",
+  path = "/organization/<org_id>/project/<project_id>/deployment/<deployment_id>/rollout",
+  tag = "Organization Project Deployments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 202, description = "The new image is being rolled out", body = SuccessMessage),
+  ),
+  request_body = RolloutDeploymentRequest,
+)]
+#[post(
+  "/organization/<org_id>/project/<project_id>/deployment/<deployment_id>/rollout",
+  format = "application/json",
+  data = "<body>"
+)]
+pub async fn rollout(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  deployment_id: &str,
+  body: Json<RolloutDeploymentRequest>,
+) -> ApiResult<SuccessMessage> {
+  controller::rollout(db, auth, org_id, project_id, deployment_id, body).await
+}
+
+#[utoipa::path(
+  delete,
+  operation_id = "Delete a deployment of a project",
+  path = "/organization/<org_id>/project/<project_id>/deployment/<deployment_id>",
+  tag = "Organization Project Deployments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 200, description = "Successfully deleted deployment", body = SuccessMessage),
+  ),
+)]
+#[delete(
+  "/organization/<org_id>/project/<project_id>/deployment/<deployment_id>",
+  format = "application/json"
+)]
+pub async fn delete(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  deployment_id: &str,
+) -> ApiResult<SuccessMessage> {
+  controller::delete(db, auth, org_id, project_id, deployment_id).await
+}
@@ -0,0 +1,238 @@
+use crate::guard::auth::Auth;
+use crate::permission::environment::EnvironmentPermission;
+use crate::permission::general::GeneralPermission;
+use crate::route::organization::project::environment::dto::{
+  CreateEnvironmentRequest, EnvironmentInfoResponse, UpdateEnvironmentRequest,
+};
+use crate::route::{
+  custom_error, custom_message, custom_response, ApiResult, SuccessMessage,
+};
+use bson::oid::ObjectId;
+use mongodb::Database;
+use rocket::http::Status;
+use rocket::serde::json::Json;
+use rocket::State;
+use std::str::FromStr;
+use validator::Validate;
+use x_deploy_common::db::organization_project_cluster::OrganizationProjectCluster;
+use x_deploy_common::db::organization_project_deployment::OrganizationProjectDeployment;
+use x_deploy_common::db::organization_project_environment::OrganizationProjectEnvironment;
+use x_deploy_common::db::organization_role::EnvironmentPermission as CommonEnvironmentPermission;
+use x_deploy_common::db::organization_role::{
+  OrganizationRole, StandardPermission,
+};
+use x_deploy_common::db::CommonCollection;
+
+pub(crate) async fn new(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  body: Json<CreateEnvironmentRequest>,
+) -> ApiResult<SuccessMessage> {
+  body.validate()?;
+  let body = body.into_inner();
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let cluster_id = ObjectId::from_str(&body.cluster_id)?;
+
+  GeneralPermission::Project
+    .verify_auth(db, auth, &org_id, StandardPermission::ReadWrite)
+    .await?;
+
+  // Verify cluster belongs to the project
+  let cluster_coll = CommonCollection::<OrganizationProjectCluster>::new(db);
+  if let None = cluster_coll
+    .get_with_id_of_project(&org_id, &project_id, &cluster_id)
+    .await?
+  {
+    return custom_error(
+      Status::NotFound,
+      "The cluster you provided does not exist in this project",
+    );
+  }
+  // Verify name is not already used in the project
+  let env_coll = CommonCollection::<OrganizationProjectEnvironment>::new(db);
+  if let Some(_) = env_coll
+    .get_with_name_of_project(&org_id, &project_id, &body.name)
+    .await?
+  {
+    return custom_error(
+      Status::Conflict,
+      "An environment with this name already exists in this project",
+    );
+  }
+  let new_environment = OrganizationProjectEnvironment::new(
+    org_id,
+    project_id,
+    body.name,
+    body.description,
+    cluster_id,
+  );
+  env_coll.insert_one(&new_environment).await?;
+  custom_message(Status::Created, "Your environment was successfully created")
+}
+
+pub(crate) async fn get_all(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+) -> ApiResult<Vec<EnvironmentInfoResponse>> {
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+
+  let role = GeneralPermission::Project
+    .verify_auth(db, auth, &org_id, StandardPermission::None)
+    .await?;
+
+  let env_coll = CommonCollection::<OrganizationProjectEnvironment>::new(db);
+  let environments = env_coll.get_of_project(&org_id, &project_id).await?;
+  let mut response: Vec<EnvironmentInfoResponse> = Vec::new();
+  for environment in environments {
+    // Only list environments the role can read
+    if !EnvironmentPermission::new(environment.id)
+      .has_environment_permission(&role, &CommonEnvironmentPermission::Read)
+    {
+      continue;
+    }
+    let environment_info: EnvironmentInfoResponse = environment.into();
+    response.push(environment_info);
+  }
+  custom_response(Status::Ok, response)
+}
+
+pub(crate) async fn get(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  env_id: &str,
+) -> ApiResult<EnvironmentInfoResponse> {
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let env_id = ObjectId::from_str(env_id)?;
+
+  EnvironmentPermission::new(env_id)
+    .verify_auth(db, auth, &org_id, CommonEnvironmentPermission::Read)
+    .await?;
+
+  let env_coll = CommonCollection::<OrganizationProjectEnvironment>::new(db);
+  let environment = env_coll
+    .get_with_id_of_project(&org_id, &project_id, &env_id)
+    .await?;
+  return match environment {
+    Some(environment) => {
+      let environment_info: EnvironmentInfoResponse = environment.into();
+      custom_response(Status::Ok, environment_info)
+    }
+    None => custom_error(
+      Status::NotFound,
+      "The environment you requested does not exist",
+    ),
+  };
+}
+
+pub(crate) async fn update(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  env_id: &str,
+  body: Json<UpdateEnvironmentRequest>,
+) -> ApiResult<SuccessMessage> {
+  body.validate()?;
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let env_id = ObjectId::from_str(env_id)?;
+
+  EnvironmentPermission::new(env_id)
+    .verify_auth(db, auth, &org_id, CommonEnvironmentPermission::Manage)
+    .await?;
+
+  let env_coll = CommonCollection::<OrganizationProjectEnvironment>::new(db);
+  let environment = match env_coll
+    .get_with_id_of_project(&org_id, &project_id, &env_id)
+    .await?
+  {
+    Some(environment) => environment,
+    None => {
+      return custom_error(
+        Status::NotFound,
+        "The environment you requested does not exist",
+      )
+    }
+  };
+  // Verify the new name is not used by another environment
+  if let Some(other) = env_coll
+    .get_with_name_of_project(&org_id, &project_id, &body.name)
+    .await?
+  {
+    if other.id != environment.id {
+      return custom_error(
+        Status::Conflict,
+        "An environment with this name already exists in this project",
+      );
+    }
+  }
+  env_coll
+    .update_info(&environment.id, &body.name, &body.description)
+    .await?;
+  custom_message(Status::Ok, "Your environment was successfully updated")
+}
+
+pub(crate) async fn delete(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  env_id: &str,
+) -> ApiResult<SuccessMessage> {
+  let org_id = ObjectId::from_str(org_id)?;
+  let project_id = ObjectId::from_str(project_id)?;
+  let env_id = ObjectId::from_str(env_id)?;
+
+  EnvironmentPermission::new(env_id)
+    .verify_auth(db, auth, &org_id, CommonEnvironmentPermission::FullAccess)
+    .await?;
+
+  let env_coll = CommonCollection::<OrganizationProjectEnvironment>::new(db);
+  let environment = match env_coll
+    .get_with_id_of_project(&org_id, &project_id, &env_id)
+    .await?
+  {
+    Some(environment) => environment,
+    None => {
+      return custom_error(
+        Status::NotFound,
+        "The environment you requested does not exist",
+      )
+    }
+  };
+  // Verify no deployment is still running in the environment
+  let deployment_coll =
+    CommonCollection::<OrganizationProjectDeployment>::new(db);
+  let deployments = deployment_coll
+    .count_of_environment(&environment.id)
+    .await?;
+  if deployments > 0 {
+    let error_message = format!(
+      "Cannot delete environment because it still holds {} deployments",
+      deployments
+    );
+    return custom_error(Status::BadRequest, error_message.as_str());
+  }
+  let result = env_coll.delete_by_id(&environment.id).await?;
+  if result.deleted_count == 0 {
+    return custom_error(
+      Status::InternalServerError,
+      "Environment not deleted",
+    );
+  }
+  // Remove the environment from the custom roles
+  let role_coll = CommonCollection::<OrganizationRole>::new(db);
+  role_coll
+    .remove_environment_permission(&org_id, &environment.id)
+    .await?;
+  custom_message(Status::Ok, "Your environment was successfully deleted")
+}
@@ -0,0 +1,69 @@
+use chrono::SecondsFormat;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+use validator::Validate;
+use x_deploy_common::db::organization_project_environment::OrganizationProjectEnvironment;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "name": "staging",
+    "description": "Staging environment of my project",
+    "clusterId": "5f9b3b3b9c6d2b0007f1b3b3"
+}))]
+pub struct CreateEnvironmentRequest {
+  #[validate(length(min = 1, max = 64, message = "Your name is invalid"))]
+  pub name: String,
+  pub description: Option<String>,
+  pub cluster_id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "name": "production",
+    "description": "Production environment of my project",
+}))]
+pub struct UpdateEnvironmentRequest {
+  #[validate(length(min = 1, max = 64, message = "Your name is invalid"))]
+  pub name: String,
+  pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+#[schema(example = json!({
+    "id": "5f9b3b3b9c6d2b0007f1b3b3",
+    "name": "staging",
+    "description": "Staging environment of my project",
+    "clusterId": "5f9b3b3b9c6d2b0007f1b3b3",
+    "projectId": "5f9b3b3b9c6d2b0007f1b3b3",
+    "createdAt": "2020-10-29T15:00:00Z",
+}))]
+pub struct EnvironmentInfoResponse {
+  pub id: String,
+  pub name: String,
+  pub description: Option<String>,
+  pub cluster_id: String,
+  pub project_id: String,
+  pub created_at: String,
+}
+
+impl Into<EnvironmentInfoResponse> for OrganizationProjectEnvironment {
+  fn into(self) -> EnvironmentInfoResponse {
+    let created_at_str = self
+      .id
+      .timestamp()
+      .to_chrono()
+      .to_rfc3339_opts(SecondsFormat::Secs, true);
+    EnvironmentInfoResponse {
+      id: self.id.to_string(),
+      name: self.name,
+      description: self.description,
+      cluster_id: self.cluster_id.to_string(),
+      project_id: self.project_id.to_string(),
+      created_at: created_at_str,
+    }
+  }
+}
@@ -1,2 +1,150 @@
+use crate::guard::auth::Auth;
+use crate::route::organization::project::environment::dto::{
+  CreateEnvironmentRequest, EnvironmentInfoResponse, UpdateEnvironmentRequest,
+};
+use crate::route::{ApiResult, SuccessMessage};
+use mongodb::Database;
+use rocket::serde::json::Json;
+use rocket::State;
+
+mod controller;
 pub mod dto;
-mod controller;
\ No newline at end of file
+
+#[utoipa::path(
+  post,
+  operation_id = "Create a new environment",
+  path = "/organization/<org_id>/project/<project_id>/environment",
+  tag = "Organization Project Environments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 201, description = "Create a new environment", body = SuccessMessage),
+  ),
+  request_body = CreateEnvironmentRequest,
+)]
+#[post(
+  "/organization/<org_id>/project/<project_id>/environment",
+  format = "application/json",
+  data = "<body>"
+)]
+pub async fn new(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  body: Json<CreateEnvironmentRequest>,
+) -> ApiResult<SuccessMessage> {
+  controller::new(db, auth, org_id, project_id, body).await
+}
+
+#[utoipa::path(
+  get,
+  operation_id = "Get all environments of a project",
+  path = "/organization/<org_id>/project/<project_id>/environment",
+  tag = "Organization Project Environments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 200, description = "Get all environments of a project", body = Vec<EnvironmentInfoResponse>),
+  ),
+)]
+#[get(
+  "/organization/<org_id>/project/<project_id>/environment",
+  format = "application/json"
+)]
+pub async fn get_all(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+) -> ApiResult<Vec<EnvironmentInfoResponse>> {
+  controller::get_all(db, auth, org_id, project_id).await
+}
+
+#[utoipa::path(
+  get,
+  operation_id = "Get an environment of a project",
+  path = "/organization/<org_id>/project/<project_id>/environment/<env_id>",
+  tag = "Organization Project Environments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 200, description = "Get an environment of a project", body = EnvironmentInfoResponse),
+  ),
+)]
+#[get(
+  "/organization/<org_id>/project/<project_id>/environment/<env_id>",
+  format = "application/json"
+)]
+pub async fn get(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  env_id: &str,
+) -> ApiResult<EnvironmentInfoResponse> {
+  controller::get(db, auth, org_id, project_id, env_id).await
+}
+
+#[utoipa::path(
+  patch,
+  operation_id = "Update an environment of a project",
+  path = "/organization/<org_id>/project/<project_id>/environment/<env_id>",
+  tag = "Organization Project Environments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 200, description = "Successfully updated environment", body = SuccessMessage),
+  ),
+  request_body = UpdateEnvironmentRequest,
+)]
+#[patch(
+  "/organization/<org_id>/project/<project_id>/environment/<env_id>",
+  format = "application/json",
+  data = "<body>"
+)]
+pub async fn update(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  env_id: &str,
+  body: Json<UpdateEnvironmentRequest>,
+) -> ApiResult<SuccessMessage> {
+  controller::update(db, auth, org_id, project_id, env_id, body).await
+}
+
+#[utoipa::path(
+  delete,
+  operation_id = "Delete an environment of a project",
+  path = "/organization/<org_id>/project/<project_id>/environment/<env_id>",
+  tag = "Organization Project Environments",
+  security(
+    ("bearer" = []),
+    ("apiKey" = []),
+  ),
+  responses(
+    (status = 200, description = "Successfully deleted environment", body = SuccessMessage),
+  ),
+)]
+#[delete(
+  "/organization/<org_id>/project/<project_id>/environment/<env_id>",
+  format = "application/json"
+)]
+pub async fn delete(
+  db: &State<Database>,
+  auth: Auth,
+  org_id: &str,
+  project_id: &str,
+  env_id: &str,
+) -> ApiResult<SuccessMessage> {
+  controller::delete(db, auth, org_id, project_id, env_id).await
+}
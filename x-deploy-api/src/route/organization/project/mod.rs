@@ -11,9 +11,9 @@ use rocket::{Data, State};
 
 pub mod cluster;
 mod controller;
-mod deployment;
+pub mod deployment;
 pub mod dto;
-mod environment;
+pub mod environment;
 
 #[utoipa::path(
   get,
@@ -15,9 +15,11 @@ use rocket::State;
 use std::str::FromStr;
 use x_deploy_common::db::organization_invitation::OrganizationInvitation;
 use x_deploy_common::db::organization_member::OrganizationMember;
+use x_deploy_common::db::organization_project_environment::OrganizationProjectEnvironment;
 use x_deploy_common::db::organization_role::GeneralPermission as CommonGeneralPermission;
 use x_deploy_common::db::organization_role::{
-  ClusterPermission, OrganizationRole, StandardPermission,
+  ClusterPermission, OrganizationRole, RoleEnvironmentPermission,
+  StandardPermission,
 };
 use x_deploy_common::db::CommonCollection;
 
@@ -42,6 +44,7 @@ pub(crate) async fn new(
     organization_id: org_id.clone(),
     cluster_permission: Default::default(),
     general_permission: Default::default(),
+    environment_permissions: Vec::new(),
   };
   collection.insert_one(&to_insert).await?;
 
@@ -188,6 +191,37 @@ pub(crate) async fn update(
       "Role not found in the organization",
     );
   }
+  // Verify every environment belongs to the organization
+  let env_collection =
+    CommonCollection::<OrganizationProjectEnvironment>::new(db);
+  let mut environment_permissions = Vec::<RoleEnvironmentPermission>::new();
+  for access in body.environment_permissions.iter() {
+    let environment_id = ObjectId::from_str(&access.environment_id)?;
+    if let None = env_collection
+      .get_with_id_of_org(&org_id, &environment_id)
+      .await?
+    {
+      let error_message = format!(
+        "Environment {} not found in the organization",
+        access.environment_id
+      );
+      return custom_error(Status::BadRequest, error_message.as_str());
+    }
+    if environment_permissions
+      .iter()
+      .any(|p| p.environment_id == environment_id)
+    {
+      let error_message = format!(
+        "Environment {} is set more than once",
+        access.environment_id
+      );
+      return custom_error(Status::BadRequest, error_message.as_str());
+    }
+    environment_permissions.push(RoleEnvironmentPermission {
+      environment_id,
+      permission: access.permission.clone().into(),
+    });
+  }
   // Update Info
   role_collection
     .update_info(&org_id, &role_id, &body.name, body.description.clone())
@@ -203,6 +237,10 @@ pub(crate) async fn update(
   role_collection
     .update_general_permission(&org_id, &role_id, &general_permission)
     .await?;
+  // Update environment permission
+  role_collection
+    .update_environment_permissions(&org_id, &role_id, &environment_permissions)
+    .await?;
   // Send response
   custom_message(Status::Ok, "Role updated successfully")
 }
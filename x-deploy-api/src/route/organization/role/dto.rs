@@ -2,7 +2,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::json;
 use utoipa::ToSchema;
 use x_deploy_common::db::organization_role::{
-  ClusterPermission, GeneralPermission, OrganizationRole, StandardPermission,
+  ClusterPermission, EnvironmentPermission, GeneralPermission,
+  OrganizationRole, RoleEnvironmentPermission, StandardPermission,
 };
 
 #[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
@@ -23,7 +24,13 @@ pub struct CreateCustomRoleRequest {
     "id": "5f9b3b7b9c6b2b0007f1e7b2",
     "name": "My new role name",
     "description": "My new role description",
-    "clusterPermission": "FULL_ACCESS"
+    "clusterPermission": "FULL_ACCESS",
+    "environmentPermissions": [
+      {
+        "environmentId": "5f9b3b7b9c6b2b0007f1e7b3",
+        "permission": "DEPLOY"
+      }
+    ]
 }))]
 pub struct CustomRoleInfoResponse {
   #[serde(rename = "id")]
@@ -37,6 +44,9 @@ pub struct CustomRoleInfoResponse {
 
   #[serde(rename = "clusterPermission")]
   pub cluster_permission: CustomRoleClusterPermissionInfo,
+
+  #[serde(rename = "environmentPermissions")]
+  pub environment_permissions: Vec<CustomRoleEnvironmentAccessInfo>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
@@ -51,7 +61,13 @@ pub struct CustomRoleInfoResponse {
     "generalProject": "READ_WRITE",
     "generalApiKeys": "READ_WRITE",
     "generalCredentials": "READ_WRITE",
-    "generalRole": "READ_WRITE"
+    "generalRole": "READ_WRITE",
+    "environmentPermissions": [
+      {
+        "environmentId": "5f9b3b7b9c6b2b0007f1e7b3",
+        "permission": "DEPLOY"
+      }
+    ]
 }))]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateCustomRoleRequest {
@@ -68,6 +84,9 @@ pub struct UpdateCustomRoleRequest {
   pub general_api_keys: CustomRoleGeneralPermissionInfo,
   pub general_credentials: CustomRoleGeneralPermissionInfo,
   pub general_role: CustomRoleGeneralPermissionInfo,
+  // Environment permission
+  #[serde(default)]
+  pub environment_permissions: Vec<CustomRoleEnvironmentAccessInfo>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
@@ -97,6 +116,93 @@ impl Into<ClusterPermission> for CustomRoleClusterPermissionInfo {
   }
 }
 
+impl Into<CustomRoleClusterPermissionInfo> for ClusterPermission {
+  fn into(self) -> CustomRoleClusterPermissionInfo {
+    match self {
+      ClusterPermission::FullAccess => {
+        CustomRoleClusterPermissionInfo::FullAccess
+      }
+      ClusterPermission::CreateEnvironment => {
+        CustomRoleClusterPermissionInfo::CreateEnvironment
+      }
+      ClusterPermission::ReadEnvironment => {
+        CustomRoleClusterPermissionInfo::ReadEnvironment
+      }
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
+#[schema(example = json!({
+    "environmentId": "5f9b3b7b9c6b2b0007f1e7b3",
+    "permission": "DEPLOY"
+}))]
+#[serde(rename_all = "camelCase")]
+pub struct CustomRoleEnvironmentAccessInfo {
+  pub environment_id: String,
+  pub permission: CustomRoleEnvironmentPermissionInfo,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[schema(example = json!("DEPLOY"))]
+pub enum CustomRoleEnvironmentPermissionInfo {
+  NoAccess,
+  Read,
+  Deploy,
+  Manage,
+  FullAccess,
+}
+
+impl Into<EnvironmentPermission> for CustomRoleEnvironmentPermissionInfo {
+  fn into(self) -> EnvironmentPermission {
+    match self {
+      CustomRoleEnvironmentPermissionInfo::NoAccess => {
+        EnvironmentPermission::NoAccess
+      }
+      CustomRoleEnvironmentPermissionInfo::Read => EnvironmentPermission::Read,
+      CustomRoleEnvironmentPermissionInfo::Deploy => {
+        EnvironmentPermission::Deploy
+      }
+      CustomRoleEnvironmentPermissionInfo::Manage => {
+        EnvironmentPermission::Manage
+      }
+      CustomRoleEnvironmentPermissionInfo::FullAccess => {
+        EnvironmentPermission::FullAccess
+      }
+    }
+  }
+}
+
+impl Into<CustomRoleEnvironmentPermissionInfo> for EnvironmentPermission {
+  fn into(self) -> CustomRoleEnvironmentPermissionInfo {
+    match self {
+      EnvironmentPermission::NoAccess => {
+        CustomRoleEnvironmentPermissionInfo::NoAccess
+      }
+      EnvironmentPermission::Read => CustomRoleEnvironmentPermissionInfo::Read,
+      EnvironmentPermission::Deploy => {
+        CustomRoleEnvironmentPermissionInfo::Deploy
+      }
+      EnvironmentPermission::Manage => {
+        CustomRoleEnvironmentPermissionInfo::Manage
+      }
+      EnvironmentPermission::FullAccess => {
+        CustomRoleEnvironmentPermissionInfo::FullAccess
+      }
+    }
+  }
+}
+
+impl Into<CustomRoleEnvironmentAccessInfo> for RoleEnvironmentPermission {
+  fn into(self) -> CustomRoleEnvironmentAccessInfo {
+    CustomRoleEnvironmentAccessInfo {
+      environment_id: self.environment_id.to_string(),
+      permission: self.permission.into(),
+    }
+  }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[schema(example = json!("READ_WRITE"))]
@@ -126,7 +232,12 @@ impl Into<CustomRoleInfoResponse> for OrganizationRole {
       id: self.id.to_string(),
       name: self.name,
       description: self.description,
-      cluster_permission: CustomRoleClusterPermissionInfo::FullAccess,
+      cluster_permission: self.cluster_permission.into(),
+      environment_permissions: self
+        .environment_permissions
+        .into_iter()
+        .map(|permission| permission.into())
+        .collect(),
     }
   }
 }
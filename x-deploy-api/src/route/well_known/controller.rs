@@ -0,0 +1,8 @@
+use crate::route::{custom_response, ApiResult};
+use crate::JWT_KEYRING;
+use jsonwebtoken::jwk::JwkSet;
+use rocket::http::Status;
+
+pub(crate) async fn jwks() -> ApiResult<JwkSet> {
+  custom_response(Status::Ok, JWT_KEYRING.jwks().clone())
+}
@@ -0,0 +1,18 @@
+use crate::route::ApiResult;
+use jsonwebtoken::jwk::JwkSet;
+
+mod controller;
+
+#[utoipa::path(
+    get,
+    operation_id = "Get JWKS",
+    path = "/.well-known/jwks.json",
+    tag = "Well Known",
+    responses(
+        (status = 200, description = "The public keys verifying the tokens issued by the API"),
+    ),
+)]
+#[get("/.well-known/jwks.json")]
+pub(crate) async fn jwks() -> ApiResult<JwkSet> {
+  controller::jwks().await
+}
@@ -3,11 +3,15 @@ use rand::Rng;
 
 pub const API_KEY_VALUE_LENGTH: usize = 64;
 
+/// Identifies the API keys, e.g. for secret scanners.
+pub const API_KEY_PREFIX: &str = "xd_live_";
+
 pub fn new_key_value() -> String {
-  rand::thread_rng()
+  let value = rand::thread_rng()
     .sample_iter(&Alphanumeric)
     .take(API_KEY_VALUE_LENGTH)
     .map(char::from)
     .collect::<String>()
-    .to_uppercase()
-}
\ No newline at end of file
+    .to_uppercase();
+  format!("{}{}", API_KEY_PREFIX, value)
+}
@@ -1,4 +1,9 @@
 pub mod api_key;
 pub mod password;
+pub mod phone;
 pub mod profile_picture;
+pub mod session;
+pub mod sms;
 pub mod two_factor;
+pub mod verification;
+pub mod webauthn;
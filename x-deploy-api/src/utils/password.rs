@@ -1,11 +1,19 @@
 use crate::error::ApiError;
 use bcrypt::{hash, verify, DEFAULT_COST};
+use lazy_static::lazy_static;
 use rand::distributions::Alphanumeric;
 use rand::Rng;
 use rocket::http::Status;
 
 const FORGOT_PASSWORD_TOKEN_LENGTH: usize = 64;
 
+lazy_static! {
+  // Verified when there is no user for an email, so that the response
+  // takes as long as for a wrong password
+  static ref DUMMY_PASSWORD_HASH: String =
+    hash("dummy-password", DEFAULT_COST).expect("Could not hash password");
+}
+
 pub(crate) fn hash_password(password: &str) -> Result<String, ApiError> {
   let result = hash(password, DEFAULT_COST);
   return match result {
@@ -31,6 +39,12 @@ pub(crate) fn verify_password(
   };
 }
 
+/// Spends the time of a password verification, the result is always false.
+pub(crate) fn verify_dummy_password(password: &str) -> Result<bool, ApiError> {
+  verify_password(password, DUMMY_PASSWORD_HASH.as_str())?;
+  Ok(false)
+}
+
 pub(crate) fn is_strong_password(password: &String) -> Result<bool, ApiError> {
   let regex = r"^(?=.*[A-Za-z])(?=.*\d)(?=.*[@$!%*#?&])[A-Za-z\d@$!%*#?&]{8,}$";
   let result = regex::Regex::new(regex);
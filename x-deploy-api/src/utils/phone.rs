@@ -0,0 +1,58 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use validator::ValidationError;
+
+lazy_static! {
+  // E.164, a `+`, the country code and at most 15 digits in total
+  static ref E164_REGEX: Regex = Regex::new(r"^\+[1-9]\d{1,14}$").unwrap();
+}
+
+pub(crate) fn is_e164_phone(phone: &str) -> bool {
+  E164_REGEX.is_match(phone)
+}
+
+pub(crate) fn validate_e164_phone(
+  phone: &String
+) -> Result<(), ValidationError> {
+  return match is_e164_phone(phone) {
+    true => Ok(()),
+    false => Err(ValidationError::new("e164")),
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn valid_e164_phones() {
+    assert!(is_e164_phone("+33612345678"));
+    assert!(is_e164_phone("+14155552671"));
+    assert!(is_e164_phone("+12"));
+    assert!(is_e164_phone("+123456789012345"));
+  }
+
+  #[test]
+  fn phone_without_plus() {
+    assert!(!is_e164_phone("33612345678"));
+  }
+
+  #[test]
+  fn phone_with_a_leading_zero() {
+    assert!(!is_e164_phone("+0612345678"));
+    assert!(!is_e164_phone("0612345678"));
+  }
+
+  #[test]
+  fn phone_with_more_than_15_digits() {
+    assert!(!is_e164_phone("+1234567890123456"));
+  }
+
+  #[test]
+  fn phone_with_other_characters() {
+    assert!(!is_e164_phone("+33 6 12 34 56 78"));
+    assert!(!is_e164_phone("+33-612345678"));
+    assert!(!is_e164_phone(""));
+    assert!(!is_e164_phone("+"));
+  }
+}
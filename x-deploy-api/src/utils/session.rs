@@ -0,0 +1,39 @@
+use crate::error::ApiError;
+use crate::CONFIG;
+use bson::oid::ObjectId;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use x_deploy_common::cache::session::revoke_session;
+
+pub const REFRESH_TOKEN_LENGTH: usize = 64;
+pub const MAGIC_LINK_TOKEN_LENGTH: usize = 64;
+
+pub(crate) fn new_refresh_token() -> String {
+  rand::thread_rng()
+    .sample_iter(&Alphanumeric)
+    .take(REFRESH_TOKEN_LENGTH)
+    .map(char::from)
+    .collect::<String>()
+}
+
+pub(crate) fn new_magic_link_token() -> String {
+  rand::thread_rng()
+    .sample_iter(&Alphanumeric)
+    .take(MAGIC_LINK_TOKEN_LENGTH)
+    .map(char::from)
+    .collect::<String>()
+}
+
+/// Rejects the access tokens already issued for the sessions, the
+/// sessions themselves must be revoked in the database by the caller.
+pub(crate) async fn revoke_access_tokens(
+  redis: &redis::Client,
+  session_ids: &Vec<ObjectId>,
+) -> Result<(), ApiError> {
+  // The access tokens can't outlive their own duration
+  let ttl = CONFIG.jwt_key_duration_in_minutes * 60;
+  for session_id in session_ids {
+    revoke_session(redis, session_id, ttl).await?;
+  }
+  Ok(())
+}
@@ -0,0 +1,81 @@
+use crate::error::ApiError;
+use log::info;
+use mongodb::{ClientSession, Database};
+use x_deploy_common::db::event_outbox::EventOutbox;
+use x_deploy_common::db::user::User;
+use x_deploy_common::db::CommonCollection;
+use x_deploy_common::event::user::UserPhoneVerificationEvent;
+
+/// Sends the SMS of the API, managed as a `Box<dyn SmsProvider>`. The SMS
+/// is sent in the transaction of `session`, along with the code it holds.
+#[rocket::async_trait]
+pub(crate) trait SmsProvider: Send + Sync {
+  async fn send_phone_verification(
+    &self,
+    user: &User,
+    phone: &String,
+    code: &String,
+    session: &mut ClientSession,
+  ) -> Result<(), ApiError>;
+}
+
+/// Publishes the SMS as events on Kafka, through the outbox, for the
+/// service delivering them.
+pub(crate) struct KafkaSmsProvider {
+  db: Database,
+}
+
+impl KafkaSmsProvider {
+  pub(crate) fn new(db: Database) -> Self {
+    Self { db }
+  }
+}
+
+#[rocket::async_trait]
+impl SmsProvider for KafkaSmsProvider {
+  async fn send_phone_verification(
+    &self,
+    user: &User,
+    phone: &String,
+    code: &String,
+    session: &mut ClientSession,
+  ) -> Result<(), ApiError> {
+    CommonCollection::<EventOutbox>::new(&self.db)
+      .add_with_session(
+        &UserPhoneVerificationEvent {
+          id: user.id.clone(),
+          firstname: user.firstname.clone(),
+          lastname: user.lastname.clone(),
+          phone: phone.clone(),
+          code: code.clone(),
+        },
+        session,
+      )
+      .await?;
+    Ok(())
+  }
+}
+
+/// Logs the code sent to each phone instead of sending it, for the local
+/// development.
+pub(crate) struct InMemorySmsProvider;
+
+impl InMemorySmsProvider {
+  pub(crate) fn new() -> Self {
+    Self
+  }
+}
+
+#[rocket::async_trait]
+impl SmsProvider for InMemorySmsProvider {
+  async fn send_phone_verification(
+    &self,
+    _: &User,
+    phone: &String,
+    code: &String,
+    _: &mut ClientSession,
+  ) -> Result<(), ApiError> {
+    info!("Phone verification code for {}: {}", phone, code);
+    Ok(())
+  }
+}
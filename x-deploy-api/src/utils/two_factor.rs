@@ -10,7 +10,8 @@ const DIGITS: usize = 6;
 const SKEW: u8 = 1;
 const STEP: u64 = 30;
 
-const RECOVERY_CODE_LENGTH: usize = 30;
+const RECOVERY_CODE_COUNT: usize = 10;
+const RECOVERY_CODE_LENGTH: usize = 10;
 
 pub(crate) fn new_2fa(email: String) -> Result<TOTP, ApiError> {
   let secret = Secret::default().to_bytes().unwrap();
@@ -67,13 +68,23 @@ pub(crate) fn verify_2fa_code(
   }
 }
 
-pub(crate) fn generate_recovery_code() -> String {
-  rand::thread_rng()
-    .sample_iter(&Alphanumeric)
-    .take(RECOVERY_CODE_LENGTH)
-    .map(char::from)
-    .collect::<String>()
-    .to_uppercase()
+/// Generates a new set of recovery codes, returning the codes to show to
+/// the user and the hashes to store.
+pub(crate) fn generate_recovery_codes() -> (Vec<String>, Vec<String>) {
+  let mut codes = Vec::with_capacity(RECOVERY_CODE_COUNT);
+  let mut hashes = Vec::with_capacity(RECOVERY_CODE_COUNT);
+  for _ in 0..RECOVERY_CODE_COUNT {
+    let code = rand::thread_rng()
+      .sample_iter(&Alphanumeric)
+      .take(RECOVERY_CODE_LENGTH)
+      .map(char::from)
+      .collect::<String>()
+      .to_uppercase();
+    hashes.push(TwoFactor::hash_recovery_code(&code));
+    let (first, second) = code.split_at(RECOVERY_CODE_LENGTH / 2);
+    codes.push(format!("{}-{}", first, second));
+  }
+  (codes, hashes)
 }
 
 impl From<TotpUrlError> for ApiError {
@@ -0,0 +1,128 @@
+use crate::error::ApiError;
+use bson::DateTime;
+use mongodb::{ClientSession, Database};
+use rand::Rng;
+use x_deploy_common::db::event_outbox::EventOutbox;
+use x_deploy_common::db::user::User;
+use x_deploy_common::db::CommonCollection;
+use x_deploy_common::event::user::UserEmailVerificationEvent;
+
+const VERIFICATION_CODE_LENGTH: usize = 6;
+
+// Validity of a code sent to verify an email
+const EMAIL_CODE_DURATION_IN_HOURS: i64 = 24;
+
+/// A numeric code, short enough to be typed from an email or an SMS.
+pub(crate) fn generate_verification_code() -> String {
+  let mut rng = rand::thread_rng();
+  (0..VERIFICATION_CODE_LENGTH)
+    .map(|_| char::from(b'0' + rng.gen_range(0..10)))
+    .collect()
+}
+
+/// Why a verification code is refused.
+#[derive(Debug, PartialEq)]
+pub(crate) enum CodeError {
+  /// No code was sent, or it was already used
+  Missing,
+  Expired,
+  Invalid,
+}
+
+/// Checks the code typed by the user against the one sent to them.
+pub(crate) fn check_verification_code(
+  sent_code: &Option<String>,
+  code_expires_at: &Option<DateTime>,
+  code: &str,
+) -> Result<(), CodeError> {
+  let (sent_code, code_expires_at) = match (sent_code, code_expires_at) {
+    (Some(sent_code), Some(code_expires_at)) => (sent_code, code_expires_at),
+    _ => return Err(CodeError::Missing),
+  };
+  if *code_expires_at < DateTime::now() {
+    return Err(CodeError::Expired);
+  }
+  if sent_code != code {
+    return Err(CodeError::Invalid);
+  }
+  Ok(())
+}
+
+/// Replaces the code verifying the email of the user and sends it, in the
+/// transaction of `session`.
+pub(crate) async fn send_email_verification(
+  db: &Database,
+  session: &mut ClientSession,
+  user: &User,
+) -> Result<(), ApiError> {
+  let code = generate_verification_code();
+  let code_expires_at = DateTime::from_chrono(
+    chrono::Utc::now() + chrono::Duration::hours(EMAIL_CODE_DURATION_IN_HOURS),
+  );
+  CommonCollection::<User>::new(db)
+    .email_update_code_with_session(&user.id, &code, code_expires_at, session)
+    .await?;
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &UserEmailVerificationEvent {
+        id: user.id.clone(),
+        firstname: user.firstname.clone(),
+        lastname: user.lastname.clone(),
+        email: user.email.email.clone(),
+        code,
+      },
+      session,
+    )
+    .await?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn expires_in(minutes: i64) -> Option<DateTime> {
+    Some(DateTime::from_chrono(
+      chrono::Utc::now() + chrono::Duration::minutes(minutes),
+    ))
+  }
+
+  #[test]
+  fn accept_the_sent_code() {
+    let code = generate_verification_code();
+    let checked =
+      check_verification_code(&Some(code.clone()), &expires_in(10), &code);
+    assert_eq!(checked, Ok(()));
+  }
+
+  #[test]
+  fn refuse_another_code() {
+    let sent_code = Some("123456".to_string());
+    let checked =
+      check_verification_code(&sent_code, &expires_in(10), "654321");
+    assert_eq!(checked, Err(CodeError::Invalid));
+  }
+
+  #[test]
+  fn refuse_an_expired_code() {
+    let sent_code = Some("123456".to_string());
+    let checked =
+      check_verification_code(&sent_code, &expires_in(-1), "123456");
+    assert_eq!(checked, Err(CodeError::Expired));
+  }
+
+  #[test]
+  fn refuse_when_no_code_was_sent() {
+    let checked = check_verification_code(&None, &None, "123456");
+    assert_eq!(checked, Err(CodeError::Missing));
+    let checked = check_verification_code(&None, &expires_in(10), "123456");
+    assert_eq!(checked, Err(CodeError::Missing));
+  }
+
+  #[test]
+  fn codes_are_six_digits() {
+    let code = generate_verification_code();
+    assert_eq!(code.len(), 6);
+    assert!(code.chars().all(|c| c.is_ascii_digit()));
+  }
+}
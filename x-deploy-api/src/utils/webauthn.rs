@@ -0,0 +1,142 @@
+use crate::error::ApiError;
+use crate::WEBAUTHN;
+use bson::oid::ObjectId;
+use rocket::http::Status;
+use serde::{Deserialize, Serialize};
+use webauthn_rs::prelude::{
+  CreationChallengeResponse, Passkey, PasskeyAuthentication,
+  PasskeyRegistration, PublicKeyCredential, RegisterPublicKeyCredential,
+  RequestChallengeResponse, Uuid, WebauthnError,
+};
+use x_deploy_common::cache::webauthn::{
+  save_webauthn_state, take_webauthn_state, WebauthnCeremony,
+};
+use x_deploy_common::db::user::{User, WebauthnCredential};
+use x_deploy_common::db::CommonCollection;
+
+/// A registration waiting for the answer of the authenticator.
+#[derive(Deserialize, Serialize)]
+struct PendingRegistration {
+  name: String,
+  state: PasskeyRegistration,
+}
+
+/// The WebAuthn handle of the user, derived from its id so that it
+/// doesn't need to be stored.
+fn user_unique_id(user_id: &ObjectId) -> Uuid {
+  let mut bytes = [0u8; 16];
+  bytes[..12].copy_from_slice(&user_id.bytes());
+  Uuid::from_bytes(bytes)
+}
+
+fn to_passkey(credential: &WebauthnCredential) -> Result<Passkey, ApiError> {
+  Ok(serde_json::from_str::<Passkey>(&credential.passkey)?)
+}
+
+pub(crate) async fn start_registration(
+  redis: &redis::Client,
+  user: &User,
+  name: String,
+) -> Result<CreationChallengeResponse, ApiError> {
+  let mut exclude_credentials = Vec::new();
+  for credential in &user.webauthn_credentials {
+    exclude_credentials.push(to_passkey(credential)?.cred_id().clone());
+  }
+  let display_name = format!("{} {}", user.firstname, user.lastname);
+  let (challenge, state) = WEBAUTHN.start_passkey_registration(
+    user_unique_id(&user.id),
+    &user.email.email,
+    display_name.trim(),
+    Some(exclude_credentials),
+  )?;
+  let pending = serde_json::to_string(&PendingRegistration { name, state })?;
+  let ceremony = WebauthnCeremony::Registration;
+  save_webauthn_state(redis, ceremony, &user.id, &pending).await?;
+  Ok(challenge)
+}
+
+pub(crate) async fn finish_registration(
+  redis: &redis::Client,
+  user: &User,
+  credential: &RegisterPublicKeyCredential,
+) -> Result<WebauthnCredential, ApiError> {
+  let ceremony = WebauthnCeremony::Registration;
+  let pending = match take_webauthn_state(redis, ceremony, &user.id).await? {
+    Some(pending) => pending,
+    None => return Err(no_ceremony_error()),
+  };
+  let pending = serde_json::from_str::<PendingRegistration>(&pending)?;
+  let passkey =
+    WEBAUTHN.finish_passkey_registration(credential, &pending.state)?;
+  let passkey = serde_json::to_string(&passkey)?;
+  Ok(WebauthnCredential::new(pending.name, passkey))
+}
+
+pub(crate) async fn start_authentication(
+  redis: &redis::Client,
+  user: &User,
+) -> Result<RequestChallengeResponse, ApiError> {
+  let mut passkeys = Vec::new();
+  for credential in &user.webauthn_credentials {
+    passkeys.push(to_passkey(credential)?);
+  }
+  if passkeys.is_empty() {
+    return Err(ApiError::new(
+      Status::BadRequest,
+      "No passkey is registered for this account".to_string(),
+    ));
+  }
+  let (challenge, state) = WEBAUTHN.start_passkey_authentication(&passkeys)?;
+  let state = serde_json::to_string(&state)?;
+  let ceremony = WebauthnCeremony::Authentication;
+  save_webauthn_state(redis, ceremony, &user.id, &state).await?;
+  Ok(challenge)
+}
+
+/// Verifies the answer of an authenticator to the challenge of
+/// `start_authentication` and records the use of its credential.
+pub(crate) async fn finish_authentication(
+  db: &mongodb::Database,
+  redis: &redis::Client,
+  user: &User,
+  credential: &PublicKeyCredential,
+) -> Result<(), ApiError> {
+  let ceremony = WebauthnCeremony::Authentication;
+  let state = match take_webauthn_state(redis, ceremony, &user.id).await? {
+    Some(state) => state,
+    None => return Err(no_ceremony_error()),
+  };
+  let state = serde_json::from_str::<PasskeyAuthentication>(&state)?;
+  let result = WEBAUTHN.finish_passkey_authentication(credential, &state)?;
+  for stored in &user.webauthn_credentials {
+    let mut passkey = to_passkey(stored)?;
+    if passkey.cred_id() != result.cred_id() {
+      continue;
+    }
+    let updated = match passkey.update_credential(&result) {
+      Some(true) => Some(serde_json::to_string(&passkey)?),
+      _ => None,
+    };
+    CommonCollection::<User>::new(db)
+      .webauthn_credential_used(&user.id, &stored.id, updated.as_ref())
+      .await?;
+    break;
+  }
+  Ok(())
+}
+
+fn no_ceremony_error() -> ApiError {
+  ApiError::new(
+    Status::BadRequest,
+    "The challenge is unknown or expired, please try again".to_string(),
+  )
+}
+
+impl From<WebauthnError> for ApiError {
+  fn from(_: WebauthnError) -> Self {
+    ApiError::new(
+      Status::Unauthorized,
+      "The answer of your authenticator is invalid".to_string(),
+    )
+  }
+}
@@ -0,0 +1,73 @@
+use crate::CommonResult;
+use redis::AsyncCommands;
+
+/// How many failures are allowed for a subject, e.g. the email of a
+/// login, before it is locked.
+#[derive(Debug, Clone, Copy)]
+pub struct LockoutPolicy {
+  pub max_failures: u64,
+  /// Failures are forgotten after this time without a new one
+  pub failure_window_in_seconds: u64,
+  /// The first lock, it doubles with every failure after `max_failures`
+  pub lock_in_seconds: u64,
+  pub max_lock_in_seconds: u64,
+}
+
+fn failures_key(subject: &String) -> String {
+  format!("lockout:failures:{}", subject)
+}
+
+fn locked_key(subject: &String) -> String {
+  format!("lockout:locked:{}", subject)
+}
+
+/// Returns the seconds left before the subject is unlocked.
+pub async fn locked_for(
+  client: &redis::Client,
+  subject: &String,
+) -> CommonResult<Option<u64>> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let ttl: i64 = connection.ttl(locked_key(subject)).await?;
+  return match ttl > 0 {
+    true => Ok(Some(ttl as u64)),
+    false => Ok(None),
+  };
+}
+
+/// Records a failure and locks the subject once it has too many, returning
+/// the duration of the lock.
+pub async fn record_failure(
+  client: &redis::Client,
+  subject: &String,
+  policy: &LockoutPolicy,
+) -> CommonResult<Option<u64>> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let key = failures_key(subject);
+  let (failures,): (u64,) = redis::pipe()
+    .atomic()
+    .incr(&key, 1)
+    .expire(&key, policy.failure_window_in_seconds as i64)
+    .ignore()
+    .query_async(&mut connection)
+    .await?;
+  if failures < policy.max_failures {
+    return Ok(None);
+  }
+  let exponent = (failures - policy.max_failures).min(32) as u32;
+  let lock = policy
+    .lock_in_seconds
+    .saturating_mul(2u64.saturating_pow(exponent))
+    .min(policy.max_lock_in_seconds);
+  let _: () = connection.set_ex(locked_key(subject), true, lock).await?;
+  Ok(Some(lock))
+}
+
+/// Forgets the failures of a subject, e.g. after a successful login.
+pub async fn clear_failures(
+  client: &redis::Client,
+  subject: &String,
+) -> CommonResult<()> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let _: () = connection.del(failures_key(subject)).await?;
+  Ok(())
+}
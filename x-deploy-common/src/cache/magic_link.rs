@@ -0,0 +1,42 @@
+use crate::crypto::hash::hash_token;
+use crate::CommonResult;
+use bson::oid::ObjectId;
+use redis::AsyncCommands;
+use std::str::FromStr;
+
+/// How long the link sent by email can be used to login.
+pub const MAGIC_LINK_TTL_IN_SECONDS: u64 = 900;
+
+// The token is only stored hashed, it is enough to login
+fn magic_link_key(token: &str) -> String {
+  format!("magic-link:{}", hash_token(token))
+}
+
+pub async fn save_magic_link(
+  client: &redis::Client,
+  token: &str,
+  user_id: &ObjectId,
+) -> CommonResult<()> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let _: () = connection
+    .set_ex(
+      magic_link_key(token),
+      user_id.to_string(),
+      MAGIC_LINK_TTL_IN_SECONDS,
+    )
+    .await?;
+  Ok(())
+}
+
+/// Returns the user of the link and forgets it, a link is only usable once.
+pub async fn take_magic_link(
+  client: &redis::Client,
+  token: &str,
+) -> CommonResult<Option<ObjectId>> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let value: Option<String> = connection.get_del(magic_link_key(token)).await?;
+  return match value {
+    Some(value) => Ok(ObjectId::from_str(&value).ok()),
+    None => Ok(None),
+  };
+}
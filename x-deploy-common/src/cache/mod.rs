@@ -0,0 +1,6 @@
+pub mod lockout;
+pub mod magic_link;
+pub mod oauth;
+pub mod rate_limit;
+pub mod session;
+pub mod webauthn;
@@ -0,0 +1,49 @@
+use crate::db::user::OAuthProvider;
+use crate::CommonResult;
+use bson::oid::ObjectId;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+
+/// How long a user has to complete an authorization on the provider.
+pub const OAUTH_STATE_TTL_IN_SECONDS: u64 = 600;
+
+/// An authorization in progress, stored under its `state` parameter.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthState {
+  pub provider: OAuthProvider,
+  pub code_verifier: String,
+  /// The user linking the provider, none to login or sign up
+  pub user_id: Option<ObjectId>,
+}
+
+fn oauth_state_key(state: &str) -> String {
+  format!("oauth:state:{}", state)
+}
+
+pub async fn save_oauth_state(
+  client: &redis::Client,
+  state: &str,
+  oauth_state: &OAuthState,
+) -> CommonResult<()> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let value = serde_json::to_string(oauth_state)?;
+  let _: () = connection
+    .set_ex(oauth_state_key(state), value, OAUTH_STATE_TTL_IN_SECONDS)
+    .await?;
+  Ok(())
+}
+
+/// Returns the authorization and forgets it, a state is only usable once.
+pub async fn take_oauth_state(
+  client: &redis::Client,
+  state: &str,
+) -> CommonResult<Option<OAuthState>> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let value: Option<String> =
+    connection.get_del(oauth_state_key(state)).await?;
+  return match value {
+    Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+    None => Ok(None),
+  };
+}
@@ -0,0 +1,42 @@
+use crate::CommonResult;
+
+/// The hits counted for a key in the current window.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitHit {
+  pub count: u64,
+  /// Seconds left before the window is reset
+  pub reset_in: u64,
+}
+
+fn rate_limit_key(key: &String) -> String {
+  format!("rate_limit:{}", key)
+}
+
+/// Counts a hit on `key` in a fixed window of `window_in_seconds`.
+pub async fn hit(
+  client: &redis::Client,
+  key: &String,
+  window_in_seconds: u64,
+) -> CommonResult<RateLimitHit> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let key = rate_limit_key(key);
+  // The window starts with the first hit, it is not extended by the
+  // following ones
+  let (count, ttl): (u64, i64) = redis::pipe()
+    .atomic()
+    .cmd("SET")
+    .arg(&key)
+    .arg(0)
+    .arg("EX")
+    .arg(window_in_seconds)
+    .arg("NX")
+    .ignore()
+    .incr(&key, 1)
+    .ttl(&key)
+    .query_async(&mut connection)
+    .await?;
+  Ok(RateLimitHit {
+    count,
+    reset_in: ttl.max(0) as u64,
+  })
+}
@@ -0,0 +1,30 @@
+use crate::CommonResult;
+use bson::oid::ObjectId;
+use redis::AsyncCommands;
+
+fn revoked_session_key(session_id: &String) -> String {
+  format!("session:revoked:{}", session_id)
+}
+
+/// Marks a session as revoked for `ttl_in_seconds`, which must cover the
+/// lifetime of the access tokens already issued for it.
+pub async fn revoke_session(
+  client: &redis::Client,
+  session_id: &ObjectId,
+  ttl_in_seconds: u64,
+) -> CommonResult<()> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let key = revoked_session_key(&session_id.to_string());
+  let _: () = connection.set_ex(key, true, ttl_in_seconds).await?;
+  Ok(())
+}
+
+pub async fn is_session_revoked(
+  client: &redis::Client,
+  session_id: &String,
+) -> CommonResult<bool> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let revoked: bool =
+    connection.exists(revoked_session_key(session_id)).await?;
+  Ok(revoked)
+}
@@ -0,0 +1,60 @@
+use crate::CommonResult;
+use bson::oid::ObjectId;
+use redis::AsyncCommands;
+
+/// How long a user has to answer a WebAuthn challenge.
+pub const WEBAUTHN_STATE_TTL_IN_SECONDS: u64 = 300;
+
+/// The WebAuthn ceremonies, a user has at most one of each in progress.
+#[derive(Clone, Copy, Debug)]
+pub enum WebauthnCeremony {
+  Registration,
+  Authentication,
+}
+
+impl std::fmt::Display for WebauthnCeremony {
+  fn fmt(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+  ) -> std::fmt::Result {
+    match self {
+      WebauthnCeremony::Registration => write!(f, "registration"),
+      WebauthnCeremony::Authentication => write!(f, "authentication"),
+    }
+  }
+}
+
+fn webauthn_state_key(
+  ceremony: WebauthnCeremony,
+  user_id: &ObjectId,
+) -> String {
+  format!("webauthn:{}:{}", ceremony, user_id)
+}
+
+/// Stores the serialized state of a ceremony, replacing the previous one.
+pub async fn save_webauthn_state(
+  client: &redis::Client,
+  ceremony: WebauthnCeremony,
+  user_id: &ObjectId,
+  state: &String,
+) -> CommonResult<()> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let key = webauthn_state_key(ceremony, user_id);
+  let _: () = connection
+    .set_ex(key, state, WEBAUTHN_STATE_TTL_IN_SECONDS)
+    .await?;
+  Ok(())
+}
+
+/// Returns the state of a ceremony and forgets it, a challenge is only
+/// usable once.
+pub async fn take_webauthn_state(
+  client: &redis::Client,
+  ceremony: WebauthnCeremony,
+  user_id: &ObjectId,
+) -> CommonResult<Option<String>> {
+  let mut connection = client.get_multiplexed_async_connection().await?;
+  let key = webauthn_state_key(ceremony, user_id);
+  let state: Option<String> = connection.get_del(key).await?;
+  Ok(state)
+}
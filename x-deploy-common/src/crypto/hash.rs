@@ -0,0 +1,14 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+/// Hashes a long random token, such as an API key or a refresh token,
+/// so that it can be stored and still be looked up.
+pub fn hash_token(token: &str) -> String {
+  format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// The `S256` PKCE challenge of an OAuth code verifier.
+pub fn pkce_challenge(code_verifier: &str) -> String {
+  URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
@@ -0,0 +1,254 @@
+use crate::{CommonError, CommonResult};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::{
+  AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet,
+  KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+  RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{
+  decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header,
+  Validation,
+};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// DER prefix of an Ed25519 SubjectPublicKeyInfo, followed by the key.
+const ED25519_SPKI_PREFIX: [u8; 12] =
+  [48, 42, 48, 5, 6, 3, 43, 101, 112, 3, 33, 0];
+
+const ED25519_KEY_LENGTH: usize = 32;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
+pub enum JwtAlgorithm {
+  #[serde(rename = "RS256")]
+  Rs256,
+  #[serde(rename = "EdDSA")]
+  EdDsa,
+}
+
+impl From<JwtAlgorithm> for Algorithm {
+  fn from(value: JwtAlgorithm) -> Self {
+    match value {
+      JwtAlgorithm::Rs256 => Algorithm::RS256,
+      JwtAlgorithm::EdDsa => Algorithm::EdDSA,
+    }
+  }
+}
+
+/// A signing key as found in the configuration, keys are PEM encoded.
+#[derive(Deserialize, Clone, Debug)]
+pub struct JwtKeyConfig {
+  pub algorithm: JwtAlgorithm,
+  /// Only needed for the current key, the retired keys are kept to verify
+  /// the tokens they signed until they expire
+  pub private_key: Option<String>,
+  pub public_key: String,
+}
+
+/// The keys used by the API to sign its tokens. Tokens carry the id of
+/// their key in the `kid` header, so the keys can be rotated by adding a
+/// new current key while keeping the previous one for verification.
+pub struct JwtKeyring {
+  current_key_id: String,
+  algorithm: Algorithm,
+  encoding_key: EncodingKey,
+  jwks: JwkSet,
+  verifier: JwksVerifier,
+}
+
+impl JwtKeyring {
+  pub fn new(
+    current_key_id: String,
+    keys: &HashMap<String, JwtKeyConfig>,
+  ) -> CommonResult<Self> {
+    let current_key = match keys.get(&current_key_id) {
+      Some(key) => key,
+      None => {
+        return Err(CommonError::CryptoError(format!(
+          "Current jwt key {} is not in the keyring",
+          current_key_id
+        )))
+      }
+    };
+    let private_key = match &current_key.private_key {
+      Some(private_key) => private_key,
+      None => {
+        return Err(CommonError::CryptoError(format!(
+          "Current jwt key {} has no private key",
+          current_key_id
+        )))
+      }
+    };
+    let encoding_key = match current_key.algorithm {
+      JwtAlgorithm::Rs256 => EncodingKey::from_rsa_pem(private_key.as_bytes()),
+      JwtAlgorithm::EdDsa => EncodingKey::from_ed_pem(private_key.as_bytes()),
+    }
+    .map_err(|_| {
+      CommonError::CryptoError(format!(
+        "Private key of jwt key {} is invalid",
+        current_key_id
+      ))
+    })?;
+    let mut jwks = JwkSet { keys: Vec::new() };
+    for (key_id, key) in keys {
+      jwks.keys.push(to_jwk(key_id, key)?);
+    }
+    let verifier = JwksVerifier::new(&jwks)?;
+    Ok(Self {
+      algorithm: current_key.algorithm.into(),
+      current_key_id,
+      encoding_key,
+      jwks,
+      verifier,
+    })
+  }
+
+  pub fn sign<T: Serialize>(
+    &self,
+    claims: &T,
+  ) -> CommonResult<String> {
+    let mut header = Header::new(self.algorithm);
+    header.kid = Some(self.current_key_id.clone());
+    encode(&header, claims, &self.encoding_key)
+      .map_err(|e| CommonError::CryptoError(e.to_string()))
+  }
+
+  pub fn verify<T: DeserializeOwned>(
+    &self,
+    token: &str,
+  ) -> jsonwebtoken::errors::Result<T> {
+    self.verifier.verify(token)
+  }
+
+  /// The public keys, to be published for the services verifying tokens.
+  pub fn jwks(&self) -> &JwkSet {
+    &self.jwks
+  }
+}
+
+/// Verifies tokens with public keys only, e.g. those fetched from the
+/// JWKS endpoint of the API.
+pub struct JwksVerifier {
+  keys: HashMap<String, (Algorithm, DecodingKey)>,
+}
+
+impl JwksVerifier {
+  pub fn new(jwks: &JwkSet) -> CommonResult<Self> {
+    let mut keys = HashMap::new();
+    for jwk in &jwks.keys {
+      let key_id = match &jwk.common.key_id {
+        Some(key_id) => key_id.clone(),
+        None => continue,
+      };
+      let algorithm = match jwk.common.key_algorithm {
+        Some(KeyAlgorithm::RS256) => Algorithm::RS256,
+        Some(KeyAlgorithm::EdDSA) => Algorithm::EdDSA,
+        _ => continue,
+      };
+      let decoding_key = DecodingKey::from_jwk(jwk).map_err(|_| {
+        CommonError::CryptoError(format!("Jwk {} is invalid", key_id))
+      })?;
+      keys.insert(key_id, (algorithm, decoding_key));
+    }
+    Ok(Self { keys })
+  }
+
+  pub fn has_key(
+    &self,
+    key_id: &str,
+  ) -> bool {
+    self.keys.contains_key(key_id)
+  }
+
+  pub fn verify<T: DeserializeOwned>(
+    &self,
+    token: &str,
+  ) -> jsonwebtoken::errors::Result<T> {
+    let header = decode_header(token)?;
+    let key = match &header.kid {
+      Some(key_id) => self.keys.get(key_id),
+      None => None,
+    };
+    let (algorithm, decoding_key) = match key {
+      Some(key) => key,
+      None => return Err(ErrorKind::InvalidToken.into()),
+    };
+    // The algorithm comes from the key, never from the token
+    let validation = Validation::new(*algorithm);
+    let token_data = decode::<T>(token, decoding_key, &validation)?;
+    Ok(token_data.claims)
+  }
+}
+
+/// Reads the key id of a token without verifying it.
+pub fn key_id_of(token: &str) -> Option<String> {
+  match decode_header(token) {
+    Ok(header) => header.kid,
+    Err(_) => None,
+  }
+}
+
+fn to_jwk(
+  key_id: &String,
+  key: &JwtKeyConfig,
+) -> CommonResult<Jwk> {
+  let invalid_key = || {
+    CommonError::CryptoError(format!(
+      "Public key of jwt key {} is invalid",
+      key_id
+    ))
+  };
+  let (key_algorithm, algorithm) = match key.algorithm {
+    JwtAlgorithm::Rs256 => {
+      let public_key = RsaPublicKey::from_public_key_pem(&key.public_key)
+        .map_err(|_| invalid_key())?;
+      let parameters = RSAKeyParameters {
+        key_type: RSAKeyType::RSA,
+        n: URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+        e: URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+      };
+      (KeyAlgorithm::RS256, AlgorithmParameters::RSA(parameters))
+    }
+    JwtAlgorithm::EdDsa => {
+      let der = pem_to_der(&key.public_key).ok_or_else(invalid_key)?;
+      let valid = der.len() == ED25519_SPKI_PREFIX.len() + ED25519_KEY_LENGTH
+        && der.starts_with(&ED25519_SPKI_PREFIX);
+      if !valid {
+        return Err(invalid_key());
+      }
+      let parameters = OctetKeyPairParameters {
+        key_type: OctetKeyPairType::OctetKeyPair,
+        curve: EllipticCurve::Ed25519,
+        x: URL_SAFE_NO_PAD.encode(&der[ED25519_SPKI_PREFIX.len()..]),
+      };
+      (
+        KeyAlgorithm::EdDSA,
+        AlgorithmParameters::OctetKeyPair(parameters),
+      )
+    }
+  };
+  Ok(Jwk {
+    common: CommonParameters {
+      public_key_use: Some(PublicKeyUse::Signature),
+      key_algorithm: Some(key_algorithm),
+      key_id: Some(key_id.clone()),
+      ..Default::default()
+    },
+    algorithm,
+  })
+}
+
+fn pem_to_der(pem: &str) -> Option<Vec<u8>> {
+  let body: String = pem
+    .lines()
+    .map(|line| line.trim())
+    .filter(|line| !line.is_empty() && !line.starts_with("-----"))
+    .collect();
+  STANDARD.decode(body).ok()
+}
@@ -0,0 +1,210 @@
+use crate::crypto::secret::EncryptedSecret;
+use crate::{CommonError, CommonResult};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rsa::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use rsa::{Oaep, RsaPrivateKey, RsaPublicKey};
+use sha2::Sha256;
+use std::collections::HashMap;
+
+const KEY_LENGTH: usize = 32;
+
+const NONCE_LENGTH: usize = 12;
+
+/// The public key-encryption key, enough to encrypt new secrets but not
+/// to decrypt them. This is the only key the API holds.
+pub struct EncryptionKey {
+  key_id: String,
+  public_key: RsaPublicKey,
+}
+
+impl EncryptionKey {
+  /// Builds the key from a PEM encoded RSA public key.
+  pub fn new(
+    key_id: String,
+    public_key: &str,
+  ) -> CommonResult<Self> {
+    let public_key =
+      RsaPublicKey::from_public_key_pem(public_key).map_err(|_| {
+        CommonError::CryptoError(format!("Key {} is not a valid PEM", key_id))
+      })?;
+    Ok(Self { key_id, public_key })
+  }
+
+  pub fn encrypt(
+    &self,
+    plaintext: &str,
+  ) -> CommonResult<EncryptedSecret> {
+    let data_key = Aes256Gcm::generate_key(&mut OsRng);
+    let ciphertext = seal(&data_key, plaintext.as_bytes(), &[])?;
+    self.wrap(&data_key, ciphertext)
+  }
+
+  fn wrap(
+    &self,
+    data_key: &Key<Aes256Gcm>,
+    ciphertext: Vec<u8>,
+  ) -> CommonResult<EncryptedSecret> {
+    // The key id is the label so a data key can't be moved to another
+    let padding = Oaep::new_with_label::<Sha256, _>(self.key_id.clone());
+    let encrypted_key = self
+      .public_key
+      .encrypt(&mut OsRng, padding, data_key.as_slice())
+      .map_err(|_| CommonError::CryptoError("Encryption failed".into()))?;
+    Ok(EncryptedSecret {
+      key_id: self.key_id.clone(),
+      encrypted_key: STANDARD.encode(encrypted_key),
+      ciphertext: STANDARD.encode(ciphertext),
+    })
+  }
+}
+
+/// The private key-encryption keys used for envelope encryption, only
+/// held by the daemon. New secrets are encrypted with the current key,
+/// older keys are only kept to decrypt secrets that weren't migrated yet.
+pub struct Keyring {
+  current_key: EncryptionKey,
+  keys: HashMap<String, RsaPrivateKey>,
+}
+
+impl Keyring {
+  /// Builds the keyring from PEM encoded RSA private keys by id.
+  pub fn new(
+    current_key_id: String,
+    keys: &HashMap<String, String>,
+  ) -> CommonResult<Self> {
+    let mut decoded_keys = HashMap::new();
+    for (key_id, key) in keys {
+      let key = RsaPrivateKey::from_pkcs8_pem(key).map_err(|_| {
+        CommonError::CryptoError(format!("Key {} is not a valid PEM", key_id))
+      })?;
+      decoded_keys.insert(key_id.clone(), key);
+    }
+    let current_key = match decoded_keys.get(&current_key_id) {
+      Some(key) => EncryptionKey {
+        key_id: current_key_id,
+        public_key: key.to_public_key(),
+      },
+      None => {
+        return Err(CommonError::CryptoError(format!(
+          "Current key {} is not in the keyring",
+          current_key_id
+        )))
+      }
+    };
+    Ok(Self {
+      current_key,
+      keys: decoded_keys,
+    })
+  }
+
+  pub fn encrypt(
+    &self,
+    plaintext: &str,
+  ) -> CommonResult<EncryptedSecret> {
+    self.current_key.encrypt(plaintext)
+  }
+
+  pub fn decrypt(
+    &self,
+    secret: &EncryptedSecret,
+  ) -> CommonResult<String> {
+    let data_key = self.unwrap(secret)?;
+    let ciphertext = decode(&secret.ciphertext)?;
+    let plaintext = open(&data_key, &ciphertext, &[])?;
+    String::from_utf8(plaintext).map_err(|_| {
+      CommonError::CryptoError("Decrypted secret is not valid UTF-8".into())
+    })
+  }
+
+  /// Whether the secret was encrypted with an older key.
+  pub fn needs_rewrap(
+    &self,
+    secret: &EncryptedSecret,
+  ) -> bool {
+    secret.key_id != self.current_key.key_id
+  }
+
+  /// Encrypts the data key of a secret again with the current key. The
+  /// secret itself is left untouched.
+  pub fn rewrap(
+    &self,
+    secret: &EncryptedSecret,
+  ) -> CommonResult<EncryptedSecret> {
+    let data_key = self.unwrap(secret)?;
+    let ciphertext = decode(&secret.ciphertext)?;
+    self.current_key.wrap(&data_key, ciphertext)
+  }
+
+  fn unwrap(
+    &self,
+    secret: &EncryptedSecret,
+  ) -> CommonResult<Key<Aes256Gcm>> {
+    let key = match self.keys.get(&secret.key_id) {
+      Some(key) => key,
+      None => {
+        return Err(CommonError::CryptoError(format!(
+          "Key {} is not in the keyring",
+          secret.key_id
+        )))
+      }
+    };
+    let encrypted_key = decode(&secret.encrypted_key)?;
+    let padding = Oaep::new_with_label::<Sha256, _>(secret.key_id.clone());
+    let data_key = key
+      .decrypt(padding, &encrypted_key)
+      .map_err(|_| CommonError::CryptoError("Decryption failed".into()))?;
+    if data_key.len() != KEY_LENGTH {
+      return Err(CommonError::CryptoError("Invalid data key".into()));
+    }
+    Ok(Key::<Aes256Gcm>::clone_from_slice(&data_key))
+  }
+}
+
+/// Encrypts with a random nonce and returns the nonce followed by the
+/// ciphertext.
+fn seal(
+  key: &Key<Aes256Gcm>,
+  plaintext: &[u8],
+  aad: &[u8],
+) -> CommonResult<Vec<u8>> {
+  let cipher = Aes256Gcm::new(key);
+  let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+  let payload = Payload {
+    msg: plaintext,
+    aad,
+  };
+  let ciphertext = cipher
+    .encrypt(&nonce, payload)
+    .map_err(|_| CommonError::CryptoError("Encryption failed".into()))?;
+  let mut result = nonce.to_vec();
+  result.extend(ciphertext);
+  Ok(result)
+}
+
+fn open(
+  key: &Key<Aes256Gcm>,
+  data: &[u8],
+  aad: &[u8],
+) -> CommonResult<Vec<u8>> {
+  if data.len() < NONCE_LENGTH {
+    return Err(CommonError::CryptoError("Ciphertext is too short".into()));
+  }
+  let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+  let cipher = Aes256Gcm::new(key);
+  let payload = Payload {
+    msg: ciphertext,
+    aad,
+  };
+  cipher
+    .decrypt(Nonce::from_slice(nonce), payload)
+    .map_err(|_| CommonError::CryptoError("Decryption failed".into()))
+}
+
+fn decode(value: &String) -> CommonResult<Vec<u8>> {
+  STANDARD
+    .decode(value)
+    .map_err(|_| CommonError::CryptoError("Invalid base64 value".into()))
+}
@@ -0,0 +1,104 @@
+use crate::crypto::keyring::{EncryptionKey, Keyring};
+use crate::crypto::secret::EncryptedSecret;
+use crate::db::organization_credential_aws::OrganizationCredentialAws;
+use crate::db::organization_credential_docker_hub::OrganizationCredentialDockerHub;
+use crate::db::organization_credential_ovh::OrganizationCredentialOvh;
+use crate::db::ToCollectionName;
+use crate::CommonResult;
+use bson::{doc, Bson, Document};
+use futures::TryStreamExt;
+use log::info;
+use mongodb::Database;
+
+/// Encrypts the credential secrets still stored as plaintext and
+/// re-encrypts the ones using an older key with the current key.
+/// Returns the number of updated credentials.
+pub async fn migrate_credentials(
+  db: &Database,
+  keyring: &Keyring,
+) -> CommonResult<u64> {
+  migrate_secrets(db, |value| match value {
+    Bson::String(plaintext) => Ok(Some(keyring.encrypt(plaintext)?)),
+    Bson::Document(secret) => {
+      let secret: EncryptedSecret = bson::from_document(secret.clone())?;
+      if !keyring.needs_rewrap(&secret) {
+        return Ok(None);
+      }
+      Ok(Some(keyring.rewrap(&secret)?))
+    }
+    _ => Ok(None),
+  })
+  .await
+}
+
+/// Encrypts the credential secrets still stored as plaintext, which only
+/// needs the public key. Returns the number of updated credentials.
+pub async fn encrypt_plaintext_credentials(
+  db: &Database,
+  key: &EncryptionKey,
+) -> CommonResult<u64> {
+  migrate_secrets(db, |value| match value {
+    Bson::String(plaintext) => Ok(Some(key.encrypt(plaintext)?)),
+    _ => Ok(None),
+  })
+  .await
+}
+
+/// Replaces each secret field of the credentials for which `migrate`
+/// returns a new secret.
+async fn migrate_secrets<F>(
+  db: &Database,
+  migrate: F,
+) -> CommonResult<u64>
+where
+  F: Fn(&Bson) -> CommonResult<Option<EncryptedSecret>>,
+{
+  let collections = [
+    (
+      OrganizationCredentialAws::collection_name(),
+      vec!["secretKey"],
+    ),
+    (
+      OrganizationCredentialOvh::collection_name(),
+      vec!["applicationSecret", "consumerKey"],
+    ),
+    (
+      OrganizationCredentialDockerHub::collection_name(),
+      vec!["accessToken"],
+    ),
+  ];
+  let mut updated = 0;
+  for (collection_name, fields) in collections {
+    let collection = db.collection::<Document>(collection_name.as_str());
+    let mut cursor = collection.find(None, None).await?;
+    while let Some(credential) = cursor.try_next().await? {
+      let mut changes = Document::new();
+      for field in fields.iter() {
+        let secret = match credential.get(*field) {
+          Some(value) => migrate(value)?,
+          None => None,
+        };
+        if let Some(secret) = secret {
+          changes.insert(*field, bson::to_bson(&secret)?);
+        }
+      }
+      if changes.is_empty() {
+        continue;
+      }
+      let id = match credential.get("_id") {
+        Some(id) => id.clone(),
+        None => continue,
+      };
+      let filter = doc! {
+        "_id": id,
+      };
+      let update = doc! {
+        "$set": changes,
+      };
+      collection.update_one(filter, update, None).await?;
+      updated += 1;
+    }
+    info!("Migrated the secrets of {}", collection_name);
+  }
+  Ok(updated)
+}
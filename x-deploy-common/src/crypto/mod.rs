@@ -0,0 +1,5 @@
+pub mod hash;
+pub mod jwt;
+pub mod keyring;
+pub mod migration;
+pub mod secret;
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+/// A secret encrypted with its own data key, the data key being itself
+/// encrypted with the RSA-OAEP key-encryption key `key_id`. The secret is
+/// stored as base64 of the nonce followed by the AES-GCM ciphertext, and
+/// the data key as base64 of its RSA-OAEP ciphertext.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct EncryptedSecret {
+  #[serde(rename = "keyId")]
+  pub key_id: String,
+
+  #[serde(rename = "encryptedKey")]
+  pub encrypted_key: String,
+
+  #[serde(rename = "ciphertext")]
+  pub ciphertext: String,
+}
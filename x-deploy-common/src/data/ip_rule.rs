@@ -0,0 +1,56 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// Whether `rule` is an IP address or a CIDR range such as `10.0.0.0/8`.
+pub fn is_valid_ip_rule(rule: &str) -> bool {
+  parse_ip_rule(rule).is_some()
+}
+
+/// Whether `ip` matches one of the addresses or CIDR ranges of `rules`.
+pub fn is_ip_allowed(
+  rules: &Vec<String>,
+  ip: &IpAddr,
+) -> bool {
+  rules.iter().any(|rule| match parse_ip_rule(rule) {
+    Some((network, prefix_len)) => is_in_network(ip, &network, prefix_len),
+    None => false,
+  })
+}
+
+fn parse_ip_rule(rule: &str) -> Option<(IpAddr, u32)> {
+  let (address, prefix_len) = match rule.split_once('/') {
+    Some((address, prefix_len)) => (address, Some(prefix_len)),
+    None => (rule, None),
+  };
+  let address = IpAddr::from_str(address).ok()?;
+  let max_prefix_len = match address {
+    IpAddr::V4(_) => 32,
+    IpAddr::V6(_) => 128,
+  };
+  let prefix_len = match prefix_len {
+    Some(prefix_len) => prefix_len.parse::<u32>().ok()?,
+    None => max_prefix_len,
+  };
+  if prefix_len > max_prefix_len {
+    return None;
+  }
+  Some((address, prefix_len))
+}
+
+fn is_in_network(
+  ip: &IpAddr,
+  network: &IpAddr,
+  prefix_len: u32,
+) -> bool {
+  return match (ip, network) {
+    (IpAddr::V4(ip), IpAddr::V4(network)) => {
+      let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+      u32::from(*ip) & mask == u32::from(*network) & mask
+    }
+    (IpAddr::V6(ip), IpAddr::V6(network)) => {
+      let mask = u128::MAX.checked_shl(128 - prefix_len).unwrap_or(0);
+      u128::from(*ip) & mask == u128::from(*network) & mask
+    }
+    _ => false,
+  };
+}
@@ -1 +1,2 @@
 pub mod cloud_provider;
+pub mod ip_rule;
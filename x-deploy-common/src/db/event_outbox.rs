@@ -0,0 +1,215 @@
+use crate::db::{CommonCollection, ToCollectionName};
+use crate::event::ToTopicName;
+use crate::CommonResult;
+use bson::oid::ObjectId;
+use bson::{doc, Bson, DateTime};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument};
+use mongodb::results::{InsertOneResult, UpdateResult};
+use mongodb::ClientSession;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::time::Duration;
+
+const EVENT_OUTBOX_COLLECTION_NAME: &str = "eventOutbox";
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum EventOutboxStatus {
+  #[serde(rename = "PENDING")]
+  Pending,
+
+  #[serde(rename = "SENDING")]
+  Sending,
+
+  #[serde(rename = "FAILED")]
+  Failed,
+}
+
+impl Display for EventOutboxStatus {
+  fn fmt(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+  ) -> std::fmt::Result {
+    match self {
+      EventOutboxStatus::Pending => write!(f, "PENDING"),
+      EventOutboxStatus::Sending => write!(f, "SENDING"),
+      EventOutboxStatus::Failed => write!(f, "FAILED"),
+    }
+  }
+}
+
+impl From<EventOutboxStatus> for Bson {
+  fn from(status: EventOutboxStatus) -> Self {
+    Bson::String(status.to_string())
+  }
+}
+
+/// An event waiting to be published on Kafka. It is written next to the
+/// data it describes and removed by the relay once published. An event
+/// which can't be published after several attempts is kept as `Failed`.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct EventOutbox {
+  #[serde(rename = "_id")]
+  pub id: ObjectId,
+
+  #[serde(rename = "topic")]
+  pub topic: String,
+
+  #[serde(rename = "payload")]
+  pub payload: String,
+
+  #[serde(rename = "status")]
+  pub status: EventOutboxStatus,
+
+  #[serde(rename = "attempts")]
+  pub attempts: u32,
+
+  #[serde(rename = "lastError")]
+  pub last_error: Option<String>,
+
+  #[serde(rename = "claimedAt")]
+  pub claimed_at: Option<DateTime>,
+
+  #[serde(rename = "nextAttemptAt")]
+  pub next_attempt_at: Option<DateTime>,
+}
+
+impl EventOutbox {
+  pub fn new<T>(event: &T) -> CommonResult<Self>
+  where
+    T: ToTopicName + Serialize,
+  {
+    Ok(Self {
+      id: ObjectId::new(),
+      topic: T::topic_name(),
+      payload: serde_json::to_string(event)?,
+      status: EventOutboxStatus::Pending,
+      attempts: 0,
+      last_error: None,
+      claimed_at: None,
+      next_attempt_at: None,
+    })
+  }
+}
+
+impl ToCollectionName for EventOutbox {
+  fn collection_name() -> String {
+    String::from(EVENT_OUTBOX_COLLECTION_NAME)
+  }
+}
+
+impl CommonCollection<EventOutbox> {
+  pub async fn add<T>(
+    &self,
+    event: &T,
+  ) -> CommonResult<InsertOneResult>
+  where
+    T: ToTopicName + Serialize,
+  {
+    let outbox = EventOutbox::new(event)?;
+    self.insert_one(&outbox).await
+  }
+
+  /// Adds the event in the transaction of `session`, so that it is only
+  /// published if the data it describes is written.
+  pub async fn add_with_session<T>(
+    &self,
+    event: &T,
+    session: &mut ClientSession,
+  ) -> CommonResult<InsertOneResult>
+  where
+    T: ToTopicName + Serialize,
+  {
+    let outbox = EventOutbox::new(event)?;
+    self.insert_one_with_session(&outbox, session).await
+  }
+
+  /// Claims the oldest pending event whose next attempt is due. Events
+  /// claimed for longer than `claim_timeout` are considered abandoned and
+  /// can be claimed again.
+  pub async fn claim_next(
+    &self,
+    claim_timeout: Duration,
+  ) -> CommonResult<Option<EventOutbox>> {
+    let now = DateTime::now();
+    let expired_claim = DateTime::from_millis(
+      now.timestamp_millis() - claim_timeout.as_millis() as i64,
+    );
+    let filter = doc! {
+      "$or": [
+        {
+          "status": EventOutboxStatus::Pending,
+          "nextAttemptAt": { "$not": { "$gt": now } },
+        },
+        {
+          "status": EventOutboxStatus::Sending,
+          "claimedAt": { "$lt": expired_claim },
+        },
+      ]
+    };
+    let update = doc! {
+      "$set": {
+        "status": EventOutboxStatus::Sending,
+        "claimedAt": now,
+      }
+    };
+    let options = FindOneAndUpdateOptions::builder()
+      .sort(doc! { "_id": 1 })
+      .return_document(ReturnDocument::After)
+      .build();
+    let result = self
+      .collection
+      .find_one_and_update(filter, update, options)
+      .await?;
+    Ok(result)
+  }
+
+  /// Gives a claimed event back to the relay after a failed attempt, to
+  /// be tried again at `next_attempt_at`.
+  pub async fn release(
+    &self,
+    event_id: &ObjectId,
+    error: &String,
+    next_attempt_at: DateTime,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": event_id,
+    };
+    let update = doc! {
+      "$set": {
+        "status": EventOutboxStatus::Pending,
+        "lastError": error,
+        "claimedAt": Bson::Null,
+        "nextAttemptAt": next_attempt_at,
+      },
+      "$inc": {
+        "attempts": 1,
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  /// Sets aside a claimed event after its last failed attempt, it is kept
+  /// with its error but no longer published.
+  pub async fn mark_failed(
+    &self,
+    event_id: &ObjectId,
+    error: &String,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": event_id,
+    };
+    let update = doc! {
+      "$set": {
+        "status": EventOutboxStatus::Failed,
+        "lastError": error,
+        "claimedAt": Bson::Null,
+      },
+      "$inc": {
+        "attempts": 1,
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+}
@@ -0,0 +1,184 @@
+use crate::db::{CommonCollection, ToCollectionName};
+use crate::CommonResult;
+use bson::{doc, Bson, DateTime};
+use mongodb::options::{
+  FindOneAndUpdateOptions, ReturnDocument, UpdateOptions,
+};
+use mongodb::results::UpdateResult;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::time::Duration;
+
+const MAIL_DELIVERY_COLLECTION_NAME: &str = "mailDeliveries";
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum MailDeliveryStatus {
+  #[serde(rename = "PENDING")]
+  Pending,
+
+  #[serde(rename = "SENDING")]
+  Sending,
+
+  #[serde(rename = "SENT")]
+  Sent,
+
+  #[serde(rename = "FAILED")]
+  Failed,
+}
+
+impl Display for MailDeliveryStatus {
+  fn fmt(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+  ) -> std::fmt::Result {
+    match self {
+      MailDeliveryStatus::Pending => write!(f, "PENDING"),
+      MailDeliveryStatus::Sending => write!(f, "SENDING"),
+      MailDeliveryStatus::Sent => write!(f, "SENT"),
+      MailDeliveryStatus::Failed => write!(f, "FAILED"),
+    }
+  }
+}
+
+impl From<MailDeliveryStatus> for Bson {
+  fn from(status: MailDeliveryStatus) -> Self {
+    Bson::String(status.to_string())
+  }
+}
+
+/// An email sent for an event. The id is derived from the event, so that
+/// an event consumed again doesn't send the email twice.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct MailDelivery {
+  #[serde(rename = "_id")]
+  pub id: String,
+
+  #[serde(rename = "template")]
+  pub template: String,
+
+  #[serde(rename = "to")]
+  pub to: String,
+
+  #[serde(rename = "status")]
+  pub status: MailDeliveryStatus,
+
+  #[serde(rename = "attempts")]
+  pub attempts: u32,
+
+  #[serde(rename = "lastError")]
+  pub last_error: Option<String>,
+
+  #[serde(rename = "claimedAt")]
+  pub claimed_at: Option<DateTime>,
+
+  #[serde(rename = "sentAt")]
+  pub sent_at: Option<DateTime>,
+}
+
+impl ToCollectionName for MailDelivery {
+  fn collection_name() -> String {
+    String::from(MAIL_DELIVERY_COLLECTION_NAME)
+  }
+}
+
+impl CommonCollection<MailDelivery> {
+  /// Claims the delivery of `id`, creating it on the first attempt. Returns
+  /// `None` when the email was already sent or another attempt is running,
+  /// attempts claimed for longer than `claim_timeout` are considered
+  /// abandoned and can be claimed again.
+  pub async fn claim(
+    &self,
+    id: &String,
+    template: &String,
+    to: &String,
+    claim_timeout: Duration,
+  ) -> CommonResult<Option<MailDelivery>> {
+    let filter = doc! {
+      "_id": id,
+    };
+    let insert = doc! {
+      "$setOnInsert": {
+        "template": template,
+        "to": to,
+        "status": MailDeliveryStatus::Pending,
+        "attempts": 0,
+        "lastError": Bson::Null,
+        "claimedAt": Bson::Null,
+        "sentAt": Bson::Null,
+      }
+    };
+    let options = UpdateOptions::builder().upsert(true).build();
+    self.collection.update_one(filter, insert, options).await?;
+    let now = DateTime::now();
+    let expired_claim = DateTime::from_millis(
+      now.timestamp_millis() - claim_timeout.as_millis() as i64,
+    );
+    let filter = doc! {
+      "_id": id,
+      "$or": [
+        { "status": MailDeliveryStatus::Pending },
+        { "status": MailDeliveryStatus::Failed },
+        {
+          "status": MailDeliveryStatus::Sending,
+          "claimedAt": { "$lt": expired_claim },
+        },
+      ]
+    };
+    let update = doc! {
+      "$set": {
+        "status": MailDeliveryStatus::Sending,
+        "claimedAt": now,
+      },
+      "$inc": {
+        "attempts": 1,
+      }
+    };
+    let options = FindOneAndUpdateOptions::builder()
+      .return_document(ReturnDocument::After)
+      .build();
+    let result = self
+      .collection
+      .find_one_and_update(filter, update, options)
+      .await?;
+    Ok(result)
+  }
+
+  pub async fn mark_sent(
+    &self,
+    id: &String,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+    };
+    let update = doc! {
+      "$set": {
+        "status": MailDeliveryStatus::Sent,
+        "sentAt": DateTime::now(),
+        "claimedAt": Bson::Null,
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  /// Releases a claimed delivery after a failed attempt, so that it can be
+  /// retried.
+  pub async fn mark_failed(
+    &self,
+    id: &String,
+    error: &String,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+    };
+    let update = doc! {
+      "$set": {
+        "status": MailDeliveryStatus::Failed,
+        "lastError": error,
+        "claimedAt": Bson::Null,
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+}
@@ -1,10 +1,13 @@
 use crate::db::user::User;
 use crate::CommonResult;
 use bson::{doc, oid};
+use mongodb::ClientSession;
 use oid::ObjectId;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+pub mod event_outbox;
+pub mod mail_delivery;
 pub mod organization;
 pub mod organization_apikey;
 pub mod organization_credential_aws;
@@ -14,11 +17,30 @@ pub mod organization_invitation;
 pub mod organization_member;
 pub mod organization_project;
 pub mod organization_project_cluster;
-mod organization_project_deployment;
+pub mod organization_project_deployment;
 pub mod organization_project_environment;
 pub mod organization_role;
 pub mod query;
 pub mod user;
+pub mod user_session;
+
+/// Starts a session running a transaction. The writes done with it are
+/// applied together by `commit_transaction`, or not at all when the session
+/// is dropped before.
+pub async fn start_transaction(
+  client: &mongodb::Client
+) -> CommonResult<ClientSession> {
+  let mut session = client.start_session(None).await?;
+  session.start_transaction(None).await?;
+  Ok(session)
+}
+
+pub async fn commit_transaction(
+  session: &mut ClientSession
+) -> CommonResult<()> {
+  session.commit_transaction().await?;
+  Ok(())
+}
 
 pub trait ToCollectionName {
   fn collection_name() -> String;
@@ -48,6 +70,18 @@ where
     Ok(result)
   }
 
+  pub async fn insert_one_with_session(
+    &self,
+    document: &T,
+    session: &mut ClientSession,
+  ) -> CommonResult<mongodb::results::InsertOneResult> {
+    let result = self
+      .collection
+      .insert_one_with_session(document, None, session)
+      .await?;
+    Ok(result)
+  }
+
   pub async fn insert_many(
     &self,
     documents: &Vec<T>,
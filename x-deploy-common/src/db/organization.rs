@@ -1,9 +1,11 @@
 use crate::db::{CommonCollection, ToCollectionName};
 use crate::CommonResult;
 use bson::oid::ObjectId;
-use bson::{doc, Bson};
+use bson::{doc, Bson, DateTime};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument};
 use mongodb::results::UpdateResult;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 const ORGANIZATION_COLLECTION_NAME: &str = "organizations";
 
@@ -26,6 +28,22 @@ pub struct Organization {
 
   #[serde(rename = "contactEmail")]
   pub contact_email: Option<String>,
+
+  /// Set when the owner deleted the organization, it is hidden until it
+  /// is restored or purged
+  #[serde(rename = "deletedAt", default)]
+  pub deleted_at: Option<DateTime>,
+
+  #[serde(rename = "deletedBy", default)]
+  pub deleted_by: Option<ObjectId>,
+
+  /// End of the restore window, the organization and everything it owns
+  /// are purged afterward
+  #[serde(rename = "purgeAt", default)]
+  pub purge_at: Option<DateTime>,
+
+  #[serde(rename = "purgeClaimedAt", default)]
+  pub purge_claimed_at: Option<DateTime>,
 }
 
 impl Organization {
@@ -42,6 +60,10 @@ impl Organization {
       logo_url: None,
       website,
       contact_email,
+      deleted_at: None,
+      deleted_by: None,
+      purge_at: None,
+      purge_claimed_at: None,
     }
   }
 }
@@ -96,4 +118,81 @@ impl CommonCollection<Organization> {
     let result = self.collection.update_one(filter, update, None).await?;
     Ok(result)
   }
+
+  pub async fn soft_delete(
+    &self,
+    org_id: &ObjectId,
+    deleted_by: &ObjectId,
+    purge_at: DateTime,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": org_id,
+      "deletedAt": Bson::Null,
+    };
+    let update = doc! {
+      "$set": {
+        "deletedAt": DateTime::now(),
+        "deletedBy": deleted_by,
+        "purgeAt": purge_at,
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  /// Cancels the deletion, only while the restore window is open.
+  pub async fn restore(
+    &self,
+    org_id: &ObjectId,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": org_id,
+      "deletedAt": { "$ne": Bson::Null },
+      "purgeAt": { "$gt": DateTime::now() },
+    };
+    let update = doc! {
+      "$set": {
+        "deletedAt": Bson::Null,
+        "deletedBy": Bson::Null,
+        "purgeAt": Bson::Null,
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  /// Claims a deleted organization whose restore window is over. Purges
+  /// claimed for longer than `claim_timeout` are considered abandoned and
+  /// can be claimed again.
+  pub async fn claim_next_purge(
+    &self,
+    claim_timeout: Duration,
+  ) -> CommonResult<Option<Organization>> {
+    let now = DateTime::now();
+    let expired_claim = DateTime::from_millis(
+      now.timestamp_millis() - claim_timeout.as_millis() as i64,
+    );
+    let filter = doc! {
+      "deletedAt": { "$ne": Bson::Null },
+      "purgeAt": { "$lte": now },
+      "$or": [
+        { "purgeClaimedAt": Bson::Null },
+        { "purgeClaimedAt": { "$lt": expired_claim } },
+      ]
+    };
+    let update = doc! {
+      "$set": {
+        "purgeClaimedAt": now,
+      }
+    };
+    let options = FindOneAndUpdateOptions::builder()
+      .sort(doc! { "purgeAt": 1 })
+      .return_document(ReturnDocument::After)
+      .build();
+    let result = self
+      .collection
+      .find_one_and_update(filter, update, options)
+      .await?;
+    Ok(result)
+  }
 }
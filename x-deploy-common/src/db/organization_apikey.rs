@@ -1,12 +1,16 @@
+use crate::crypto::hash::hash_token;
 use crate::db::{CommonCollection, ToCollectionName};
 use crate::CommonResult;
-use bson::doc;
 use bson::oid::ObjectId;
+use bson::{doc, Bson, Document};
+use futures::TryStreamExt;
 use mongodb::results::{DeleteResult, UpdateResult};
 use serde::{Deserialize, Serialize};
 
 const ORGANIZATION_APIKEY_COLLECTION_NAME: &str = "organizations";
 
+const API_KEY_DISPLAY_PREFIX_LENGTH: usize = 12;
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct OrganizationApiKey {
   #[serde(rename = "_id")]
@@ -18,8 +22,11 @@ pub struct OrganizationApiKey {
   #[serde(rename = "description")]
   pub description: Option<String>,
 
-  #[serde(rename = "key")]
-  pub key: String,
+  #[serde(rename = "keyHash")]
+  pub key_hash: String,
+
+  #[serde(rename = "keyPrefix")]
+  pub key_prefix: String,
 
   #[serde(rename = "organizationId")]
   pub organization_id: ObjectId,
@@ -29,27 +36,64 @@ pub struct OrganizationApiKey {
 
   #[serde(rename = "expiresAt")]
   pub expires_at: Option<bson::DateTime>,
+
+  /// Hash of the key replaced by the last rotation, still accepted until
+  /// `previous_key_expires_at`
+  #[serde(rename = "previousKeyHash")]
+  pub previous_key_hash: Option<String>,
+
+  #[serde(rename = "previousKeyExpiresAt")]
+  pub previous_key_expires_at: Option<bson::DateTime>,
+
+  #[serde(rename = "lastUsedAt")]
+  pub last_used_at: Option<bson::DateTime>,
+
+  #[serde(rename = "lastUsedIp")]
+  pub last_used_ip: Option<String>,
+
+  /// IP addresses or CIDR ranges allowed to use the key, any address is
+  /// allowed when empty
+  #[serde(rename = "allowedIps", default)]
+  pub allowed_ips: Vec<String>,
 }
 
 impl OrganizationApiKey {
+  /// Only the hash of `key` and its first characters are stored, the key
+  /// itself can't be retrieved afterwards.
   pub fn new(
     name: String,
     description: Option<String>,
-    key: String,
+    key: &String,
     organization_id: ObjectId,
     role_id: Option<ObjectId>,
     expires_at: Option<bson::DateTime>,
+    allowed_ips: Vec<String>,
   ) -> Self {
     Self {
       id: ObjectId::new(),
       name,
       organization_id,
-      key,
+      key_hash: Self::hash_key(key),
+      key_prefix: Self::display_prefix(key),
       description,
       role_id,
       expires_at,
+      previous_key_hash: None,
+      previous_key_expires_at: None,
+      last_used_at: None,
+      last_used_ip: None,
+      allowed_ips,
     }
   }
+
+  pub fn hash_key(key: &str) -> String {
+    hash_token(key)
+  }
+
+  /// The start of the key shown to users to recognize their keys.
+  pub fn display_prefix(key: &str) -> String {
+    key.chars().take(API_KEY_DISPLAY_PREFIX_LENGTH).collect()
+  }
 }
 
 impl ToCollectionName for OrganizationApiKey {
@@ -59,11 +103,14 @@ impl ToCollectionName for OrganizationApiKey {
 }
 
 impl CommonCollection<OrganizationApiKey> {
+  /// Updates the name and the description of a key, and its allowed IPs
+  /// when they are given.
   pub async fn update_info(
     &self,
     id: &ObjectId,
     name: &String,
     description: &Option<String>,
+    allowed_ips: &Option<Vec<String>>,
   ) -> CommonResult<UpdateResult> {
     let filter = doc! {
       "_id": id,
@@ -72,14 +119,77 @@ impl CommonCollection<OrganizationApiKey> {
       Some(description) => bson::to_bson(description)?,
       None => bson::Bson::Null,
     };
+    let mut set = doc! {
+      "name": name,
+      "description": bson_description,
+    };
+    if let Some(allowed_ips) = allowed_ips {
+      set.insert("allowedIps", allowed_ips.clone());
+    }
+    let update = doc! {
+      "$set": set,
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
+
+  /// Replaces the key, the current one stays valid until
+  /// `previous_key_expires_at`.
+  pub async fn rotate(
+    &self,
+    id: &ObjectId,
+    key: &String,
+    previous_key_hash: &String,
+    previous_key_expires_at: bson::DateTime,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+    };
     let update = doc! {
       "$set": {
-        "name": name,
-        "description": bson_description,
+        "keyHash": OrganizationApiKey::hash_key(key),
+        "keyPrefix": OrganizationApiKey::display_prefix(key),
+        "previousKeyHash": previous_key_hash,
+        "previousKeyExpiresAt": previous_key_expires_at,
       },
     };
     let result = self.collection.update_one(filter, update, None).await?;
-    return Ok(result);
+    Ok(result)
+  }
+
+  pub async fn update_last_used(
+    &self,
+    id: &ObjectId,
+    ip: Option<String>,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+    };
+    let bson_ip = match ip {
+      Some(ip) => Bson::String(ip),
+      None => Bson::Null,
+    };
+    let update = doc! {
+      "$set": {
+        "lastUsedAt": bson::DateTime::now(),
+        "lastUsedIp": bson_ip,
+      },
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  pub async fn delete_by_id_of_org(
+    &self,
+    org_id: &ObjectId,
+    id: &ObjectId,
+  ) -> CommonResult<DeleteResult> {
+    let filter = doc! {
+      "_id": id,
+      "organizationId": org_id,
+    };
+    let result = self.collection.delete_one(filter, None).await?;
+    Ok(result)
   }
 
   pub async fn delete_of_org(
@@ -92,4 +202,36 @@ impl CommonCollection<OrganizationApiKey> {
     let result = self.collection.delete_many(filter, None).await?;
     Ok(result)
   }
+
+  /// Replaces the cleartext keys stored before keys were hashed by their
+  /// hash. Returns the number of migrated keys.
+  pub async fn migrate_key_hashes(&self) -> CommonResult<u64> {
+    let collection = self.collection.clone_with_type::<Document>();
+    let filter = doc! {
+      "key": { "$type": "string" },
+    };
+    let mut cursor = collection.find(filter, None).await?;
+    let mut migrated = 0;
+    while let Some(api_key) = cursor.try_next().await? {
+      let (id, key) = match (api_key.get("_id"), api_key.get("key")) {
+        (Some(id), Some(Bson::String(key))) => (id.clone(), key.clone()),
+        _ => continue,
+      };
+      let filter = doc! {
+        "_id": id,
+      };
+      let update = doc! {
+        "$set": {
+          "keyHash": OrganizationApiKey::hash_key(&key),
+          "keyPrefix": OrganizationApiKey::display_prefix(&key),
+        },
+        "$unset": {
+          "key": "",
+        },
+      };
+      collection.update_one(filter, update, None).await?;
+      migrated += 1;
+    }
+    Ok(migrated)
+  }
 }
@@ -1,3 +1,4 @@
+use crate::crypto::secret::EncryptedSecret;
 use crate::db::query::cursor_to_vec;
 use crate::db::{CommonCollection, ToCollectionName};
 use crate::CommonResult;
@@ -27,7 +28,7 @@ pub struct OrganizationCredentialAws {
   pub access_key: String,
 
   #[serde(rename = "secretKey")]
-  pub secret_key: String,
+  pub secret_key: EncryptedSecret,
 }
 
 impl OrganizationCredentialAws {
@@ -36,7 +37,7 @@ impl OrganizationCredentialAws {
     name: String,
     description: Option<String>,
     access_key: String,
-    secret_key: String,
+    secret_key: EncryptedSecret,
   ) -> Self {
     Self {
       id: ObjectId::new(),
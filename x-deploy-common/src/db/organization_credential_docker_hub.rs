@@ -1,3 +1,4 @@
+use crate::crypto::secret::EncryptedSecret;
 use crate::db::query::cursor_to_vec;
 use crate::db::{CommonCollection, ToCollectionName};
 use crate::CommonResult;
@@ -24,7 +25,7 @@ pub struct OrganizationCredentialDockerHub {
   pub description: Option<String>,
 
   #[serde(rename = "accessToken")]
-  pub access_token: String,
+  pub access_token: EncryptedSecret,
 }
 
 impl OrganizationCredentialDockerHub {
@@ -32,7 +33,7 @@ impl OrganizationCredentialDockerHub {
     organization_id: ObjectId,
     name: String,
     description: Option<String>,
-    access_token: String,
+    access_token: EncryptedSecret,
   ) -> Self {
     Self {
       id: ObjectId::new(),
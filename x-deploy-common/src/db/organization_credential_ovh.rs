@@ -1,3 +1,4 @@
+use crate::crypto::secret::EncryptedSecret;
 use crate::db::query::cursor_to_vec;
 use crate::db::{CommonCollection, ToCollectionName};
 use crate::CommonResult;
@@ -27,10 +28,15 @@ pub struct OrganizationCredentialOvh {
   pub application_key: String,
 
   #[serde(rename = "applicationSecret")]
-  pub application_secret: String,
+  pub application_secret: EncryptedSecret,
 
   #[serde(rename = "consumerKey")]
-  pub consumer_key: String,
+  pub consumer_key: EncryptedSecret,
+
+  /// The public cloud project of the clusters, absent for the credentials
+  /// added before it was asked
+  #[serde(rename = "projectId", default)]
+  pub project_id: Option<String>,
 }
 
 impl OrganizationCredentialOvh {
@@ -39,8 +45,9 @@ impl OrganizationCredentialOvh {
     name: String,
     description: Option<String>,
     application_key: String,
-    application_secret: String,
-    consumer_key: String,
+    application_secret: EncryptedSecret,
+    consumer_key: EncryptedSecret,
+    project_id: String,
   ) -> Self {
     Self {
       id: ObjectId::new(),
@@ -50,6 +57,7 @@ impl OrganizationCredentialOvh {
       application_key,
       application_secret,
       consumer_key,
+      project_id: Some(project_id),
     }
   }
 }
@@ -134,5 +142,4 @@ impl CommonCollection<OrganizationCredentialOvh> {
     let result = self.collection.delete_many(filter, None).await?;
     Ok(result)
   }
-  
 }
@@ -4,7 +4,7 @@ use crate::CommonResult;
 use bson::oid::ObjectId;
 use bson::{doc, Bson};
 use chrono::{DateTime, Utc};
-use mongodb::results::UpdateResult;
+use mongodb::results::{DeleteResult, UpdateResult};
 use mongodb::{Collection, Database};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
@@ -115,6 +115,17 @@ impl CommonCollection<OrganizationInvitation> {
     let result = cursor_to_vec(result).await?;
     return Ok(result);
   }
+
+  pub async fn delete_of_org(
+    &self,
+    org_id: &ObjectId,
+  ) -> CommonResult<DeleteResult> {
+    let filter = doc! {
+      "organizationId": org_id,
+    };
+    let result = self.collection.delete_many(filter, None).await?;
+    Ok(result)
+  }
   
   pub async fn update_status(
     &self,
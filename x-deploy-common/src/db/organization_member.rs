@@ -2,9 +2,10 @@ use crate::db::organization_role::OrganizationRole;
 use crate::db::query::cursor_to_vec;
 use crate::db::{CommonCollection, ToCollectionName};
 use crate::CommonResult;
-use bson::doc;
 use bson::oid::ObjectId;
+use bson::{doc, Bson};
 use mongodb::results::DeleteResult;
+use mongodb::ClientSession;
 use serde::{Deserialize, Serialize};
 
 const ORGANIZATION_MEMBER_COLLECTION_NAME: &str = "organizationMembers";
@@ -63,6 +64,20 @@ impl CommonCollection<OrganizationMember> {
     return Ok(result);
   }
 
+  /// The membership of a user, even in a deleted organization.
+  pub async fn get_by_user_and_org(
+    &self,
+    org_id: &ObjectId,
+    user_id: &ObjectId,
+  ) -> CommonResult<Option<OrganizationMember>> {
+    let filter = doc! {
+      "organizationId": org_id,
+      "userId": user_id,
+    };
+    let result = self.collection.find_one(filter, None).await?;
+    Ok(result)
+  }
+
   pub async fn delete_by_id_and_org(
     &self,
     id: &ObjectId,
@@ -75,4 +90,61 @@ impl CommonCollection<OrganizationMember> {
     let result = self.collection.delete_one(filter, None).await?;
     Ok(result)
   }
+
+  pub async fn delete_of_org(
+    &self,
+    org_id: &ObjectId,
+  ) -> CommonResult<DeleteResult> {
+    let filter = doc! {
+      "organizationId": org_id,
+    };
+    let result = self.collection.delete_many(filter, None).await?;
+    Ok(result)
+  }
+
+  /// Makes `new_owner_id` the owner of the organization in the transaction
+  /// of `session`, the previous owner stays as a member with
+  /// `old_owner_role`. Returns false when `old_owner_id` is no longer the
+  /// owner or `new_owner_id` isn't a member, the transaction must then be
+  /// dropped rather than committed.
+  pub async fn transfer_ownership(
+    &self,
+    session: &mut ClientSession,
+    org_id: &ObjectId,
+    old_owner_id: &ObjectId,
+    new_owner_id: &ObjectId,
+    old_owner_role: &ObjectId,
+  ) -> CommonResult<bool> {
+    let filter = doc! {
+      "organizationId": org_id,
+      "userId": old_owner_id,
+      "isOwner": true,
+    };
+    let update = doc! {
+      "$set": {
+        "isOwner": false,
+        "role": old_owner_role,
+      }
+    };
+    let demoted = self
+      .collection
+      .update_one_with_session(filter, update, None, session)
+      .await?;
+    let filter = doc! {
+      "organizationId": org_id,
+      "userId": new_owner_id,
+      "isOwner": false,
+    };
+    let update = doc! {
+      "$set": {
+        "isOwner": true,
+        "role": Bson::Null,
+      }
+    };
+    let promoted = self
+      .collection
+      .update_one_with_session(filter, update, None, session)
+      .await?;
+    Ok(demoted.modified_count == 1 && promoted.modified_count == 1)
+  }
 }
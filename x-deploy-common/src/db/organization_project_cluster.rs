@@ -1,9 +1,10 @@
 use crate::db::query::cursor_to_vec;
 use crate::db::{CommonCollection, ToCollectionName};
 use crate::CommonResult;
-use bson::doc;
 use bson::oid::ObjectId;
-use mongodb::results::DeleteResult;
+use bson::{doc, Bson};
+use mongodb::results::{DeleteResult, UpdateResult};
+use mongodb::ClientSession;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
@@ -39,6 +40,12 @@ impl Display for ClusterStatus {
   }
 }
 
+impl From<ClusterStatus> for Bson {
+  fn from(status: ClusterStatus) -> Self {
+    Bson::String(status.to_string())
+  }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct OrganizationProjectCluster {
   #[serde(rename = "_id")]
@@ -96,6 +103,18 @@ impl OrganizationProjectCluster {
 }
 
 impl CommonCollection<OrganizationProjectCluster> {
+  pub async fn get_of_org(
+    &self,
+    org_id: &ObjectId,
+  ) -> CommonResult<Vec<OrganizationProjectCluster>> {
+    let filter = doc! {
+      "organizationId": org_id,
+    };
+    let cursor = self.collection.find(filter, None).await?;
+    let clusters = cursor_to_vec(cursor).await?;
+    Ok(clusters)
+  }
+
   pub async fn get_of_org_and_project(
     &self,
     org_id: &ObjectId,
@@ -125,6 +144,67 @@ impl CommonCollection<OrganizationProjectCluster> {
     Ok(cluster)
   }
 
+  pub async fn update_info(
+    &self,
+    cluster_id: &ObjectId,
+    name: &String,
+    description: &Option<String>,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": cluster_id,
+    };
+    let bson_description = match description {
+      Some(description) => Bson::String(description.clone()),
+      None => Bson::Null,
+    };
+    let update = doc! {
+      "$set": {
+        "name": name,
+        "description": bson_description,
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  pub async fn update_status(
+    &self,
+    cluster_id: &ObjectId,
+    status: ClusterStatus,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": cluster_id,
+    };
+    let update = doc! {
+      "$set": {
+        "status": status,
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  pub async fn update_status_with_session(
+    &self,
+    cluster_id: &ObjectId,
+    status: ClusterStatus,
+    session: &mut ClientSession,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": cluster_id,
+    };
+    let update = doc! {
+      "$set": {
+        "status": status,
+      }
+    };
+    let result = self
+      .collection
+      .update_one_with_session(filter, update, None, session)
+      .await?;
+    Ok(result)
+  }
+
   pub async fn delete_of_org(
     &self,
     org_id: &ObjectId,
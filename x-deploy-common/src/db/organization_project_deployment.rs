@@ -1,5 +1,10 @@
-use crate::db::ToCollectionName;
+use crate::db::query::cursor_to_vec;
+use crate::db::{CommonCollection, ToCollectionName};
+use crate::CommonResult;
 use bson::oid::ObjectId;
+use bson::{doc, Bson};
+use mongodb::results::{DeleteResult, UpdateResult};
+use mongodb::ClientSession;
 use serde::{Deserialize, Serialize};
 
 const ORGANIZATION_PROJECT_DEPLOYMENT_COLLECTION_NAME: &str =
@@ -28,12 +33,139 @@ pub struct OrganizationProjectDeployment {
   #[serde(rename = "projectId")]
   pub project_id: ObjectId,
 
+  #[serde(rename = "environmentId")]
+  pub environment_id: ObjectId,
+
   #[serde(rename = "clusterId")]
   pub cluster_id: ObjectId,
 }
 
+impl OrganizationProjectDeployment {
+  pub fn new(
+    organization_id: ObjectId,
+    project_id: ObjectId,
+    environment_id: ObjectId,
+    cluster_id: ObjectId,
+    name: String,
+    description: Option<String>,
+    image_name: String,
+    image_tag: String,
+  ) -> Self {
+    Self {
+      id: ObjectId::new(),
+      name,
+      description,
+      image_name,
+      image_tag,
+      organization_id,
+      project_id,
+      environment_id,
+      cluster_id,
+    }
+  }
+}
+
 impl ToCollectionName for OrganizationProjectDeployment {
   fn collection_name() -> String {
     String::from(ORGANIZATION_PROJECT_DEPLOYMENT_COLLECTION_NAME)
   }
 }
+
+impl CommonCollection<OrganizationProjectDeployment> {
+  pub async fn get_of_project(
+    &self,
+    org_id: &ObjectId,
+    project_id: &ObjectId,
+  ) -> CommonResult<Vec<OrganizationProjectDeployment>> {
+    let filter = doc! {
+      "organizationId": org_id,
+      "projectId": project_id,
+    };
+    let cursor = self.collection.find(filter, None).await?;
+    let deployments = cursor_to_vec(cursor).await?;
+    Ok(deployments)
+  }
+
+  pub async fn get_with_id_of_project(
+    &self,
+    org_id: &ObjectId,
+    project_id: &ObjectId,
+    deployment_id: &ObjectId,
+  ) -> CommonResult<Option<OrganizationProjectDeployment>> {
+    let filter = doc! {
+      "_id": deployment_id,
+      "organizationId": org_id,
+      "projectId": project_id,
+    };
+    let deployment = self.collection.find_one(filter, None).await?;
+    Ok(deployment)
+  }
+
+  pub async fn count_of_environment(
+    &self,
+    environment_id: &ObjectId,
+  ) -> CommonResult<u64> {
+    let filter = doc! {
+      "environmentId": environment_id,
+    };
+    let count = self.collection.count_documents(filter, None).await?;
+    Ok(count)
+  }
+
+  pub async fn update_info(
+    &self,
+    deployment_id: &ObjectId,
+    name: &String,
+    description: &Option<String>,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": deployment_id,
+    };
+    let bson_description = match description {
+      Some(description) => Bson::String(description.clone()),
+      None => Bson::Null,
+    };
+    let update = doc! {
+      "$set": {
+        "name": name,
+        "description": bson_description,
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  pub async fn update_image_with_session(
+    &self,
+    deployment_id: &ObjectId,
+    image_name: &String,
+    image_tag: &String,
+    session: &mut ClientSession,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": deployment_id,
+    };
+    let update = doc! {
+      "$set": {
+        "imageName": image_name,
+        "imageTag": image_tag,
+      }
+    };
+    let result = self
+      .collection
+      .update_one_with_session(filter, update, None, session)
+      .await?;
+    Ok(result)
+  }
+
+  pub async fn delete_of_org(
+    &self,
+    org_id: &ObjectId,
+  ) -> CommonResult<DeleteResult> {
+    let filter = doc! {
+      "organizationId": org_id,
+    };
+    let result = self.collection.delete_many(filter, None).await?;
+    Ok(result)
+  }
+}
@@ -1,5 +1,9 @@
-use crate::db::ToCollectionName;
+use crate::db::query::cursor_to_vec;
+use crate::db::{CommonCollection, ToCollectionName};
+use crate::CommonResult;
 use bson::oid::ObjectId;
+use bson::{doc, Bson};
+use mongodb::results::{DeleteResult, UpdateResult};
 use serde::{Deserialize, Serialize};
 
 const ORGANIZATION_PROJECT_ENVIRONMENT_COLLECTION_NAME: &str =
@@ -50,3 +54,107 @@ impl ToCollectionName for OrganizationProjectEnvironment {
     String::from(ORGANIZATION_PROJECT_ENVIRONMENT_COLLECTION_NAME)
   }
 }
+
+impl CommonCollection<OrganizationProjectEnvironment> {
+  pub async fn get_of_project(
+    &self,
+    org_id: &ObjectId,
+    project_id: &ObjectId,
+  ) -> CommonResult<Vec<OrganizationProjectEnvironment>> {
+    let filter = doc! {
+      "organizationId": org_id,
+      "projectId": project_id,
+    };
+    let cursor = self.collection.find(filter, None).await?;
+    let environments = cursor_to_vec(cursor).await?;
+    Ok(environments)
+  }
+
+  pub async fn get_with_id_of_project(
+    &self,
+    org_id: &ObjectId,
+    project_id: &ObjectId,
+    environment_id: &ObjectId,
+  ) -> CommonResult<Option<OrganizationProjectEnvironment>> {
+    let filter = doc! {
+      "_id": environment_id,
+      "organizationId": org_id,
+      "projectId": project_id,
+    };
+    let environment = self.collection.find_one(filter, None).await?;
+    Ok(environment)
+  }
+
+  pub async fn get_with_id_of_org(
+    &self,
+    org_id: &ObjectId,
+    environment_id: &ObjectId,
+  ) -> CommonResult<Option<OrganizationProjectEnvironment>> {
+    let filter = doc! {
+      "_id": environment_id,
+      "organizationId": org_id,
+    };
+    let environment = self.collection.find_one(filter, None).await?;
+    Ok(environment)
+  }
+
+  pub async fn get_with_name_of_project(
+    &self,
+    org_id: &ObjectId,
+    project_id: &ObjectId,
+    name: &String,
+  ) -> CommonResult<Option<OrganizationProjectEnvironment>> {
+    let filter = doc! {
+      "organizationId": org_id,
+      "projectId": project_id,
+      "name": name,
+    };
+    let environment = self.collection.find_one(filter, None).await?;
+    Ok(environment)
+  }
+
+  pub async fn count_of_cluster(
+    &self,
+    cluster_id: &ObjectId,
+  ) -> CommonResult<u64> {
+    let filter = doc! {
+      "clusterId": cluster_id,
+    };
+    let count = self.collection.count_documents(filter, None).await?;
+    Ok(count)
+  }
+
+  pub async fn update_info(
+    &self,
+    environment_id: &ObjectId,
+    name: &String,
+    description: &Option<String>,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": environment_id,
+    };
+    let bson_description = match description {
+      Some(description) => Bson::String(description.clone()),
+      None => Bson::Null,
+    };
+    let update = doc! {
+      "$set": {
+        "name": name,
+        "description": bson_description,
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  pub async fn delete_of_org(
+    &self,
+    org_id: &ObjectId,
+  ) -> CommonResult<DeleteResult> {
+    let filter = doc! {
+      "organizationId": org_id,
+    };
+    let result = self.collection.delete_many(filter, None).await?;
+    Ok(result)
+  }
+}
@@ -29,6 +29,9 @@ pub struct OrganizationRole {
 
   #[serde(rename = "generalPermission")]
   pub general_permission: GeneralPermission,
+
+  #[serde(rename = "environmentPermissions", default)]
+  pub environment_permissions: Vec<RoleEnvironmentPermission>,
 }
 
 impl OrganizationRole {
@@ -46,8 +49,22 @@ impl OrganizationRole {
       description,
       cluster_permission,
       general_permission,
+      environment_permissions: Vec::new(),
     }
   }
+
+  /// Returns the permission granted by this role on an environment, or
+  /// None when the role doesn't define one for it.
+  pub fn get_environment_permission(
+    &self,
+    environment_id: &ObjectId,
+  ) -> Option<EnvironmentPermission> {
+    self
+      .environment_permissions
+      .iter()
+      .find(|p| p.environment_id == *environment_id)
+      .map(|p| p.permission.clone())
+  }
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -121,7 +138,7 @@ pub enum EnvironmentPermission {
   #[serde(rename = "DEPLOY")]
   Deploy,
 
-  #[serde(rename = "Manage")]
+  #[serde(rename = "MANAGE")]
   Manage,
 
   #[serde(rename = "FULL_ACCESS")]
@@ -134,6 +151,38 @@ impl Default for EnvironmentPermission {
   }
 }
 
+impl From<EnvironmentPermission> for Bson {
+  fn from(permission: EnvironmentPermission) -> Self {
+    match permission {
+      EnvironmentPermission::NoAccess => Bson::String("NO_ACCESS".to_string()),
+      EnvironmentPermission::Read => Bson::String("READ".to_string()),
+      EnvironmentPermission::Deploy => Bson::String("DEPLOY".to_string()),
+      EnvironmentPermission::Manage => Bson::String("MANAGE".to_string()),
+      EnvironmentPermission::FullAccess => {
+        Bson::String("FULL_ACCESS".to_string())
+      }
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct RoleEnvironmentPermission {
+  #[serde(rename = "environmentId")]
+  pub environment_id: ObjectId,
+
+  #[serde(rename = "permission")]
+  pub permission: EnvironmentPermission,
+}
+
+impl From<RoleEnvironmentPermission> for Bson {
+  fn from(value: RoleEnvironmentPermission) -> Self {
+    return Bson::from(doc! {
+      "environmentId": value.environment_id,
+      "permission": value.permission,
+    });
+  }
+}
+
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct GeneralPermission {
   #[serde(rename = "organization")]
@@ -267,6 +316,44 @@ impl CommonCollection<OrganizationRole> {
     Ok(result)
   }
 
+  pub async fn update_environment_permissions(
+    &self,
+    org_id: &ObjectId,
+    role_id: &ObjectId,
+    permissions: &Vec<RoleEnvironmentPermission>,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": role_id,
+      "organizationId": org_id,
+    };
+    let update = doc! {
+      "$set": {
+        "environmentPermissions": permissions.clone(),
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  pub async fn remove_environment_permission(
+    &self,
+    org_id: &ObjectId,
+    environment_id: &ObjectId,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "organizationId": org_id,
+    };
+    let update = doc! {
+      "$pull": {
+        "environmentPermissions": {
+          "environmentId": environment_id,
+        }
+      }
+    };
+    let result = self.collection.update_many(filter, update, None).await?;
+    Ok(result)
+  }
+
   pub async fn update_info(
     &self,
     org_id: &ObjectId,
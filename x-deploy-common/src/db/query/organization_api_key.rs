@@ -1,10 +1,11 @@
+use crate::db::organization::Organization;
 use crate::db::organization_apikey::OrganizationApiKey;
 use crate::db::organization_role::OrganizationRole;
 use crate::db::query::cursor_doc_to_vec;
 use crate::db::{CommonCollection, ToCollectionName};
 use crate::CommonResult;
-use bson::doc;
 use bson::oid::ObjectId;
+use bson::{doc, Bson};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -18,8 +19,11 @@ pub struct OrganizationApiKeyQuery {
   #[serde(rename = "description")]
   pub description: Option<String>,
 
-  #[serde(rename = "key")]
-  pub key: String,
+  #[serde(rename = "keyHash")]
+  pub key_hash: String,
+
+  #[serde(rename = "keyPrefix")]
+  pub key_prefix: String,
 
   #[serde(rename = "organizationId")]
   pub organization_id: ObjectId,
@@ -29,6 +33,18 @@ pub struct OrganizationApiKeyQuery {
 
   #[serde(rename = "expiresAt")]
   pub expires_at: Option<bson::DateTime>,
+
+  #[serde(rename = "previousKeyExpiresAt")]
+  pub previous_key_expires_at: Option<bson::DateTime>,
+
+  #[serde(rename = "lastUsedAt")]
+  pub last_used_at: Option<bson::DateTime>,
+
+  #[serde(rename = "lastUsedIp")]
+  pub last_used_ip: Option<String>,
+
+  #[serde(rename = "allowedIps", default)]
+  pub allowed_ips: Vec<String>,
 }
 
 impl CommonCollection<OrganizationApiKey> {
@@ -67,14 +83,23 @@ impl CommonCollection<OrganizationApiKey> {
     Ok(result.pop())
   }
 
+  /// Finds a key from the value given by a client, through its hash.
   pub async fn get_by_value(
     &self,
     value: &str,
   ) -> CommonResult<Option<OrganizationApiKeyQuery>> {
     let mut pipeline = self.default_pipeline();
+    let key_hash = OrganizationApiKey::hash_key(value);
+    // The previous key of a rotation is accepted during its grace period
     let match_stage = doc! {
       "$match": {
-        "key": value
+        "$or": [
+          { "keyHash": &key_hash },
+          {
+            "previousKeyHash": &key_hash,
+            "previousKeyExpiresAt": { "$gt": bson::DateTime::now() },
+          },
+        ]
       }
     };
     pipeline.insert(0, match_stage);
@@ -86,7 +111,22 @@ impl CommonCollection<OrganizationApiKey> {
 
   fn default_pipeline(&self) -> Vec<bson::Document> {
     let org_role_name = OrganizationRole::collection_name();
+    let org_name = Organization::collection_name();
     vec![
+      // The keys of a deleted organization stop working
+      doc! {
+        "$lookup": {
+          "from": org_name,
+          "localField": "organizationId",
+          "foreignField": "_id",
+          "as": "organization"
+        }
+      },
+      doc! {
+        "$match": {
+          "organization.deletedAt": Bson::Null
+        }
+      },
       doc! {
         "$lookup": {
           "from": org_role_name,
@@ -5,7 +5,7 @@ use crate::db::query::{cursor_doc_to_vec, cursor_to_vec};
 use crate::db::user::User;
 use crate::db::{CommonCollection, ToCollectionName};
 use crate::CommonResult;
-use bson::{doc, oid, Document};
+use bson::{doc, oid, Bson, Document};
 use mongodb::Collection;
 use oid::ObjectId;
 use serde::{Deserialize, Serialize};
@@ -138,6 +138,12 @@ impl CommonCollection<OrganizationMember> {
       doc! {
         "$unwind": "$organization"
       },
+      // Deleted organizations are hidden until they are restored
+      doc! {
+        "$match": {
+          "organization.deletedAt": Bson::Null
+        }
+      },
     ];
   }
 }
@@ -1,9 +1,11 @@
+use crate::crypto::hash::hash_token;
 use crate::db::{CommonCollection, ToCollectionName};
 use crate::CommonResult;
-use bson::{doc, Bson};
+use bson::{doc, Bson, DateTime, Document};
+use futures::TryStreamExt;
 use mongodb::bson::oid::ObjectId;
 use mongodb::results::UpdateResult;
-use mongodb::{Collection, Database};
+use mongodb::{ClientSession, Collection, Database};
 use serde::{Deserialize, Serialize};
 
 const USER_COLLECTION_NAME: &str = "users";
@@ -33,6 +35,21 @@ pub struct User {
 
   #[serde(rename = "phone")]
   pub phone: Phone,
+
+  #[serde(rename = "oauthProviders", default)]
+  pub oauth_providers: Vec<UserOAuthProvider>,
+
+  #[serde(rename = "webauthnCredentials", default)]
+  pub webauthn_credentials: Vec<WebauthnCredential>,
+
+  /// Hashes of the single-use recovery codes of a user whose second factor
+  /// is only passkeys, the ones of the TOTP are kept in `two_factor`
+  #[serde(rename = "webauthnRecoveryCodes", default)]
+  pub webauthn_recovery_codes: Vec<String>,
+
+  /// Language tag of the emails sent to the user, e.g. `fr-FR`
+  #[serde(rename = "locale", default)]
+  pub locale: Option<String>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -45,6 +62,9 @@ pub struct Email {
 
   #[serde(rename = "code")]
   pub code: Option<String>,
+
+  #[serde(rename = "codeExpiresAt", default)]
+  pub code_expires_at: Option<DateTime>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -57,6 +77,9 @@ pub struct Phone {
 
   #[serde(rename = "code")]
   pub code: Option<String>,
+
+  #[serde(rename = "codeExpiresAt", default)]
+  pub code_expires_at: Option<DateTime>,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -69,6 +92,71 @@ pub struct Password {
 
   #[serde(rename = "tokenReset")]
   pub token_reset: Option<String>,
+
+  /// The hash is of a random password nobody knows, e.g. for the users
+  /// who signed up with an OAuth provider
+  #[serde(rename = "generated", default)]
+  pub generated: bool,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OAuthProvider {
+  #[serde(rename = "GITHUB")]
+  Github,
+  #[serde(rename = "GITLAB")]
+  GitLab,
+  #[serde(rename = "GOOGLE")]
+  Google,
+}
+
+impl std::fmt::Display for OAuthProvider {
+  fn fmt(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+  ) -> std::fmt::Result {
+    match self {
+      OAuthProvider::Github => write!(f, "GITHUB"),
+      OAuthProvider::GitLab => write!(f, "GITLAB"),
+      OAuthProvider::Google => write!(f, "GOOGLE"),
+    }
+  }
+}
+
+impl From<OAuthProvider> for Bson {
+  fn from(value: OAuthProvider) -> Self {
+    Bson::String(value.to_string())
+  }
+}
+
+/// An account of the user on an OAuth provider, usable to login.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct UserOAuthProvider {
+  #[serde(rename = "provider")]
+  pub provider: OAuthProvider,
+
+  #[serde(rename = "providerUserId")]
+  pub provider_user_id: String,
+
+  #[serde(rename = "email")]
+  pub email: String,
+
+  #[serde(rename = "linkedAt")]
+  pub linked_at: DateTime,
+}
+
+impl UserOAuthProvider {
+  pub fn new(
+    provider: OAuthProvider,
+    provider_user_id: String,
+    email: String,
+  ) -> Self {
+    Self {
+      provider,
+      provider_user_id,
+      email,
+      linked_at: DateTime::now(),
+    }
+  }
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug)]
@@ -76,13 +164,64 @@ pub struct TwoFactor {
   #[serde(rename = "secretBase32")]
   pub secret_base32: String,
 
-  #[serde(rename = "recoveryCode")]
-  pub recovery_code: String,
+  /// Hashes of the single-use recovery codes left
+  #[serde(rename = "recoveryCodes", default)]
+  pub recovery_codes: Vec<String>,
 
   #[serde(rename = "setup")]
   pub setup: Option<bson::DateTime>,
 }
 
+impl TwoFactor {
+  /// Hashes a recovery code as typed by the user, the separators and the
+  /// case are ignored.
+  pub fn hash_recovery_code(code: &str) -> String {
+    let code = code
+      .chars()
+      .filter(|c| c.is_ascii_alphanumeric())
+      .collect::<String>()
+      .to_uppercase();
+    hash_token(&code)
+  }
+}
+
+/// A WebAuthn authenticator (passkey, security key) of the user, usable
+/// as second factor.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct WebauthnCredential {
+  #[serde(rename = "id")]
+  pub id: ObjectId,
+
+  #[serde(rename = "name")]
+  pub name: String,
+
+  /// The passkey as serialized by the API, it holds the public key and
+  /// the signature counter
+  #[serde(rename = "passkey")]
+  pub passkey: String,
+
+  #[serde(rename = "createdAt")]
+  pub created_at: DateTime,
+
+  #[serde(rename = "lastUsedAt")]
+  pub last_used_at: Option<DateTime>,
+}
+
+impl WebauthnCredential {
+  pub fn new(
+    name: String,
+    passkey: String,
+  ) -> Self {
+    Self {
+      id: ObjectId::new(),
+      name,
+      passkey,
+      created_at: DateTime::now(),
+      last_used_at: None,
+    }
+  }
+}
+
 impl TwoFactor {
   pub fn is_enabled(&self) -> bool {
     return match self.setup {
@@ -109,20 +248,65 @@ impl User {
         password,
         last_changed: None,
         token_reset: None,
+        generated: false,
       },
       two_factor: None,
       email: Email {
         email,
         verified: false,
         code: None,
+        code_expires_at: None,
       },
       phone: Phone {
         phone,
         verified: false,
         code: None,
+        code_expires_at: None,
       },
+      oauth_providers: Vec::new(),
+      webauthn_credentials: Vec::new(),
+      webauthn_recovery_codes: Vec::new(),
+      locale: None,
     }
   }
+
+  /// Whether a second factor must be verified at login, a TOTP which is
+  /// only setup isn't required yet.
+  pub fn requires_two_factor(&self) -> bool {
+    let totp_enabled = match &self.two_factor {
+      Some(two_factor) => two_factor.is_enabled(),
+      None => false,
+    };
+    totp_enabled || !self.webauthn_credentials.is_empty()
+  }
+
+  /// A user signing up with an OAuth provider, which already verified
+  /// the email. The password hash must be of a generated password.
+  pub fn new_from_oauth(
+    firstname: String,
+    lastname: String,
+    password: String,
+    oauth_provider: UserOAuthProvider,
+  ) -> Self {
+    let mut user = Self::new(
+      firstname,
+      lastname,
+      password,
+      oauth_provider.email.clone(),
+      String::new(),
+    );
+    user.password.generated = true;
+    user.email.verified = true;
+    user.oauth_providers.push(oauth_provider);
+    user
+  }
+
+  pub fn get_oauth_provider(
+    &self,
+    provider: OAuthProvider,
+  ) -> Option<&UserOAuthProvider> {
+    self.oauth_providers.iter().find(|p| p.provider == provider)
+  }
 }
 
 impl ToCollectionName for User {
@@ -158,13 +342,37 @@ impl CommonCollection<User> {
     let update = doc! {
       "$set": {
         "email.verified": true,
-        "email.code": Bson::Null
+        "email.code": Bson::Null,
+        "email.codeExpiresAt": Bson::Null
       }
     };
     let result = self.collection.update_one(filter, update, None).await?;
     return Ok(result);
   }
 
+  pub async fn email_update_code_with_session(
+    &self,
+    id: &ObjectId,
+    code: &String,
+    code_expires_at: DateTime,
+    session: &mut ClientSession,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id
+    };
+    let update = doc! {
+      "$set": {
+        "email.code": code,
+        "email.codeExpiresAt": code_expires_at
+      }
+    };
+    let result = self
+      .collection
+      .update_one_with_session(filter, update, None, session)
+      .await?;
+    return Ok(result);
+  }
+
   pub async fn password_update_hash(
     &self,
     id: &ObjectId,
@@ -175,31 +383,58 @@ impl CommonCollection<User> {
     };
     let update = doc! {
       "$set": {
-        "password": hash
+        "password.password": hash,
+        "password.lastChanged": DateTime::now(),
+        "password.generated": false
       }
     };
     let result = self.collection.update_one(filter, update, None).await?;
     return Ok(result);
   }
 
-  pub async fn password_update_forgot_token(
+  pub async fn password_update_forgot_token_with_session(
     &self,
     id: &ObjectId,
-    token: Option<&str>,
+    token: &str,
+    session: &mut ClientSession,
   ) -> CommonResult<UpdateResult> {
-    let new_token = match token {
-      None => Bson::Null,
-      Some(token) => Bson::String(token.to_string()),
+    let filter = doc! {
+      "_id": id
+    };
+    let update = doc! {
+      "$set": {
+        "password.tokenReset": token
+      }
     };
+    let result = self
+      .collection
+      .update_one_with_session(filter, update, None, session)
+      .await?;
+    return Ok(result);
+  }
+
+  /// Replaces the password and consumes the forgot password token.
+  pub async fn password_reset_with_session(
+    &self,
+    id: &ObjectId,
+    hash: &str,
+    session: &mut ClientSession,
+  ) -> CommonResult<UpdateResult> {
     let filter = doc! {
       "_id": id
     };
     let update = doc! {
       "$set": {
-        "password.tokenReset": new_token
+        "password.password": hash,
+        "password.lastChanged": DateTime::now(),
+        "password.generated": false,
+        "password.tokenReset": Bson::Null
       }
     };
-    let result = self.collection.update_one(filter, update, None).await?;
+    let result = self
+      .collection
+      .update_one_with_session(filter, update, None, session)
+      .await?;
     return Ok(result);
   }
 
@@ -214,17 +449,46 @@ impl CommonCollection<User> {
     return Ok(result);
   }
 
-  pub async fn change_phone(
+  /// Changes the phone, which stays unverified until the code sent to it
+  /// is confirmed.
+  pub async fn change_phone_with_session(
     &self,
     id: &ObjectId,
     new_phone: &String,
+    code: &String,
+    code_expires_at: DateTime,
+    session: &mut ClientSession,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id
+    };
+    let update = doc! {
+      "$set": {
+        "phone.phone": new_phone,
+        "phone.verified": false,
+        "phone.code": code,
+        "phone.codeExpiresAt": code_expires_at
+      }
+    };
+    let result = self
+      .collection
+      .update_one_with_session(filter, update, None, session)
+      .await?;
+    return Ok(result);
+  }
+
+  pub async fn phone_confirm(
+    &self,
+    id: &ObjectId,
   ) -> CommonResult<UpdateResult> {
     let filter = doc! {
       "_id": id
     };
     let update = doc! {
       "$set": {
-        "phone.phone": new_phone
+        "phone.verified": true,
+        "phone.code": Bson::Null,
+        "phone.codeExpiresAt": Bson::Null
       }
     };
     let result = self.collection.update_one(filter, update, None).await?;
@@ -268,4 +532,250 @@ impl CommonCollection<User> {
     let result = self.collection.update_one(filter, update, None).await?;
     return Ok(result);
   }
+
+  pub async fn two_factor_recovery_codes_update(
+    &self,
+    id: &ObjectId,
+    recovery_codes: &Vec<String>,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+      "twoFactor": {
+        "$ne": null
+      }
+    };
+    let update = doc! {
+      "$set": {
+        "twoFactor.recoveryCodes": recovery_codes
+      },
+      "$unset": {
+        "twoFactor.recoveryCode": ""
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
+
+  /// Replaces the cleartext recovery code stored before the codes were
+  /// hashed by its hash. Returns the number of migrated users.
+  pub async fn migrate_recovery_code_hashes(&self) -> CommonResult<u64> {
+    let collection = self.collection.clone_with_type::<Document>();
+    let filter = doc! {
+      "twoFactor.recoveryCode": { "$type": "string" },
+    };
+    let mut cursor = collection.find(filter, None).await?;
+    let mut migrated = 0;
+    while let Some(user) = cursor.try_next().await? {
+      let code = user
+        .get_document("twoFactor")
+        .ok()
+        .and_then(|two_factor| two_factor.get_str("recoveryCode").ok());
+      let (id, code) = match (user.get("_id"), code) {
+        (Some(id), Some(code)) => (id.clone(), code.to_string()),
+        _ => continue,
+      };
+      let filter = doc! {
+        "_id": id,
+      };
+      let update = doc! {
+        "$addToSet": {
+          "twoFactor.recoveryCodes": TwoFactor::hash_recovery_code(&code),
+        },
+        "$unset": {
+          "twoFactor.recoveryCode": "",
+        },
+      };
+      collection.update_one(filter, update, None).await?;
+      migrated += 1;
+    }
+    Ok(migrated)
+  }
+
+  /// Consumes a recovery code, nothing is modified when the code was
+  /// already used or doesn't exist.
+  pub async fn two_factor_recovery_code_use(
+    &self,
+    id: &ObjectId,
+    recovery_code: &String,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+      "twoFactor.recoveryCodes": recovery_code
+    };
+    let update = doc! {
+      "$pull": {
+        "twoFactor.recoveryCodes": recovery_code
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
+
+  pub async fn find_with_oauth_provider(
+    &self,
+    provider: OAuthProvider,
+    provider_user_id: &String,
+  ) -> CommonResult<Option<User>> {
+    let filter = doc! {
+      "oauthProviders": {
+        "$elemMatch": {
+          "provider": provider,
+          "providerUserId": provider_user_id
+        }
+      }
+    };
+    let result = self.collection.find_one(filter, None).await?;
+    return Ok(result);
+  }
+
+  /// Links a provider to the user, unless one is already linked for it.
+  pub async fn oauth_provider_link(
+    &self,
+    id: &ObjectId,
+    oauth_provider: &UserOAuthProvider,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+      "oauthProviders.provider": { "$ne": oauth_provider.provider }
+    };
+    let update = doc! {
+      "$push": {
+        "oauthProviders": bson::to_bson(oauth_provider)?
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
+
+  pub async fn oauth_provider_unlink(
+    &self,
+    id: &ObjectId,
+    provider: OAuthProvider,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id
+    };
+    let update = doc! {
+      "$pull": {
+        "oauthProviders": { "provider": provider }
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
+
+  pub async fn webauthn_credential_add(
+    &self,
+    id: &ObjectId,
+    credential: &WebauthnCredential,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id
+    };
+    let update = doc! {
+      "$push": {
+        "webauthnCredentials": bson::to_bson(credential)?
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
+
+  pub async fn webauthn_recovery_codes_update(
+    &self,
+    id: &ObjectId,
+    recovery_codes: &Vec<String>,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id
+    };
+    let update = doc! {
+      "$set": {
+        "webauthnRecoveryCodes": recovery_codes
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
+
+  /// Consumes a recovery code given with the passkeys, nothing is modified
+  /// when the code was already used or doesn't exist.
+  pub async fn webauthn_recovery_code_use(
+    &self,
+    id: &ObjectId,
+    recovery_code: &String,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+      "webauthnRecoveryCodes": recovery_code
+    };
+    let update = doc! {
+      "$pull": {
+        "webauthnRecoveryCodes": recovery_code
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
+
+  pub async fn webauthn_credential_rename(
+    &self,
+    id: &ObjectId,
+    credential_id: &ObjectId,
+    name: &String,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+      "webauthnCredentials.id": credential_id
+    };
+    let update = doc! {
+      "$set": {
+        "webauthnCredentials.$.name": name
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
+
+  /// Records a use of the credential, with its new passkey when the
+  /// authentication changed it (e.g. the signature counter).
+  pub async fn webauthn_credential_used(
+    &self,
+    id: &ObjectId,
+    credential_id: &ObjectId,
+    passkey: Option<&String>,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+      "webauthnCredentials.id": credential_id
+    };
+    let mut set = doc! {
+      "webauthnCredentials.$.lastUsedAt": DateTime::now()
+    };
+    if let Some(passkey) = passkey {
+      set.insert("webauthnCredentials.$.passkey", passkey);
+    }
+    let update = doc! {
+      "$set": set
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
+
+  pub async fn webauthn_credential_delete(
+    &self,
+    id: &ObjectId,
+    credential_id: &ObjectId,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id
+    };
+    let update = doc! {
+      "$pull": {
+        "webauthnCredentials": { "id": credential_id }
+      }
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    return Ok(result);
+  }
 }
@@ -0,0 +1,177 @@
+use crate::crypto::hash::hash_token;
+use crate::db::query::cursor_to_vec;
+use crate::db::{CommonCollection, ToCollectionName};
+use crate::CommonResult;
+use bson::oid::ObjectId;
+use bson::{doc, DateTime};
+use mongodb::results::UpdateResult;
+use serde::{Deserialize, Serialize};
+
+const USER_SESSION_COLLECTION_NAME: &str = "userSessions";
+
+/// A login of a user on a device, kept alive by its refresh token.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct UserSession {
+  #[serde(rename = "_id")]
+  pub id: ObjectId,
+
+  #[serde(rename = "userId")]
+  pub user_id: ObjectId,
+
+  #[serde(rename = "refreshTokenHash")]
+  pub refresh_token_hash: String,
+
+  #[serde(rename = "userAgent")]
+  pub user_agent: Option<String>,
+
+  #[serde(rename = "ip")]
+  pub ip: Option<String>,
+
+  #[serde(rename = "createdAt")]
+  pub created_at: DateTime,
+
+  #[serde(rename = "lastUsedAt")]
+  pub last_used_at: DateTime,
+
+  #[serde(rename = "expiresAt")]
+  pub expires_at: DateTime,
+
+  #[serde(rename = "revokedAt")]
+  pub revoked_at: Option<DateTime>,
+}
+
+impl UserSession {
+  pub fn new(
+    user_id: ObjectId,
+    refresh_token: &String,
+    user_agent: Option<String>,
+    ip: Option<String>,
+    expires_at: DateTime,
+  ) -> Self {
+    let now = DateTime::now();
+    Self {
+      id: ObjectId::new(),
+      user_id,
+      refresh_token_hash: hash_token(refresh_token),
+      user_agent,
+      ip,
+      created_at: now,
+      last_used_at: now,
+      expires_at,
+      revoked_at: None,
+    }
+  }
+}
+
+impl ToCollectionName for UserSession {
+  fn collection_name() -> String {
+    String::from(USER_SESSION_COLLECTION_NAME)
+  }
+}
+
+impl CommonCollection<UserSession> {
+  pub async fn get_active_of_user(
+    &self,
+    user_id: &ObjectId,
+  ) -> CommonResult<Vec<UserSession>> {
+    let filter = doc! {
+      "userId": user_id,
+      "revokedAt": null,
+      "expiresAt": { "$gt": DateTime::now() },
+    };
+    let cursor = self.collection.find(filter, None).await?;
+    let sessions = cursor_to_vec(cursor).await?;
+    Ok(sessions)
+  }
+
+  pub async fn get_active_by_refresh_token(
+    &self,
+    refresh_token: &str,
+  ) -> CommonResult<Option<UserSession>> {
+    let filter = doc! {
+      "refreshTokenHash": hash_token(refresh_token),
+      "revokedAt": null,
+      "expiresAt": { "$gt": DateTime::now() },
+    };
+    let session = self.collection.find_one(filter, None).await?;
+    Ok(session)
+  }
+
+  /// Replaces the refresh token of a session, so that each refresh token
+  /// can only be used once, even by concurrent requests.
+  pub async fn refresh(
+    &self,
+    id: &ObjectId,
+    previous_refresh_token: &str,
+    refresh_token: &String,
+    ip: Option<String>,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+      "refreshTokenHash": hash_token(previous_refresh_token),
+      "revokedAt": null,
+    };
+    let update = doc! {
+      "$set": {
+        "refreshTokenHash": hash_token(refresh_token),
+        "ip": ip,
+        "lastUsedAt": DateTime::now(),
+      },
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  pub async fn revoke_of_user(
+    &self,
+    user_id: &ObjectId,
+    id: &ObjectId,
+  ) -> CommonResult<UpdateResult> {
+    let filter = doc! {
+      "_id": id,
+      "userId": user_id,
+      "revokedAt": null,
+    };
+    let update = doc! {
+      "$set": {
+        "revokedAt": DateTime::now(),
+      },
+    };
+    let result = self.collection.update_one(filter, update, None).await?;
+    Ok(result)
+  }
+
+  /// Revokes every active session of a user and returns their ids.
+  pub async fn revoke_all_of_user(
+    &self,
+    user_id: &ObjectId,
+  ) -> CommonResult<Vec<ObjectId>> {
+    self.revoke_all_of_user_except(user_id, None).await
+  }
+
+  /// Revokes every active session of a user but `except`, e.g. the one
+  /// making the request, and returns their ids.
+  pub async fn revoke_all_of_user_except(
+    &self,
+    user_id: &ObjectId,
+    except: Option<&ObjectId>,
+  ) -> CommonResult<Vec<ObjectId>> {
+    let sessions = self.get_active_of_user(user_id).await?;
+    let ids: Vec<ObjectId> = sessions
+      .iter()
+      .map(|s| s.id.clone())
+      .filter(|id| Some(id) != except)
+      .collect();
+    let filter = doc! {
+      "_id": { "$in": &ids },
+      "revokedAt": null,
+    };
+    let update = doc! {
+      "$set": {
+        "revokedAt": DateTime::now(),
+      },
+    };
+    self.collection.update_many(filter, update, None).await?;
+    Ok(ids)
+  }
+}
@@ -0,0 +1,63 @@
+use crate::event::ToTopicName;
+use serde::{Deserialize, Serialize};
+
+// Cluster created
+
+pub const CLUSTER_CREATED_TOPIC: &str = "cluster.created";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterCreatedEvent {
+  pub id: String,
+  pub organization_id: String,
+  pub project_id: String,
+  pub name: String,
+  pub cloud_provider: String,
+  pub credential_id: String,
+}
+
+impl ToTopicName for ClusterCreatedEvent {
+  fn topic_name() -> String {
+    CLUSTER_CREATED_TOPIC.to_string()
+  }
+}
+
+// Cluster delete requested
+
+pub const CLUSTER_DELETE_REQUESTED_TOPIC: &str = "cluster.delete_requested";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterDeleteRequestedEvent {
+  pub id: String,
+  pub organization_id: String,
+  pub project_id: String,
+  pub name: String,
+  pub cloud_provider: String,
+  pub credential_id: String,
+}
+
+impl ToTopicName for ClusterDeleteRequestedEvent {
+  fn topic_name() -> String {
+    CLUSTER_DELETE_REQUESTED_TOPIC.to_string()
+  }
+}
+
+// Cluster status changed
+
+pub const CLUSTER_STATUS_CHANGED_TOPIC: &str = "cluster.status_changed";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ClusterStatusChangedEvent {
+  pub id: String,
+  pub organization_id: String,
+  pub project_id: String,
+  pub status: String,
+}
+
+impl ToTopicName for ClusterStatusChangedEvent {
+  fn topic_name() -> String {
+    CLUSTER_STATUS_CHANGED_TOPIC.to_string()
+  }
+}
@@ -0,0 +1,57 @@
+use crate::event::ToTopicName;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const DEAD_LETTER_TOPIC: &str = "dead_letter";
+
+// Fields holding a secret, e.g. the token of a magic link or a
+// verification code, they are not copied in the dead letters
+const REDACTED_FIELDS: [&str; 5] =
+  ["token", "jwt", "code", "password", "secret"];
+const REDACTED_VALUE: &str = "[REDACTED]";
+
+/// A message a consumer group failed to deserialize or to handle,
+/// kept with its payload so it can be inspected and replayed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetterEvent {
+  pub topic: String,
+  pub group: String,
+  pub partition: i32,
+  pub offset: i64,
+  pub payload: String,
+  pub error: String,
+}
+
+impl ToTopicName for DeadLetterEvent {
+  fn topic_name() -> String {
+    DEAD_LETTER_TOPIC.to_string()
+  }
+}
+
+/// The payload of a message to keep in a dead letter, without the values
+/// of its secret fields. A payload which isn't JSON is kept as is.
+pub fn redact_payload(payload: &[u8]) -> String {
+  let mut value = match serde_json::from_slice::<Value>(payload) {
+    Ok(value) => value,
+    Err(_) => return String::from_utf8_lossy(payload).to_string(),
+  };
+  redact_value(&mut value);
+  value.to_string()
+}
+
+fn redact_value(value: &mut Value) {
+  match value {
+    Value::Object(fields) => {
+      for (name, field) in fields.iter_mut() {
+        if REDACTED_FIELDS.contains(&name.to_lowercase().as_str()) {
+          *field = Value::String(REDACTED_VALUE.to_string());
+        } else {
+          redact_value(field);
+        }
+      }
+    }
+    Value::Array(values) => values.iter_mut().for_each(redact_value),
+    _ => {}
+  }
+}
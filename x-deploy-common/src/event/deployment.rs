@@ -0,0 +1,25 @@
+use crate::event::ToTopicName;
+use serde::{Deserialize, Serialize};
+
+// Deployment rollout requested
+
+pub const DEPLOYMENT_ROLLOUT_REQUESTED_TOPIC: &str =
+  "deployment.rollout_requested";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeploymentRolloutRequestedEvent {
+  pub id: String,
+  pub organization_id: String,
+  pub project_id: String,
+  pub environment_id: String,
+  pub cluster_id: String,
+  pub image_name: String,
+  pub image_tag: String,
+}
+
+impl ToTopicName for DeploymentRolloutRequestedEvent {
+  fn topic_name() -> String {
+    DEPLOYMENT_ROLLOUT_REQUESTED_TOPIC.to_string()
+  }
+}
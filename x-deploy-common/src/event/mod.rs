@@ -1,29 +1,51 @@
+use crate::event::dead_letter::{redact_payload, DeadLetterEvent};
+use crate::event::producer::EventProducer;
 use crate::CommonResult;
-use kafka::client::RequiredAcks;
+use kafka::client::{GroupOffsetStorage, RequiredAcks};
 use kafka::consumer::{Consumer, FetchOffset};
-use kafka::producer::{Producer, Record};
-use log::debug;
+use kafka::producer::Producer;
+use log::{debug, error, info, warn};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use serde_json::from_slice;
+use std::future::Future;
 use std::time::Duration;
+use tokio::sync::watch;
 
+pub mod cluster;
+pub mod dead_letter;
+pub mod deployment;
 pub mod organization;
+pub mod producer;
 pub mod user;
 
-trait ToTopicName {
+// Delay before polling again when the brokers can't be reached
+const POLL_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+// A failing handler is tried again after 1, 2 then 4 seconds, e.g. while a
+// service it depends on is down, before its message is sent to dead letter
+const HANDLER_MAX_ATTEMPTS: u32 = 4;
+const HANDLER_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+pub trait ToTopicName {
   fn topic_name() -> String;
 }
 
 pub struct CommonEvent {
   kafka_url: Vec<String>,
+  producer: EventProducer,
 }
 
 impl CommonEvent {
   pub fn new(kafka_url: Vec<String>) -> Self {
-    Self { kafka_url }
+    Self {
+      producer: EventProducer::new(kafka_url.clone()),
+      kafka_url,
+    }
   }
 
+  /// Sends an event through the producer shared by the consumers. This
+  /// call blocks until the brokers acknowledge the message.
   pub fn send<T>(
     &self,
     data: T,
@@ -31,42 +53,179 @@ impl CommonEvent {
   where
     T: ToTopicName + DeserializeOwned + Serialize,
   {
-    let topic = T::topic_name();
-    let mut producer = Producer::from_hosts(self.kafka_url.clone())
-      // Give the brokers one second time to ack the message.
-      .with_ack_timeout(Duration::from_secs(1))
-      // Require only one broker to ack the message.
-      .with_required_acks(RequiredAcks::One)
-      // Build the producer with the above settings.
-      .create()?;
-    let json_string = serde_json::to_string(&data)?;
-    debug!("Sending to topic {} with data: {}...", topic, json_string);
-    let record = Record::from_value(&topic, json_string);
-    Ok(producer.send(&record)?)
+    self.producer.send(&data)
   }
 
-  pub fn loop_consumer<T>(
+  /// Consumes the topic of `T` as a member of the consumer `group` until
+  /// `shutdown` turns true. Each event is given to `handler` together with
+  /// a clone of `state`, and offsets are committed once a batch has been
+  /// handled. A failing handler is tried again a few times, then its
+  /// message is sent to the dead-letter topic instead of stopping the
+  /// consumer, as are the messages that can't be deserialized.
+  pub async fn consume<T, S, F, Fut>(
     &self,
-    callback: fn(T) -> CommonResult<()>,
+    group: &str,
+    state: S,
+    mut shutdown: watch::Receiver<bool>,
+    handler: F,
   ) -> CommonResult<()>
   where
     T: ToTopicName + DeserializeOwned + Serialize,
+    S: Clone,
+    F: Fn(T, S) -> Fut,
+    Fut: Future<Output = CommonResult<()>>,
   {
     let topic = T::topic_name();
-    debug!("Listening to topic: {}", topic);
-    let mut consumer = Consumer::from_hosts(self.kafka_url.clone())
-      .with_topic(topic)
-      .with_fallback_offset(FetchOffset::Earliest)
-      .create()?;
-    loop {
-      for ms in consumer.poll().unwrap().iter() {
+    info!("Consuming topic {} as group {}", topic, group);
+    let mut consumer = self.create_consumer(&topic, group)?;
+    while !*shutdown.borrow() {
+      // The kafka client is blocking, so it runs outside the async workers
+      let (returned, polled) = tokio::task::spawn_blocking(move || {
+        let polled = consumer.poll();
+        (consumer, polled)
+      })
+      .await?;
+      consumer = returned;
+      let message_sets = match polled {
+        Ok(message_sets) => message_sets,
+        Err(err) => {
+          error!("Error polling topic {}: {:?}", topic, err);
+          wait_or_shutdown(&mut shutdown, POLL_RETRY_DELAY).await;
+          continue;
+        }
+      };
+      let mut interrupted = false;
+      'message_sets: for ms in message_sets.iter() {
         for m in ms.messages() {
-          let json = from_slice::<T>(m.value).unwrap();
-          callback(json)?;
+          let result =
+            handle_message(&handler, &state, &mut shutdown, m.value).await;
+          let err = match result {
+            Ok(_) => continue,
+            Err(_) if *shutdown.borrow() => {
+              // Stopped while retrying, the message is consumed again on
+              // the next start
+              interrupted = true;
+              break 'message_sets;
+            }
+            Err(err) => err,
+          };
+          warn!(
+            "Sending message {}:{} of topic {} to dead letter: {:?}",
+            ms.partition(),
+            m.offset,
+            topic,
+            err
+          );
+          let dead_letter = DeadLetterEvent {
+            topic: topic.clone(),
+            group: group.to_string(),
+            partition: ms.partition(),
+            offset: m.offset,
+            payload: redact_payload(m.value),
+            error: format!("{:?}", err),
+          };
+          if let Err(err) = self.producer.publish(&dead_letter).await {
+            error!(
+              "Error sending message {}:{} of topic {} to dead letter, it \
+               will be consumed again: {:?}",
+              ms.partition(),
+              m.offset,
+              topic,
+              err
+            );
+            interrupted = true;
+            break 'message_sets;
+          }
         }
         consumer.consume_messageset(ms)?;
       }
-      consumer.commit_consumed()?;
+      let (returned, committed) = tokio::task::spawn_blocking(move || {
+        let committed = consumer.commit_consumed();
+        (consumer, committed)
+      })
+      .await?;
+      consumer = returned;
+      committed?;
+      if interrupted && !*shutdown.borrow() {
+        // The consumer already fetched past the messages left, a new one
+        // starts again from the committed offsets
+        wait_or_shutdown(&mut shutdown, POLL_RETRY_DELAY).await;
+        consumer = self.create_consumer(&topic, group)?;
+      }
     }
+    info!("Stopped consuming topic {} as group {}", topic, group);
+    Ok(())
   }
+
+  fn create_consumer(
+    &self,
+    topic: &String,
+    group: &str,
+  ) -> CommonResult<Consumer> {
+    let consumer = Consumer::from_hosts(self.kafka_url.clone())
+      .with_topic(topic.clone())
+      .with_group(group.to_string())
+      .with_fallback_offset(FetchOffset::Earliest)
+      .with_offset_storage(Some(GroupOffsetStorage::Kafka))
+      .create()?;
+    Ok(consumer)
+  }
+}
+
+/// Gives the message to `handler`, trying again with an exponential
+/// backoff while it fails. A message that can't be deserialized is not
+/// tried again.
+async fn handle_message<T, S, F, Fut>(
+  handler: &F,
+  state: &S,
+  shutdown: &mut watch::Receiver<bool>,
+  value: &[u8],
+) -> CommonResult<()>
+where
+  T: DeserializeOwned,
+  S: Clone,
+  F: Fn(T, S) -> Fut,
+  Fut: Future<Output = CommonResult<()>>,
+{
+  let mut delay = HANDLER_RETRY_DELAY;
+  for attempt in 1..=HANDLER_MAX_ATTEMPTS {
+    let event = from_slice::<T>(value)?;
+    let err = match handler(event, state.clone()).await {
+      Ok(_) => return Ok(()),
+      Err(err) => err,
+    };
+    if attempt == HANDLER_MAX_ATTEMPTS || *shutdown.borrow() {
+      return Err(err);
+    }
+    warn!(
+      "Error handling a message, attempt {} of {}: {:?}",
+      attempt, HANDLER_MAX_ATTEMPTS, err
+    );
+    wait_or_shutdown(shutdown, delay).await;
+    delay *= 2;
+  }
+  Ok(())
+}
+
+async fn wait_or_shutdown(
+  shutdown: &mut watch::Receiver<bool>,
+  delay: Duration,
+) {
+  tokio::select! {
+    _ = tokio::time::sleep(delay) => {}
+    _ = shutdown.changed() => {}
+  }
+}
+
+pub(crate) fn create_producer(
+  kafka_url: Vec<String>
+) -> CommonResult<Producer> {
+  let producer = Producer::from_hosts(kafka_url)
+    // Give the brokers one second time to ack the message.
+    .with_ack_timeout(Duration::from_secs(1))
+    // Require only one broker to ack the message.
+    .with_required_acks(RequiredAcks::One)
+    // Build the producer with the above settings.
+    .create()?;
+  Ok(producer)
 }
@@ -69,3 +69,23 @@ impl ToTopicName for OrganizationTransferOwnershipEvent {
     ORGANIZATION_TRANSFER_OWNERSHIP_TOPIC.to_string()
   }
 }
+
+// Organization invitation created
+
+pub const ORGANIZATION_INVITATION_CREATED_TOPIC: &str =
+  "organization.invitation_created";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OrganizationInvitationCreatedEvent {
+  pub id: String,
+  pub organization_id: String,
+  pub sender_id: String,
+  pub receiver_id: String,
+}
+
+impl ToTopicName for OrganizationInvitationCreatedEvent {
+  fn topic_name() -> String {
+    ORGANIZATION_INVITATION_CREATED_TOPIC.to_string()
+  }
+}
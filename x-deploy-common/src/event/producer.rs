@@ -0,0 +1,79 @@
+use crate::event::{create_producer, ToTopicName};
+use crate::CommonResult;
+use kafka::producer::{Producer, Record};
+use log::debug;
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use tokio::task::spawn_blocking;
+
+/// A Kafka producer shared by the whole process. The connection is opened
+/// on the first send and opened again after a failed send.
+#[derive(Clone)]
+pub struct EventProducer {
+  kafka_url: Vec<String>,
+  producer: Arc<Mutex<Option<Producer>>>,
+}
+
+impl EventProducer {
+  pub fn new(kafka_url: Vec<String>) -> Self {
+    Self {
+      kafka_url,
+      producer: Arc::new(Mutex::new(None)),
+    }
+  }
+
+  pub fn send<T>(
+    &self,
+    data: &T,
+  ) -> CommonResult<()>
+  where
+    T: ToTopicName + Serialize,
+  {
+    let json_string = serde_json::to_string(data)?;
+    self.send_raw(&T::topic_name(), &json_string)
+  }
+
+  /// Sends an event from an async task, the blocking send runs outside
+  /// of the async workers.
+  pub async fn publish<T>(
+    &self,
+    data: &T,
+  ) -> CommonResult<()>
+  where
+    T: ToTopicName + Serialize,
+  {
+    let topic = T::topic_name();
+    let payload = serde_json::to_string(data)?;
+    let producer = self.clone();
+    spawn_blocking(move || producer.send_raw(&topic, &payload)).await?
+  }
+
+  /// Sends an already serialized event. This call blocks until the
+  /// brokers acknowledge the message.
+  pub fn send_raw(
+    &self,
+    topic: &str,
+    payload: &str,
+  ) -> CommonResult<()> {
+    let mut producer = self
+      .producer
+      .lock()
+      .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if producer.is_none() {
+      *producer = Some(create_producer(self.kafka_url.clone())?);
+    }
+    let result = match producer.as_mut() {
+      Some(producer) => {
+        debug!("Sending to topic {} with data: {}...", topic, payload);
+        producer.send(&Record::from_value(topic, payload))
+      }
+      None => return Ok(()),
+    };
+    if let Err(err) = result {
+      // Drop the connection so the next send opens a fresh one
+      *producer = None;
+      return Err(err.into());
+    }
+    Ok(())
+  }
+}
@@ -28,7 +28,8 @@ pub struct UserMagicLinkEvent {
   pub firstname: String,
   pub lastname: String,
   pub email: String,
-  pub jwt: String,
+  /// Single use token, exchanged for a session by the API
+  pub token: String,
 }
 
 impl ToTopicName for UserMagicLinkEvent {
@@ -64,6 +65,7 @@ pub struct UserPasswordResetEvent {
   pub firstname: String,
   pub lastname: String,
   pub email: String,
+  pub reset_at: chrono::DateTime<chrono::Utc>,
 }
 
 impl ToTopicName for UserPasswordResetEvent {
@@ -71,3 +73,58 @@ impl ToTopicName for UserPasswordResetEvent {
     USER_PASSWORD_RESET_TOPIC.to_string()
   }
 }
+
+pub const USER_PHONE_VERIFICATION_TOPIC: &str = "user.phone_verification";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserPhoneVerificationEvent {
+  pub id: ObjectId,
+  pub firstname: String,
+  pub lastname: String,
+  pub phone: String,
+  pub code: String,
+}
+
+impl ToTopicName for UserPhoneVerificationEvent {
+  fn topic_name() -> String {
+    USER_PHONE_VERIFICATION_TOPIC.to_string()
+  }
+}
+
+pub const USER_EMAIL_VERIFICATION_TOPIC: &str = "user.email_verification";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserEmailVerificationEvent {
+  pub id: ObjectId,
+  pub firstname: String,
+  pub lastname: String,
+  pub email: String,
+  pub code: String,
+}
+
+impl ToTopicName for UserEmailVerificationEvent {
+  fn topic_name() -> String {
+    USER_EMAIL_VERIFICATION_TOPIC.to_string()
+  }
+}
+
+pub const USER_ACCOUNT_EXISTS_TOPIC: &str = "user.account_exists";
+
+/// Someone tried to register with the email of an existing account.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserAccountExistsEvent {
+  pub id: ObjectId,
+  pub firstname: String,
+  pub lastname: String,
+  pub email: String,
+  pub attempted_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ToTopicName for UserAccountExistsEvent {
+  fn topic_name() -> String {
+    USER_ACCOUNT_EXISTS_TOPIC.to_string()
+  }
+}
@@ -2,6 +2,7 @@ use rusoto_core::RusotoError;
 use rusoto_s3::{DeleteObjectError, PutObjectError};
 
 pub mod cache;
+pub mod crypto;
 pub mod data;
 pub mod db;
 pub mod event;
@@ -20,6 +21,10 @@ pub enum CommonError {
   S3AddObjectError(RusotoError<PutObjectError>),
   S3DeleteObjectError(RusotoError<DeleteObjectError>),
   FromStrError(String),
+  TaskError(tokio::task::JoinError),
+  CryptoError(String),
+  MailError(String),
+  CloudProviderError(String),
 }
 
 impl From<mongodb::error::Error> for CommonError {
@@ -69,3 +74,9 @@ impl From<RusotoError<DeleteObjectError>> for CommonError {
     Self::S3DeleteObjectError(err)
   }
 }
+
+impl From<tokio::task::JoinError> for CommonError {
+  fn from(err: tokio::task::JoinError) -> Self {
+    Self::TaskError(err)
+  }
+}
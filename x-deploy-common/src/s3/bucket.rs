@@ -53,7 +53,6 @@ where
     Ok(result)
   }
 
-  #[deprecated]
   pub async fn remove(
     &self,
     filename: &String,
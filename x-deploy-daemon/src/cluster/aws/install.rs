@@ -1,7 +1,68 @@
 use crate::cluster::aws::AwsCluster;
+use crate::error::{DaemonError, DaemonResult};
+use aws_sdk_eks::types::{ClusterStatus, VpcConfigRequest};
+use std::time::Duration;
+
+const EKS_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// EKS usually takes 10 to 15 minutes to create a cluster
+const EKS_POLL_MAX_ATTEMPTS: u32 = 60;
 
 impl AwsCluster {
-  pub fn install_cluster() {
-    
+  /// Provisions the IAM role, the VPC and the EKS cluster, then waits
+  /// until the cluster is active.
+  pub async fn install_cluster(
+    &self,
+    cluster_name: &str,
+  ) -> DaemonResult<()> {
+    let role_name = format!("{}-eks-role", cluster_name);
+    let role_arn = self.create_eks_role(&role_name).await?;
+    let vpc = self.create_vpc(cluster_name).await?;
+    log::info!(
+      "Creating EKS cluster {} in VPC {}",
+      cluster_name,
+      vpc.vpc_id
+    );
+    let vpc_config = VpcConfigRequest::builder()
+      .set_subnet_ids(Some(vpc.subnet_ids))
+      .build();
+    self
+      .eks_client
+      .create_cluster()
+      .name(cluster_name)
+      .role_arn(role_arn)
+      .resources_vpc_config(vpc_config)
+      .send()
+      .await?;
+    self.wait_cluster_active(cluster_name).await
+  }
+
+  async fn wait_cluster_active(
+    &self,
+    cluster_name: &str,
+  ) -> DaemonResult<()> {
+    for _ in 0..EKS_POLL_MAX_ATTEMPTS {
+      let output = self
+        .eks_client
+        .describe_cluster()
+        .name(cluster_name)
+        .send()
+        .await?;
+      let status = output.cluster().and_then(|c| c.status()).cloned();
+      match status {
+        Some(ClusterStatus::Active) => return Ok(()),
+        Some(ClusterStatus::Failed) => {
+          return Err(DaemonError::CloudProvider(format!(
+            "EKS cluster {} failed to create",
+            cluster_name
+          )))
+        }
+        _ => tokio::time::sleep(EKS_POLL_INTERVAL).await,
+      }
+    }
+    Err(DaemonError::CloudProvider(format!(
+      "EKS cluster {} was not active in time",
+      cluster_name
+    )))
   }
 }
@@ -1,7 +1,10 @@
 mod install;
 mod role;
+mod uninstall;
+mod vpc;
 
 use aws_config::{BehaviorVersion, Region};
+use aws_sdk_ec2::{Client as Ec2Client, Config as Ec2Config};
 use aws_sdk_eks::config::Credentials;
 use aws_sdk_eks::{Client as EksClient, Config as EksConfig};
 use aws_sdk_iam::{Client as IamClient, Config as IamConfig};
@@ -10,32 +13,47 @@ pub struct AwsCluster {
   pub secret_key: String,
   pub access_key_id: String,
   pub region: String,
+  pub endpoint_url: Option<String>,
   pub iam_client: IamClient,
   pub eks_client: EksClient,
+  pub ec2_client: Ec2Client,
 }
 
 impl AwsCluster {
+  /// Builds the AWS clients of a cluster.
+  /// `endpoint_url` overrides the AWS endpoint, e.g. to target a local mock.
   pub fn new(
     secret_key: String,
     access_key_id: String,
     region: String,
+    endpoint_url: Option<String>,
   ) -> Self {
     let iam_client = Self::new_iam_client(
       secret_key.clone(),
       access_key_id.clone(),
       region.clone(),
+      endpoint_url.clone(),
     );
     let eks_client = Self::new_eks_client(
       secret_key.clone(),
       access_key_id.clone(),
       region.clone(),
+      endpoint_url.clone(),
+    );
+    let ec2_client = Self::new_ec2_client(
+      secret_key.clone(),
+      access_key_id.clone(),
+      region.clone(),
+      endpoint_url.clone(),
     );
     Self {
       secret_key,
       access_key_id,
       region,
+      endpoint_url,
       iam_client,
       eks_client,
+      ec2_client,
     }
   }
 
@@ -43,6 +61,7 @@ impl AwsCluster {
     secret_key: String,
     access_key_id: String,
     region: String,
+    endpoint_url: Option<String>,
   ) -> IamClient {
     let aws_region = Region::new(region.clone());
     let creds =
@@ -51,6 +70,7 @@ impl AwsCluster {
       .credentials_provider(creds)
       .behavior_version(BehaviorVersion::latest())
       .region(aws_region)
+      .set_endpoint_url(endpoint_url)
       .build();
     let client = IamClient::from_conf(conf);
     client
@@ -60,6 +80,7 @@ impl AwsCluster {
     secret_key: String,
     access_key_id: String,
     region: String,
+    endpoint_url: Option<String>,
   ) -> EksClient {
     let aws_region = Region::new(region.clone());
     let creds =
@@ -68,8 +89,28 @@ impl AwsCluster {
       .credentials_provider(creds)
       .behavior_version(BehaviorVersion::latest())
       .region(aws_region)
+      .set_endpoint_url(endpoint_url)
       .build();
     let client = EksClient::from_conf(conf);
     client
   }
+
+  fn new_ec2_client(
+    secret_key: String,
+    access_key_id: String,
+    region: String,
+    endpoint_url: Option<String>,
+  ) -> Ec2Client {
+    let aws_region = Region::new(region.clone());
+    let creds =
+      Credentials::from_keys(access_key_id.clone(), secret_key.clone(), None);
+    let conf = Ec2Config::builder()
+      .credentials_provider(creds)
+      .behavior_version(BehaviorVersion::latest())
+      .region(aws_region)
+      .set_endpoint_url(endpoint_url)
+      .build();
+    let client = Ec2Client::from_conf(conf);
+    client
+  }
 }
@@ -1,9 +1,16 @@
 use crate::cluster::aws::AwsCluster;
-use aws_sdk_iam::Client;
+use crate::error::{DaemonError, DaemonResult};
+
+const EKS_CLUSTER_POLICY_ARN: &str =
+  "arn:aws:iam::aws:policy/AmazonEKSClusterPolicy";
 
 impl AwsCluster {
-  pub async fn create_eks_role(&self) {
-    let role_name = "eksServiceRole";
+  /// Creates the service role assumed by EKS and returns its ARN.
+  /// An existing role with the same name is reused.
+  pub async fn create_eks_role(
+    &self,
+    role_name: &str,
+  ) -> DaemonResult<String> {
     let iam_client = &self.iam_client;
     let create_role = iam_client
       .create_role()
@@ -24,5 +31,74 @@ impl AwsCluster {
       )
       .send()
       .await;
+    let role = match create_role {
+      Ok(output) => output.role,
+      Err(err) => {
+        let already_exists = err
+          .as_service_error()
+          .map(|e| e.is_entity_already_exists_exception())
+          .unwrap_or(false);
+        if !already_exists {
+          return Err(err.into());
+        }
+        log::info!("EKS role {} already exists, reusing it", role_name);
+        iam_client
+          .get_role()
+          .role_name(role_name)
+          .send()
+          .await?
+          .role
+      }
+    };
+    let role_arn = match role {
+      Some(role) => role.arn,
+      None => {
+        return Err(DaemonError::CloudProvider(format!(
+          "AWS returned no role for {}",
+          role_name
+        )))
+      }
+    };
+    iam_client
+      .attach_role_policy()
+      .role_name(role_name)
+      .policy_arn(EKS_CLUSTER_POLICY_ARN)
+      .send()
+      .await?;
+    Ok(role_arn)
+  }
+
+  /// Deletes the service role created by `create_eks_role`, if any.
+  pub async fn delete_eks_role(
+    &self,
+    role_name: &str,
+  ) -> DaemonResult<()> {
+    let iam_client = &self.iam_client;
+    let detach = iam_client
+      .detach_role_policy()
+      .role_name(role_name)
+      .policy_arn(EKS_CLUSTER_POLICY_ARN)
+      .send()
+      .await;
+    if let Err(err) = detach {
+      let not_found = err
+        .as_service_error()
+        .map(|e| e.is_no_such_entity_exception())
+        .unwrap_or(false);
+      if !not_found {
+        return Err(err.into());
+      }
+    }
+    let delete = iam_client.delete_role().role_name(role_name).send().await;
+    if let Err(err) = delete {
+      let not_found = err
+        .as_service_error()
+        .map(|e| e.is_no_such_entity_exception())
+        .unwrap_or(false);
+      if !not_found {
+        return Err(err.into());
+      }
+    }
+    Ok(())
   }
 }
@@ -0,0 +1,81 @@
+use crate::cluster::aws::AwsCluster;
+use crate::error::{DaemonError, DaemonResult};
+use std::time::Duration;
+
+const EKS_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// EKS usually takes a few minutes to delete a cluster
+const EKS_POLL_MAX_ATTEMPTS: u32 = 60;
+
+impl AwsCluster {
+  /// Deletes the EKS cluster, then its VPC and its IAM role once AWS
+  /// confirms the cluster is gone. The VPC is found by its tag, so it is
+  /// also deleted for a cluster whose creation failed before EKS.
+  pub async fn uninstall_cluster(
+    &self,
+    cluster_name: &str,
+  ) -> DaemonResult<()> {
+    let describe = self
+      .eks_client
+      .describe_cluster()
+      .name(cluster_name)
+      .send()
+      .await;
+    match describe {
+      Ok(_) => {
+        log::info!("Deleting EKS cluster {}", cluster_name);
+        self
+          .eks_client
+          .delete_cluster()
+          .name(cluster_name)
+          .send()
+          .await?;
+        self.wait_cluster_deleted(cluster_name).await?;
+      }
+      Err(err) => {
+        let not_found = err
+          .as_service_error()
+          .map(|e| e.is_resource_not_found_exception())
+          .unwrap_or(false);
+        if !not_found {
+          return Err(err.into());
+        }
+        log::info!("EKS cluster {} is already deleted", cluster_name);
+      }
+    }
+    self.delete_vpcs_of_cluster(cluster_name).await?;
+    let role_name = format!("{}-eks-role", cluster_name);
+    self.delete_eks_role(&role_name).await
+  }
+
+  async fn wait_cluster_deleted(
+    &self,
+    cluster_name: &str,
+  ) -> DaemonResult<()> {
+    for _ in 0..EKS_POLL_MAX_ATTEMPTS {
+      let describe = self
+        .eks_client
+        .describe_cluster()
+        .name(cluster_name)
+        .send()
+        .await;
+      match describe {
+        Ok(_) => tokio::time::sleep(EKS_POLL_INTERVAL).await,
+        Err(err) => {
+          let not_found = err
+            .as_service_error()
+            .map(|e| e.is_resource_not_found_exception())
+            .unwrap_or(false);
+          if not_found {
+            return Ok(());
+          }
+          return Err(err.into());
+        }
+      }
+    }
+    Err(DaemonError::CloudProvider(format!(
+      "EKS cluster {} was not deleted in time",
+      cluster_name
+    )))
+  }
+}
@@ -0,0 +1,281 @@
+use crate::cluster::aws::AwsCluster;
+use crate::error::{DaemonError, DaemonResult};
+use aws_sdk_ec2::types::{
+  AttributeBooleanValue, Filter, ResourceType, Tag, TagSpecification,
+};
+
+const VPC_CIDR_BLOCK: &str = "10.0.0.0/16";
+
+// EKS requires subnets in at least two availability zones
+const SUBNET_CIDR_BLOCKS: [&str; 2] = ["10.0.0.0/24", "10.0.1.0/24"];
+
+// Every resource created for a cluster is tagged with its name, so that the
+// teardown finds them even if the EKS cluster was never created
+const CLUSTER_TAG_KEY: &str = "x-deploy:cluster";
+
+pub struct AwsVpc {
+  pub vpc_id: String,
+  pub subnet_ids: Vec<String>,
+}
+
+impl AwsCluster {
+  /// Creates a public VPC with one subnet per availability zone for EKS.
+  pub async fn create_vpc(
+    &self,
+    cluster_name: &str,
+  ) -> DaemonResult<AwsVpc> {
+    let ec2_client = &self.ec2_client;
+    let zones = ec2_client.describe_availability_zones().send().await?;
+    let zone_names: Vec<String> = zones
+      .availability_zones()
+      .iter()
+      .filter_map(|zone| zone.zone_name().map(|name| name.to_string()))
+      .take(SUBNET_CIDR_BLOCKS.len())
+      .collect();
+    if zone_names.len() < SUBNET_CIDR_BLOCKS.len() {
+      return Err(DaemonError::CloudProvider(format!(
+        "Region {} doesn't have enough availability zones",
+        self.region
+      )));
+    }
+    // VPC
+    let vpc = ec2_client
+      .create_vpc()
+      .cidr_block(VPC_CIDR_BLOCK)
+      .tag_specifications(cluster_tags(ResourceType::Vpc, cluster_name))
+      .send()
+      .await?;
+    let vpc_id = required_id(vpc.vpc().and_then(|v| v.vpc_id()), "VPC")?;
+    // Internet gateway
+    let gateway = ec2_client
+      .create_internet_gateway()
+      .tag_specifications(cluster_tags(
+        ResourceType::InternetGateway,
+        cluster_name,
+      ))
+      .send()
+      .await?;
+    let gateway_id = required_id(
+      gateway
+        .internet_gateway()
+        .and_then(|g| g.internet_gateway_id()),
+      "internet gateway",
+    )?;
+    ec2_client
+      .attach_internet_gateway()
+      .internet_gateway_id(&gateway_id)
+      .vpc_id(&vpc_id)
+      .send()
+      .await?;
+    // Route table
+    let route_table = ec2_client
+      .create_route_table()
+      .vpc_id(&vpc_id)
+      .tag_specifications(cluster_tags(ResourceType::RouteTable, cluster_name))
+      .send()
+      .await?;
+    let route_table_id = required_id(
+      route_table.route_table().and_then(|r| r.route_table_id()),
+      "route table",
+    )?;
+    ec2_client
+      .create_route()
+      .route_table_id(&route_table_id)
+      .destination_cidr_block("0.0.0.0/0")
+      .gateway_id(&gateway_id)
+      .send()
+      .await?;
+    // Subnets
+    let mut subnet_ids = Vec::new();
+    for (cidr_block, zone_name) in SUBNET_CIDR_BLOCKS.iter().zip(zone_names) {
+      let subnet = ec2_client
+        .create_subnet()
+        .vpc_id(&vpc_id)
+        .cidr_block(*cidr_block)
+        .availability_zone(zone_name)
+        .tag_specifications(cluster_tags(ResourceType::Subnet, cluster_name))
+        .send()
+        .await?;
+      let subnet_id =
+        required_id(subnet.subnet().and_then(|s| s.subnet_id()), "subnet")?;
+      ec2_client
+        .associate_route_table()
+        .route_table_id(&route_table_id)
+        .subnet_id(&subnet_id)
+        .send()
+        .await?;
+      ec2_client
+        .modify_subnet_attribute()
+        .subnet_id(&subnet_id)
+        .map_public_ip_on_launch(
+          AttributeBooleanValue::builder().value(true).build(),
+        )
+        .send()
+        .await?;
+      subnet_ids.push(subnet_id);
+    }
+    Ok(AwsVpc { vpc_id, subnet_ids })
+  }
+
+  /// Deletes the VPCs created by `create_vpc` for a cluster, and the
+  /// internet gateways left unattached by a failed creation.
+  pub async fn delete_vpcs_of_cluster(
+    &self,
+    cluster_name: &str,
+  ) -> DaemonResult<()> {
+    let ec2_client = &self.ec2_client;
+    let vpcs = ec2_client
+      .describe_vpcs()
+      .filters(cluster_filter(cluster_name))
+      .send()
+      .await?;
+    for vpc_id in vpcs.vpcs().iter().filter_map(|v| v.vpc_id()) {
+      log::info!("Deleting VPC {} of cluster {}", vpc_id, cluster_name);
+      self.delete_vpc(vpc_id).await?;
+    }
+    let gateways = ec2_client
+      .describe_internet_gateways()
+      .filters(cluster_filter(cluster_name))
+      .send()
+      .await?;
+    for gateway in gateways.internet_gateways() {
+      if !gateway.attachments().is_empty() {
+        continue;
+      }
+      if let Some(gateway_id) = gateway.internet_gateway_id() {
+        ec2_client
+          .delete_internet_gateway()
+          .internet_gateway_id(gateway_id)
+          .send()
+          .await?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Deletes a VPC created by `create_vpc` with everything it holds.
+  pub async fn delete_vpc(
+    &self,
+    vpc_id: &str,
+  ) -> DaemonResult<()> {
+    let ec2_client = &self.ec2_client;
+    let vpc_filter = Filter::builder().name("vpc-id").values(vpc_id).build();
+    // Subnets, which also removes their route table associations
+    let subnets = ec2_client
+      .describe_subnets()
+      .filters(vpc_filter.clone())
+      .send()
+      .await?;
+    for subnet_id in subnets.subnets().iter().filter_map(|s| s.subnet_id()) {
+      ec2_client
+        .delete_subnet()
+        .subnet_id(subnet_id)
+        .send()
+        .await?;
+    }
+    // Route tables, except the main one deleted with the VPC
+    let route_tables = ec2_client
+      .describe_route_tables()
+      .filters(vpc_filter.clone())
+      .send()
+      .await?;
+    for route_table in route_tables.route_tables() {
+      let is_main = route_table
+        .associations()
+        .iter()
+        .any(|a| a.main().unwrap_or(false));
+      if is_main {
+        continue;
+      }
+      if let Some(route_table_id) = route_table.route_table_id() {
+        ec2_client
+          .delete_route_table()
+          .route_table_id(route_table_id)
+          .send()
+          .await?;
+      }
+    }
+    // Internet gateways
+    let gateway_filter = Filter::builder()
+      .name("attachment.vpc-id")
+      .values(vpc_id)
+      .build();
+    let gateways = ec2_client
+      .describe_internet_gateways()
+      .filters(gateway_filter)
+      .send()
+      .await?;
+    for gateway_id in gateways
+      .internet_gateways()
+      .iter()
+      .filter_map(|g| g.internet_gateway_id())
+    {
+      ec2_client
+        .detach_internet_gateway()
+        .internet_gateway_id(gateway_id)
+        .vpc_id(vpc_id)
+        .send()
+        .await?;
+      ec2_client
+        .delete_internet_gateway()
+        .internet_gateway_id(gateway_id)
+        .send()
+        .await?;
+    }
+    // Security groups left by EKS, except the default one
+    let security_groups = ec2_client
+      .describe_security_groups()
+      .filters(vpc_filter)
+      .send()
+      .await?;
+    for security_group in security_groups.security_groups() {
+      if security_group.group_name() == Some("default") {
+        continue;
+      }
+      if let Some(group_id) = security_group.group_id() {
+        ec2_client
+          .delete_security_group()
+          .group_id(group_id)
+          .send()
+          .await?;
+      }
+    }
+    ec2_client.delete_vpc().vpc_id(vpc_id).send().await?;
+    Ok(())
+  }
+}
+
+fn cluster_tags(
+  resource_type: ResourceType,
+  cluster_name: &str,
+) -> TagSpecification {
+  TagSpecification::builder()
+    .resource_type(resource_type)
+    .tags(
+      Tag::builder()
+        .key(CLUSTER_TAG_KEY)
+        .value(cluster_name)
+        .build(),
+    )
+    .build()
+}
+
+fn cluster_filter(cluster_name: &str) -> Filter {
+  Filter::builder()
+    .name(format!("tag:{}", CLUSTER_TAG_KEY))
+    .values(cluster_name)
+    .build()
+}
+
+fn required_id(
+  id: Option<&str>,
+  resource: &str,
+) -> DaemonResult<String> {
+  return match id {
+    Some(id) => Ok(id.to_string()),
+    None => Err(DaemonError::CloudProvider(format!(
+      "AWS returned no id for the created {}",
+      resource
+    ))),
+  };
+}
@@ -1,2 +1,187 @@
+use crate::cluster::aws::AwsCluster;
+use crate::cluster::ovh::OvhCluster;
+use crate::error::{DaemonError, DaemonResult};
+use crate::{CONFIG, KEYRING};
+use bson::oid::ObjectId;
+use mongodb::Database;
+use std::str::FromStr;
+use x_deploy_common::data::cloud_provider::CloudProviderType;
+use x_deploy_common::db::event_outbox::EventOutbox;
+use x_deploy_common::db::organization_credential_aws::OrganizationCredentialAws;
+use x_deploy_common::db::organization_credential_ovh::OrganizationCredentialOvh;
+use x_deploy_common::db::organization_project_cluster::{
+  ClusterStatus, OrganizationProjectCluster,
+};
+use x_deploy_common::db::{
+  commit_transaction, start_transaction, CommonCollection,
+};
+use x_deploy_common::event::cluster::ClusterStatusChangedEvent;
+
 pub mod aws;
 pub mod ovh;
+
+/// Provisions the cloud resources of a cluster and stores the outcome
+/// in its status: `Running` on success, `Error` otherwise. A cluster
+/// already running or being deleted is left as is, so an event consumed
+/// again doesn't provision it twice.
+pub(crate) async fn provision_cluster(
+  db: &Database,
+  cluster_id: &ObjectId,
+) -> DaemonResult<()> {
+  let cluster_coll = CommonCollection::<OrganizationProjectCluster>::new(db);
+  let cluster = match cluster_coll.get_by_id(cluster_id).await? {
+    Some(cluster) => cluster,
+    None => {
+      log::warn!("Cluster {} to provision no longer exists", cluster_id);
+      return Ok(());
+    }
+  };
+  match cluster.status {
+    ClusterStatus::Creating | ClusterStatus::Error => {}
+    ClusterStatus::Running | ClusterStatus::Deleting => {
+      log::info!("Cluster {} is already {}", cluster_id, cluster.status);
+      return Ok(());
+    }
+  }
+  update_status(db, &cluster, ClusterStatus::Creating).await?;
+  let cluster_name = get_cluster_name(&cluster);
+  let result = match CloudProviderType::from_str(&cluster.cloud_provider) {
+    Ok(CloudProviderType::Aws) => match get_aws_cluster(db, &cluster).await {
+      Ok(aws_cluster) => aws_cluster.install_cluster(&cluster_name).await,
+      Err(err) => Err(err),
+    },
+    Ok(CloudProviderType::Ovh) => match get_ovh_cluster(db, &cluster).await {
+      Ok(ovh_cluster) => ovh_cluster.install_cluster(&cluster_name).await,
+      Err(err) => Err(err),
+    },
+    Err(err) => Err(DaemonError::from(err)),
+  };
+  let status = match result {
+    Ok(_) => ClusterStatus::Running,
+    Err(_) => ClusterStatus::Error,
+  };
+  update_status(db, &cluster, status).await?;
+  result
+}
+
+/// Deletes the cloud resources of a cluster, then its document once the
+/// cloud provider confirmed they are gone. On failure the cluster is kept
+/// with the `Error` status so the deletion can be tried again. A cluster
+/// already deleted is left as is.
+pub(crate) async fn teardown_cluster(
+  db: &Database,
+  cluster_id: &ObjectId,
+) -> DaemonResult<()> {
+  let cluster_coll = CommonCollection::<OrganizationProjectCluster>::new(db);
+  let cluster = match cluster_coll.get_by_id(cluster_id).await? {
+    Some(cluster) => cluster,
+    None => {
+      log::info!("Cluster {} is already deleted", cluster_id);
+      return Ok(());
+    }
+  };
+  if !matches!(cluster.status, ClusterStatus::Deleting) {
+    update_status(db, &cluster, ClusterStatus::Deleting).await?;
+  }
+  let cluster_name = get_cluster_name(&cluster);
+  let result = match CloudProviderType::from_str(&cluster.cloud_provider) {
+    Ok(CloudProviderType::Aws) => match get_aws_cluster(db, &cluster).await {
+      Ok(aws_cluster) => aws_cluster.uninstall_cluster(&cluster_name).await,
+      Err(err) => Err(err),
+    },
+    Ok(CloudProviderType::Ovh) => match get_ovh_cluster(db, &cluster).await {
+      Ok(ovh_cluster) => ovh_cluster.uninstall_cluster(&cluster_name).await,
+      Err(err) => Err(err),
+    },
+    Err(err) => Err(DaemonError::from(err)),
+  };
+  if let Err(err) = result {
+    update_status(db, &cluster, ClusterStatus::Error).await?;
+    return Err(err);
+  }
+  cluster_coll.delete_by_id(&cluster.id).await?;
+  Ok(())
+}
+
+/// The name of the cluster at the cloud provider, unique per cluster.
+fn get_cluster_name(cluster: &OrganizationProjectCluster) -> String {
+  format!("xd-{}", cluster.id)
+}
+
+/// Stores the new status of a cluster and notifies the other components
+/// through the outbox, in one transaction.
+async fn update_status(
+  db: &Database,
+  cluster: &OrganizationProjectCluster,
+  status: ClusterStatus,
+) -> DaemonResult<()> {
+  let mut session = start_transaction(db.client()).await?;
+  CommonCollection::<OrganizationProjectCluster>::new(db)
+    .update_status_with_session(&cluster.id, status.clone(), &mut session)
+    .await?;
+  CommonCollection::<EventOutbox>::new(db)
+    .add_with_session(
+      &ClusterStatusChangedEvent {
+        id: cluster.id.to_string(),
+        organization_id: cluster.organization_id.to_string(),
+        project_id: cluster.project_id.to_string(),
+        status: status.to_string(),
+      },
+      &mut session,
+    )
+    .await?;
+  commit_transaction(&mut session).await?;
+  Ok(())
+}
+
+async fn get_aws_cluster(
+  db: &Database,
+  cluster: &OrganizationProjectCluster,
+) -> DaemonResult<AwsCluster> {
+  let cred_coll = CommonCollection::<OrganizationCredentialAws>::new(db);
+  let credential = match cred_coll
+    .get_by_id_and_org_id(&cluster.credential_id, &cluster.organization_id)
+    .await?
+  {
+    Some(credential) => credential,
+    None => {
+      return Err(DaemonError::NotFound(format!(
+        "AWS credential {} not found",
+        cluster.credential_id
+      )))
+    }
+  };
+  Ok(AwsCluster::new(
+    KEYRING.decrypt(&credential.secret_key)?,
+    credential.access_key,
+    CONFIG.aws_region.clone(),
+    CONFIG.aws_endpoint_url.clone(),
+  ))
+}
+
+async fn get_ovh_cluster(
+  db: &Database,
+  cluster: &OrganizationProjectCluster,
+) -> DaemonResult<OvhCluster> {
+  let cred_coll = CommonCollection::<OrganizationCredentialOvh>::new(db);
+  let credential = match cred_coll
+    .get_by_id_and_org_id(&cluster.credential_id, &cluster.organization_id)
+    .await?
+  {
+    Some(credential) => credential,
+    None => {
+      return Err(DaemonError::NotFound(format!(
+        "OVH credential {} not found",
+        cluster.credential_id
+      )))
+    }
+  };
+  Ok(OvhCluster::new(
+    credential.application_key,
+    KEYRING.decrypt(&credential.application_secret)?,
+    KEYRING.decrypt(&credential.consumer_key)?,
+    credential.project_id,
+    CONFIG.ovh_region.clone(),
+    CONFIG.ovh_base_url.clone(),
+  ))
+}
@@ -0,0 +1,93 @@
+use crate::cluster::ovh::OvhCluster;
+use crate::error::{DaemonError, DaemonResult};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sha1::{Digest, Sha1};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl OvhCluster {
+  /// Sends a request signed with the OVH credential of the cluster. The
+  /// `ovh-client` crate of x-deploy-api follows the `master` branch of its
+  /// git repository, so the daemon keeps its own client: the requests
+  /// which create and delete clusters don't change with an unpinned
+  /// upstream. A 404 is returned as `NotFound`, which the teardown relies
+  /// on.
+  pub(super) async fn call<T>(
+    &self,
+    method: Method,
+    path: &str,
+    body: Option<Value>,
+  ) -> DaemonResult<T>
+  where
+    T: DeserializeOwned,
+  {
+    let url = format!("{}{}", self.base_url, path);
+    let body = match body {
+      Some(body) => serde_json::to_string(&body)
+        .map_err(|err| DaemonError::CloudProvider(err.to_string()))?,
+      None => String::new(),
+    };
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map_err(|err| DaemonError::CloudProvider(err.to_string()))?
+      .as_secs()
+      .to_string();
+    let signature = self.sign(&method, &url, &body, &timestamp);
+    let response = self
+      .http_client
+      .request(method.clone(), &url)
+      .header("Content-Type", "application/json")
+      .header("X-Ovh-Application", &self.application_key)
+      .header("X-Ovh-Consumer", &self.consumer_key)
+      .header("X-Ovh-Timestamp", &timestamp)
+      .header("X-Ovh-Signature", signature)
+      .body(body)
+      .send()
+      .await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if status == StatusCode::NOT_FOUND {
+      return Err(DaemonError::NotFound(format!(
+        "OVH API {} {} returned {}",
+        method, path, status
+      )));
+    }
+    if !status.is_success() {
+      return Err(DaemonError::CloudProvider(format!(
+        "OVH API {} {} returned {}: {}",
+        method, path, status, text
+      )));
+    }
+    // Some calls such as DELETE answer with an empty body
+    let text = if text.is_empty() {
+      "null"
+    } else {
+      text.as_str()
+    };
+    serde_json::from_str::<T>(text)
+      .map_err(|err| DaemonError::CloudProvider(err.to_string()))
+  }
+
+  /// Computes the `X-Ovh-Signature` header of a request.
+  fn sign(
+    &self,
+    method: &Method,
+    url: &str,
+    body: &str,
+    timestamp: &str,
+  ) -> String {
+    let to_sign = format!(
+      "{}+{}+{}+{}+{}+{}",
+      self.application_secret,
+      self.consumer_key,
+      method.as_str(),
+      url,
+      body,
+      timestamp
+    );
+    let hash = Sha1::digest(to_sign.as_bytes());
+    let hex: String = hash.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("$1${}", hex)
+  }
+}
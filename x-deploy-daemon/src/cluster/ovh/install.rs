@@ -0,0 +1,108 @@
+use crate::cluster::ovh::{
+  OvhCluster, OVH_POLL_INTERVAL, OVH_POLL_MAX_ATTEMPTS,
+};
+use crate::error::{DaemonError, DaemonResult};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::json;
+
+const OVH_NODE_POOL_NAME: &str = "default";
+const OVH_NODE_POOL_FLAVOR: &str = "b2-7";
+const OVH_NODE_POOL_SIZE: u32 = 3;
+
+#[derive(Deserialize, Debug)]
+struct OvhResource {
+  id: String,
+  status: String,
+}
+
+impl OvhCluster {
+  /// Creates the Managed Kubernetes cluster and its node pool in the
+  /// public cloud project of the credential, then waits until both are
+  /// ready.
+  pub async fn install_cluster(
+    &self,
+    cluster_name: &str,
+  ) -> DaemonResult<()> {
+    let project_id = self.get_project_id().await?;
+    log::info!(
+      "Creating OVH cluster {} in project {}",
+      cluster_name,
+      project_id
+    );
+    let kube_path = format!("/cloud/project/{}/kube", project_id);
+    let kube: OvhResource = self
+      .call(
+        Method::POST,
+        &kube_path,
+        Some(json!({
+          "name": cluster_name,
+          "region": self.region,
+        })),
+      )
+      .await?;
+    let kube_path = format!("{}/{}", kube_path, kube.id);
+    self.wait_ready(&kube_path).await?;
+    let node_pool_path = format!("{}/nodepool", kube_path);
+    let node_pool: OvhResource = self
+      .call(
+        Method::POST,
+        &node_pool_path,
+        Some(json!({
+          "name": OVH_NODE_POOL_NAME,
+          "flavorName": OVH_NODE_POOL_FLAVOR,
+          "desiredNodes": OVH_NODE_POOL_SIZE,
+          "minNodes": OVH_NODE_POOL_SIZE,
+          "maxNodes": OVH_NODE_POOL_SIZE,
+          "autoscale": false,
+        })),
+      )
+      .await?;
+    let node_pool_path = format!("{}/{}", node_pool_path, node_pool.id);
+    self.wait_ready(&node_pool_path).await
+  }
+
+  /// The project of the credential. The credentials added before it was
+  /// asked must give access to exactly one public cloud project.
+  pub(super) async fn get_project_id(&self) -> DaemonResult<String> {
+    if let Some(project_id) = &self.project_id {
+      return Ok(project_id.clone());
+    }
+    let projects: Vec<String> =
+      self.call(Method::GET, "/cloud/project", None).await?;
+    return match projects.as_slice() {
+      [project_id] => Ok(project_id.clone()),
+      [] => Err(DaemonError::CloudProvider(
+        "The OVH credential has no public cloud project".to_string(),
+      )),
+      _ => Err(DaemonError::CloudProvider(
+        "The OVH credential has more than one public cloud project, add it \
+         again with the project to use"
+          .to_string(),
+      )),
+    };
+  }
+
+  async fn wait_ready(
+    &self,
+    path: &str,
+  ) -> DaemonResult<()> {
+    for _ in 0..OVH_POLL_MAX_ATTEMPTS {
+      let resource: OvhResource = self.call(Method::GET, path, None).await?;
+      match resource.status.as_str() {
+        "READY" => return Ok(()),
+        "ERROR" => {
+          return Err(DaemonError::CloudProvider(format!(
+            "OVH resource {} failed to create",
+            path
+          )))
+        }
+        _ => tokio::time::sleep(OVH_POLL_INTERVAL).await,
+      }
+    }
+    Err(DaemonError::CloudProvider(format!(
+      "OVH resource {} was not ready in time",
+      path
+    )))
+  }
+}
@@ -0,0 +1,50 @@
+mod api;
+mod install;
+mod uninstall;
+
+use reqwest::Client;
+use std::time::Duration;
+
+const OVH_DEFAULT_BASE_URL: &str = "https://eu.api.ovh.com/1.0";
+
+const OVH_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+// OVH usually takes a few minutes to create or delete a cluster
+const OVH_POLL_MAX_ATTEMPTS: u32 = 60;
+
+pub struct OvhCluster {
+  pub application_key: String,
+  pub application_secret: String,
+  pub consumer_key: String,
+  pub project_id: Option<String>,
+  pub region: String,
+  pub base_url: String,
+  http_client: Client,
+}
+
+impl OvhCluster {
+  /// Builds the OVH API client of a cluster.
+  /// `base_url` overrides the OVH API endpoint, e.g. to target a local stub.
+  pub fn new(
+    application_key: String,
+    application_secret: String,
+    consumer_key: String,
+    project_id: Option<String>,
+    region: String,
+    base_url: Option<String>,
+  ) -> Self {
+    let base_url = base_url
+      .unwrap_or(OVH_DEFAULT_BASE_URL.to_string())
+      .trim_end_matches('/')
+      .to_string();
+    Self {
+      application_key,
+      application_secret,
+      consumer_key,
+      project_id,
+      region,
+      base_url,
+      http_client: Client::new(),
+    }
+  }
+}
@@ -0,0 +1,56 @@
+use crate::cluster::ovh::{
+  OvhCluster, OVH_POLL_INTERVAL, OVH_POLL_MAX_ATTEMPTS,
+};
+use crate::error::{DaemonError, DaemonResult};
+use reqwest::Method;
+use serde::Deserialize;
+use serde_json::Value;
+
+#[derive(Deserialize, Debug)]
+struct OvhKube {
+  id: String,
+  name: String,
+}
+
+impl OvhCluster {
+  /// Deletes the Managed Kubernetes cluster, with its node pools, and
+  /// waits until OVH no longer knows it.
+  pub async fn uninstall_cluster(
+    &self,
+    cluster_name: &str,
+  ) -> DaemonResult<()> {
+    let project_id = self.get_project_id().await?;
+    let kube_path = format!("/cloud/project/{}/kube", project_id);
+    let kube_ids: Vec<String> =
+      self.call(Method::GET, &kube_path, None).await?;
+    for kube_id in kube_ids {
+      let path = format!("{}/{}", kube_path, kube_id);
+      let kube: OvhKube = self.call(Method::GET, &path, None).await?;
+      if kube.name != cluster_name {
+        continue;
+      }
+      log::info!("Deleting OVH cluster {} ({})", cluster_name, kube.id);
+      let _: Value = self.call(Method::DELETE, &path, None).await?;
+      return self.wait_deleted(&path).await;
+    }
+    log::info!("OVH cluster {} is already deleted", cluster_name);
+    Ok(())
+  }
+
+  async fn wait_deleted(
+    &self,
+    path: &str,
+  ) -> DaemonResult<()> {
+    for _ in 0..OVH_POLL_MAX_ATTEMPTS {
+      match self.call::<Value>(Method::GET, path, None).await {
+        Err(DaemonError::NotFound(_)) => return Ok(()),
+        Err(err) => return Err(err),
+        Ok(_) => tokio::time::sleep(OVH_POLL_INTERVAL).await,
+      }
+    }
+    Err(DaemonError::CloudProvider(format!(
+      "OVH resource {} was not deleted in time",
+      path
+    )))
+  }
+}
@@ -1,4 +1,6 @@
+use crate::mailer::Locale;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
 
 pub(crate) const CONFIG_FILE_NAME: &str = "config.toml";
@@ -9,7 +11,60 @@ pub(crate) struct Config {
   pub(crate) mongodb_database: String,
   pub(crate) kafka_url: Vec<String>,
   pub(crate) redis_url: String,
-  pub(crate) jwt_secret: String,
+  pub(crate) aws_region: String,
+  /// Overrides the AWS endpoint, e.g. to target a local AWS mock
+  pub(crate) aws_endpoint_url: Option<String>,
+  pub(crate) ovh_region: String,
+  /// Overrides the OVH API base URL, e.g. to target a local stub
+  pub(crate) ovh_base_url: Option<String>,
+  /// Id of the key used to encrypt the credentials
+  pub(crate) encryption_key_id: String,
+  /// PEM encoded RSA private keys by id, older keys are kept to decrypt
+  /// the credentials until they are migrated. The API only gets the public
+  /// key of the current one
+  pub(crate) encryption_keys: HashMap<String, String>,
+  /// Bucket of the logos, deleted with their organization
+  pub(crate) s3_endpoint: String,
+  pub(crate) s3_bucket: String,
+  pub(crate) s3_access_key: String,
+  pub(crate) s3_secret_key: String,
+  pub(crate) s3_region: String,
+  /// The emails are not sent when missing
+  pub(crate) mailer: Option<MailerConfig>,
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct MailerConfig {
+  /// Sender of the emails, e.g. `X-Deploy <no-reply@x-deploy.com>`
+  pub(crate) from: String,
+  /// URL of the web app, the links sent by email point to it
+  pub(crate) app_url: String,
+  /// Language of the emails of the users who didn't choose a supported one
+  #[serde(default)]
+  pub(crate) default_locale: Locale,
+  pub(crate) transport: MailTransportConfig,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+pub(crate) enum MailTransportConfig {
+  #[serde(rename = "smtp")]
+  Smtp {
+    host: String,
+    /// The default port of the submission, 587
+    port: Option<u16>,
+    username: Option<String>,
+    password: Option<String>,
+    /// Sends without TLS, e.g. to a local mail catcher
+    #[serde(default)]
+    insecure: bool,
+  },
+  /// Writes each email in an `.eml` file of the directory
+  #[serde(rename = "file")]
+  File { directory: String },
+  /// Keeps the emails in memory, for the tests
+  #[serde(rename = "memory")]
+  Memory,
 }
 
 impl Config {
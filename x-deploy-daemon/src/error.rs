@@ -1 +1,50 @@
+use std::fmt::Debug;
 use x_deploy_common::CommonError;
+
+pub(crate) type DaemonResult<T> = Result<T, DaemonError>;
+
+#[derive(Debug)]
+pub(crate) enum DaemonError {
+  Common(CommonError),
+  CloudProvider(String),
+  NotFound(String),
+}
+
+impl From<CommonError> for DaemonError {
+  fn from(err: CommonError) -> Self {
+    Self::Common(err)
+  }
+}
+
+impl From<DaemonError> for CommonError {
+  fn from(err: DaemonError) -> Self {
+    match err {
+      DaemonError::Common(err) => err,
+      DaemonError::CloudProvider(message) | DaemonError::NotFound(message) => {
+        CommonError::CloudProviderError(message)
+      }
+    }
+  }
+}
+
+impl From<bson::oid::Error> for DaemonError {
+  fn from(err: bson::oid::Error) -> Self {
+    Self::Common(CommonError::FromStrError(err.to_string()))
+  }
+}
+
+impl From<reqwest::Error> for DaemonError {
+  fn from(err: reqwest::Error) -> Self {
+    Self::CloudProvider(err.to_string())
+  }
+}
+
+impl<E, R> From<aws_sdk_eks::error::SdkError<E, R>> for DaemonError
+where
+  E: Debug,
+  R: Debug,
+{
+  fn from(err: aws_sdk_eks::error::SdkError<E, R>) -> Self {
+    Self::CloudProvider(format!("{:?}", err))
+  }
+}
@@ -0,0 +1,41 @@
+use crate::cluster::{provision_cluster, teardown_cluster};
+use bson::oid::ObjectId;
+use mongodb::Database;
+use std::str::FromStr;
+use x_deploy_common::event::cluster::{
+  ClusterCreatedEvent, ClusterDeleteRequestedEvent,
+};
+use x_deploy_common::{CommonError, CommonResult};
+
+/// Provisions the cluster before the event is committed, so a failed or
+/// interrupted provisioning is tried again. As it takes several minutes,
+/// this consumer runs in its own task.
+pub async fn listen_cluster_created(
+  event: ClusterCreatedEvent,
+  db: Database,
+) -> CommonResult<()> {
+  log::info!("Cluster created: {:?}", event);
+  let cluster_id = parse_cluster_id(&event.id)?;
+  provision_cluster(&db, &cluster_id).await?;
+  log::info!("Cluster {} is provisioned", cluster_id);
+  Ok(())
+}
+
+/// Tears the cluster down before the event is committed, so a failed or
+/// interrupted teardown is tried again.
+pub async fn listen_cluster_delete_requested(
+  event: ClusterDeleteRequestedEvent,
+  db: Database,
+) -> CommonResult<()> {
+  log::info!("Cluster delete requested: {:?}", event);
+  let cluster_id = parse_cluster_id(&event.id)?;
+  teardown_cluster(&db, &cluster_id).await?;
+  log::info!("Cluster {} is deleted", cluster_id);
+  Ok(())
+}
+
+fn parse_cluster_id(id: &str) -> CommonResult<ObjectId> {
+  ObjectId::from_str(id).map_err(|err| {
+    CommonError::FromStrError(format!("Invalid cluster id {}: {}", id, err))
+  })
+}
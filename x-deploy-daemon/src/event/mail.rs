@@ -0,0 +1,244 @@
+use crate::mailer::{MailTemplate, Mailer};
+use crate::CONSUMER_GROUP;
+use bson::oid::ObjectId;
+use mongodb::Database;
+use std::str::FromStr;
+use tokio::sync::watch;
+use x_deploy_common::event::organization::OrganizationCreatedEvent;
+use x_deploy_common::event::user::{
+  UserAccountExistsEvent, UserEmailVerificationEvent, UserForgotPasswordEvent,
+  UserMagicLinkEvent, UserPasswordResetEvent,
+};
+use x_deploy_common::event::CommonEvent;
+use x_deploy_common::{CommonError, CommonResult};
+
+/// State of the consumers sending emails.
+#[derive(Clone)]
+pub(crate) struct MailState {
+  pub(crate) db: Database,
+  pub(crate) mailer: Mailer,
+}
+
+/// Consumes the events sending an email until `shutdown` turns true.
+pub(crate) async fn listen_mail_events(
+  common_event: &CommonEvent,
+  state: MailState,
+  shutdown: watch::Receiver<bool>,
+) {
+  let magic_link = common_event.consume::<UserMagicLinkEvent, _, _, _>(
+    CONSUMER_GROUP,
+    state.clone(),
+    shutdown.clone(),
+    listen_user_magic_link,
+  );
+  let forgot_password = common_event
+    .consume::<UserForgotPasswordEvent, _, _, _>(
+      CONSUMER_GROUP,
+      state.clone(),
+      shutdown.clone(),
+      listen_user_forgot_password,
+    );
+  let password_reset = common_event.consume::<UserPasswordResetEvent, _, _, _>(
+    CONSUMER_GROUP,
+    state.clone(),
+    shutdown.clone(),
+    listen_user_password_reset,
+  );
+  let email_verification = common_event
+    .consume::<UserEmailVerificationEvent, _, _, _>(
+      CONSUMER_GROUP,
+      state.clone(),
+      shutdown.clone(),
+      listen_user_email_verification,
+    );
+  let account_exists = common_event.consume::<UserAccountExistsEvent, _, _, _>(
+    CONSUMER_GROUP,
+    state.clone(),
+    shutdown.clone(),
+    listen_user_account_exists,
+  );
+  let organization_created = common_event
+    .consume::<OrganizationCreatedEvent, _, _, _>(
+      CONSUMER_GROUP,
+      state.clone(),
+      shutdown.clone(),
+      listen_organization_created,
+    );
+  let (
+    magic_link,
+    forgot_password,
+    password_reset,
+    email_verification,
+    account_exists,
+    organization_created,
+  ) = tokio::join!(
+    magic_link,
+    forgot_password,
+    password_reset,
+    email_verification,
+    account_exists,
+    organization_created
+  );
+  if let Err(err) = magic_link {
+    log::error!("Error listening to user magic link event {:?}", err);
+  }
+  if let Err(err) = forgot_password {
+    log::error!("Error listening to user forgot password event {:?}", err);
+  }
+  if let Err(err) = password_reset {
+    log::error!("Error listening to user password reset event {:?}", err);
+  }
+  if let Err(err) = email_verification {
+    log::error!("Error listening to user email verification event {:?}", err);
+  }
+  if let Err(err) = account_exists {
+    log::error!("Error listening to user account exists event {:?}", err);
+  }
+  if let Err(err) = organization_created {
+    log::error!("Error listening to organization created event {:?}", err);
+  }
+}
+
+// The events carry tokens, so only their user is logged
+
+pub(crate) async fn listen_user_magic_link(
+  event: UserMagicLinkEvent,
+  state: MailState,
+) -> CommonResult<()> {
+  log::info!("User magic link: {}", event.id);
+  let link = state
+    .mailer
+    .link(&format!("/magic-link?token={}", event.token));
+  let values = [("firstname", event.firstname.clone()), ("link", link)];
+  state
+    .mailer
+    .deliver(
+      &state.db,
+      &event,
+      MailTemplate::MagicLink,
+      &event.id,
+      &event.email,
+      &values,
+    )
+    .await
+}
+
+pub(crate) async fn listen_user_forgot_password(
+  event: UserForgotPasswordEvent,
+  state: MailState,
+) -> CommonResult<()> {
+  log::info!("User forgot password: {}", event.id);
+  let link = state
+    .mailer
+    .link(&format!("/reset-password?token={}", event.token));
+  let values = [("firstname", event.firstname.clone()), ("link", link)];
+  state
+    .mailer
+    .deliver(
+      &state.db,
+      &event,
+      MailTemplate::ForgotPassword,
+      &event.id,
+      &event.email,
+      &values,
+    )
+    .await
+}
+
+pub(crate) async fn listen_user_password_reset(
+  event: UserPasswordResetEvent,
+  state: MailState,
+) -> CommonResult<()> {
+  log::info!("User password reset: {}", event.id);
+  let values = [
+    ("firstname", event.firstname.clone()),
+    ("link", state.mailer.link("/forgot-password")),
+  ];
+  state
+    .mailer
+    .deliver(
+      &state.db,
+      &event,
+      MailTemplate::PasswordReset,
+      &event.id,
+      &event.email,
+      &values,
+    )
+    .await
+}
+
+pub(crate) async fn listen_user_email_verification(
+  event: UserEmailVerificationEvent,
+  state: MailState,
+) -> CommonResult<()> {
+  log::info!("User email verification: {}", event.id);
+  let values = [
+    ("firstname", event.firstname.clone()),
+    ("code", event.code.clone()),
+  ];
+  state
+    .mailer
+    .deliver(
+      &state.db,
+      &event,
+      MailTemplate::EmailVerification,
+      &event.id,
+      &event.email,
+      &values,
+    )
+    .await
+}
+
+pub(crate) async fn listen_user_account_exists(
+  event: UserAccountExistsEvent,
+  state: MailState,
+) -> CommonResult<()> {
+  log::info!("User account exists: {}", event.id);
+  let values = [
+    ("firstname", event.firstname.clone()),
+    ("link", state.mailer.link("/login")),
+  ];
+  state
+    .mailer
+    .deliver(
+      &state.db,
+      &event,
+      MailTemplate::AccountExists,
+      &event.id,
+      &event.email,
+      &values,
+    )
+    .await
+}
+
+pub(crate) async fn listen_organization_created(
+  event: OrganizationCreatedEvent,
+  state: MailState,
+) -> CommonResult<()> {
+  log::info!("Organization created: {:?}", event);
+  let creator_id = ObjectId::from_str(&event.creator_id).map_err(|err| {
+    CommonError::FromStrError(format!(
+      "Invalid user id {}: {}",
+      event.creator_id, err
+    ))
+  })?;
+  let values = [
+    ("firstname", event.creator_firstname.clone()),
+    ("organization", event.name.clone()),
+    (
+      "link",
+      state.mailer.link(&format!("/organizations/{}", event.id)),
+    ),
+  ];
+  state
+    .mailer
+    .deliver(
+      &state.db,
+      &event,
+      MailTemplate::OrganizationCreated,
+      &creator_id,
+      &event.creator_email,
+      &values,
+    )
+    .await
+}
@@ -1 +1,3 @@
-pub(crate) mod user;
\ No newline at end of file
+pub(crate) mod cluster;
+pub(crate) mod mail;
+pub(crate) mod user;
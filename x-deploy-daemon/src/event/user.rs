@@ -1,7 +1,11 @@
+use mongodb::Database;
 use x_deploy_common::event::user::UserRegisteredEvent;
-use x_deploy_common::{CommonError, CommonResult};
+use x_deploy_common::CommonResult;
 
-pub fn listen_user_registered(event: UserRegisteredEvent) -> CommonResult<()> {
-  println!("User registered: {:?}", event);
+pub async fn listen_user_registered(
+  event: UserRegisteredEvent,
+  _db: Database,
+) -> CommonResult<()> {
+  log::info!("User registered: {:?}", event);
   Ok(())
 }
@@ -0,0 +1,240 @@
+use crate::config::MailerConfig;
+use crate::mailer::template::render_mail;
+use crate::mailer::transport::{mail_error, MailTransport};
+use bson::oid::ObjectId;
+use lettre::message::{Mailbox, MultiPart};
+use lettre::Message;
+use mongodb::Database;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::time::Duration;
+use x_deploy_common::crypto::hash::hash_token;
+use x_deploy_common::db::mail_delivery::MailDelivery;
+use x_deploy_common::db::user::User;
+use x_deploy_common::db::CommonCollection;
+use x_deploy_common::CommonResult;
+
+pub(crate) mod template;
+pub(crate) mod transport;
+
+pub(crate) use template::MailTemplate;
+
+// An attempt still running after this delay is considered abandoned, e.g.
+// the daemon stopped while sending
+const DELIVERY_CLAIM_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Language of the emails, each one has its own templates.
+#[derive(Deserialize, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(crate) enum Locale {
+  #[default]
+  #[serde(rename = "en")]
+  En,
+  #[serde(rename = "fr")]
+  Fr,
+}
+
+impl Locale {
+  /// Reads the language of a tag such as `fr-FR`, `None` when it has no
+  /// templates.
+  pub(crate) fn from_tag(tag: &str) -> Option<Self> {
+    let language = tag.split(['-', '_']).next().unwrap_or_default();
+    return match language.to_lowercase().as_str() {
+      "en" => Some(Locale::En),
+      "fr" => Some(Locale::Fr),
+      _ => None,
+    };
+  }
+}
+
+impl Display for Locale {
+  fn fmt(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+  ) -> std::fmt::Result {
+    match self {
+      Locale::En => write!(f, "en"),
+      Locale::Fr => write!(f, "fr"),
+    }
+  }
+}
+
+#[derive(Clone)]
+pub(crate) struct Mailer {
+  transport: MailTransport,
+  from: Mailbox,
+  app_url: String,
+  default_locale: Locale,
+}
+
+impl Mailer {
+  pub(crate) fn new(config: &MailerConfig) -> CommonResult<Self> {
+    Ok(Self {
+      transport: MailTransport::from_config(&config.transport)?,
+      from: config.from.parse::<Mailbox>().map_err(mail_error)?,
+      app_url: config.app_url.trim_end_matches('/').to_string(),
+      default_locale: config.default_locale,
+    })
+  }
+
+  /// Link to a page of the web app.
+  pub(crate) fn link(
+    &self,
+    path: &str,
+  ) -> String {
+    format!("{}{}", self.app_url, path)
+  }
+
+  /// Sends the email of `template` for `event` to a user, in their
+  /// language. The delivery is tracked by event, so an event consumed
+  /// again after the email was sent doesn't send it twice.
+  pub(crate) async fn deliver<T: Serialize>(
+    &self,
+    db: &Database,
+    event: &T,
+    template: MailTemplate,
+    user_id: &ObjectId,
+    to: &String,
+    values: &[(&str, String)],
+  ) -> CommonResult<()> {
+    let deliveries = CommonCollection::<MailDelivery>::new(db);
+    let delivery_id = format!(
+      "{}:{}",
+      template.name(),
+      hash_token(&serde_json::to_string(event)?)
+    );
+    let delivery = deliveries
+      .claim(
+        &delivery_id,
+        &template.name().to_string(),
+        to,
+        DELIVERY_CLAIM_TIMEOUT,
+      )
+      .await?;
+    if delivery.is_none() {
+      log::info!("Email {} is already sent or sending", delivery_id);
+      return Ok(());
+    }
+    let locale = self.locale_of(db, user_id).await?;
+    let sent = match self.build(template, locale, to, values) {
+      Ok(message) => self.transport.send(message).await,
+      Err(err) => Err(err),
+    };
+    return match sent {
+      Ok(_) => {
+        deliveries.mark_sent(&delivery_id).await?;
+        log::info!("Email {} is sent", delivery_id);
+        Ok(())
+      }
+      Err(err) => {
+        let error = format!("{:?}", err);
+        deliveries.mark_failed(&delivery_id, &error).await?;
+        Err(err)
+      }
+    };
+  }
+
+  async fn locale_of(
+    &self,
+    db: &Database,
+    user_id: &ObjectId,
+  ) -> CommonResult<Locale> {
+    let user = CommonCollection::<User>::new(db).get_by_id(user_id).await?;
+    let locale = user
+      .and_then(|user| user.locale)
+      .and_then(|tag| Locale::from_tag(&tag));
+    Ok(locale.unwrap_or(self.default_locale))
+  }
+
+  fn build(
+    &self,
+    template: MailTemplate,
+    locale: Locale,
+    to: &String,
+    values: &[(&str, String)],
+  ) -> CommonResult<Message> {
+    let mail = render_mail(template, locale, values);
+    let message = Message::builder()
+      .from(self.from.clone())
+      .to(to.parse::<Mailbox>().map_err(mail_error)?)
+      .subject(mail.subject)
+      .multipart(MultiPart::alternative_plain_html(mail.text, mail.html))
+      .map_err(mail_error)?;
+    Ok(message)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::MailTransportConfig;
+  use x_deploy_common::event::user::UserEmailVerificationEvent;
+
+  #[test]
+  fn locale_from_tag() {
+    assert_eq!(Locale::from_tag("en"), Some(Locale::En));
+    assert_eq!(Locale::from_tag("en-US"), Some(Locale::En));
+    assert_eq!(Locale::from_tag("fr_FR"), Some(Locale::Fr));
+    assert_eq!(Locale::from_tag("FR-ca"), Some(Locale::Fr));
+    assert_eq!(Locale::from_tag("de-DE"), None);
+    assert_eq!(Locale::from_tag(""), None);
+  }
+
+  fn memory_mailer() -> Mailer {
+    let config = MailerConfig {
+      from: "X-Deploy <no-reply@x-deploy.com>".to_string(),
+      app_url: "https://app.x-deploy.com/".to_string(),
+      default_locale: Locale::En,
+      transport: MailTransportConfig::Memory,
+    };
+    Mailer::new(&config).unwrap()
+  }
+
+  #[test]
+  fn link_joins_the_app_url() {
+    let mailer = memory_mailer();
+    assert_eq!(
+      mailer.link("/forgot-password"),
+      "https://app.x-deploy.com/forgot-password"
+    );
+  }
+
+  // The deliveries are tracked in MongoDB, set MONGODB_URL to run it
+  #[tokio::test]
+  #[ignore = "needs a MongoDB server"]
+  async fn deliver_sends_an_event_once() {
+    let url = std::env::var("MONGODB_URL")
+      .unwrap_or_else(|_| "mongodb://localhost:27017".to_string());
+    let client = mongodb::Client::with_uri_str(&url).await.unwrap();
+    let db = client.database("x-deploy-daemon-test");
+    let mailer = memory_mailer();
+    let event = UserEmailVerificationEvent {
+      id: ObjectId::new(),
+      firstname: "Jane".to_string(),
+      lastname: "Doe".to_string(),
+      email: "jane@example.com".to_string(),
+      code: "123456".to_string(),
+    };
+    let values = [
+      ("firstname", event.firstname.clone()),
+      ("code", event.code.clone()),
+    ];
+    for _ in 0..2 {
+      mailer
+        .deliver(
+          &db,
+          &event,
+          MailTemplate::EmailVerification,
+          &event.id,
+          &event.email,
+          &values,
+        )
+        .await
+        .unwrap();
+    }
+    let sent = mailer.transport.sent();
+    assert_eq!(sent.len(), 1);
+    let to = sent[0].envelope().to();
+    assert_eq!(to.len(), 1);
+    assert_eq!(to[0].to_string(), "jane@example.com");
+  }
+}
@@ -0,0 +1,255 @@
+use crate::mailer::Locale;
+
+const LAYOUT: &str = include_str!("../../templates/layout.html");
+
+macro_rules! template {
+  ($locale:literal, $name:literal) => {
+    (
+      include_str!(concat!("../../templates/", $locale, "/", $name, ".html")),
+      include_str!(concat!("../../templates/", $locale, "/", $name, ".txt")),
+    )
+  };
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum MailTemplate {
+  MagicLink,
+  ForgotPassword,
+  PasswordReset,
+  EmailVerification,
+  AccountExists,
+  OrganizationCreated,
+}
+
+impl MailTemplate {
+  pub(crate) fn name(&self) -> &'static str {
+    match self {
+      MailTemplate::MagicLink => "magic_link",
+      MailTemplate::ForgotPassword => "forgot_password",
+      MailTemplate::PasswordReset => "password_reset",
+      MailTemplate::EmailVerification => "email_verification",
+      MailTemplate::AccountExists => "account_exists",
+      MailTemplate::OrganizationCreated => "organization_created",
+    }
+  }
+
+  fn subject(
+    &self,
+    locale: Locale,
+  ) -> &'static str {
+    match (self, locale) {
+      (MailTemplate::MagicLink, Locale::En) => "Your login link",
+      (MailTemplate::MagicLink, Locale::Fr) => "Votre lien de connexion",
+      (MailTemplate::ForgotPassword, Locale::En) => "Reset your password",
+      (MailTemplate::ForgotPassword, Locale::Fr) => {
+        "Réinitialisez votre mot de passe"
+      }
+      (MailTemplate::PasswordReset, Locale::En) => "Your password was reset",
+      (MailTemplate::PasswordReset, Locale::Fr) => {
+        "Votre mot de passe a été réinitialisé"
+      }
+      (MailTemplate::EmailVerification, Locale::En) => "Verify your email",
+      (MailTemplate::EmailVerification, Locale::Fr) => {
+        "Vérifiez votre adresse email"
+      }
+      (MailTemplate::AccountExists, Locale::En) => {
+        "You already have an account"
+      }
+      (MailTemplate::AccountExists, Locale::Fr) => "Vous avez déjà un compte",
+      (MailTemplate::OrganizationCreated, Locale::En) => {
+        "Your organization {{organization}} is ready"
+      }
+      (MailTemplate::OrganizationCreated, Locale::Fr) => {
+        "Votre organisation {{organization}} est prête"
+      }
+    }
+  }
+
+  /// The HTML and text bodies.
+  fn bodies(
+    &self,
+    locale: Locale,
+  ) -> (&'static str, &'static str) {
+    match (self, locale) {
+      (MailTemplate::MagicLink, Locale::En) => template!("en", "magic_link"),
+      (MailTemplate::MagicLink, Locale::Fr) => template!("fr", "magic_link"),
+      (MailTemplate::ForgotPassword, Locale::En) => {
+        template!("en", "forgot_password")
+      }
+      (MailTemplate::ForgotPassword, Locale::Fr) => {
+        template!("fr", "forgot_password")
+      }
+      (MailTemplate::PasswordReset, Locale::En) => {
+        template!("en", "password_reset")
+      }
+      (MailTemplate::PasswordReset, Locale::Fr) => {
+        template!("fr", "password_reset")
+      }
+      (MailTemplate::EmailVerification, Locale::En) => {
+        template!("en", "email_verification")
+      }
+      (MailTemplate::EmailVerification, Locale::Fr) => {
+        template!("fr", "email_verification")
+      }
+      (MailTemplate::AccountExists, Locale::En) => {
+        template!("en", "account_exists")
+      }
+      (MailTemplate::AccountExists, Locale::Fr) => {
+        template!("fr", "account_exists")
+      }
+      (MailTemplate::OrganizationCreated, Locale::En) => {
+        template!("en", "organization_created")
+      }
+      (MailTemplate::OrganizationCreated, Locale::Fr) => {
+        template!("fr", "organization_created")
+      }
+    }
+  }
+}
+
+pub(crate) struct RenderedMail {
+  pub(crate) subject: String,
+  pub(crate) html: String,
+  pub(crate) text: String,
+}
+
+pub(crate) fn render_mail(
+  template: MailTemplate,
+  locale: Locale,
+  values: &[(&str, String)],
+) -> RenderedMail {
+  let subject = render(template.subject(locale), values, false);
+  let (html, text) = template.bodies(locale);
+  let content = render(html, values, true);
+  let layout_values = [
+    ("lang", locale.to_string()),
+    ("subject", escape_html(&subject)),
+    ("content", content),
+  ];
+  RenderedMail {
+    html: render(LAYOUT, &layout_values, false),
+    text: render(text, values, false),
+    subject,
+  }
+}
+
+/// Replaces the `{{name}}` placeholders by their value, in a single pass so
+/// that values are never read as placeholders. Values are escaped when the
+/// template is HTML.
+fn render(
+  template: &str,
+  values: &[(&str, String)],
+  html: bool,
+) -> String {
+  let mut rendered = String::with_capacity(template.len());
+  let mut rest = template;
+  while let Some(start) = rest.find("{{") {
+    let end = match rest[start + 2..].find("}}") {
+      Some(end) => start + 2 + end,
+      None => break,
+    };
+    rendered.push_str(&rest[..start]);
+    let name = rest[start + 2..end].trim();
+    match values.iter().find(|(key, _)| *key == name) {
+      Some((_, value)) if html => rendered.push_str(&escape_html(value)),
+      Some((_, value)) => rendered.push_str(value),
+      None => log::warn!("No value for {} in a mail template", name),
+    }
+    rest = &rest[end + 2..];
+  }
+  rendered.push_str(rest);
+  rendered
+}
+
+fn escape_html(value: &str) -> String {
+  let mut escaped = String::with_capacity(value.len());
+  for c in value.chars() {
+    match c {
+      '&' => escaped.push_str("&amp;"),
+      '<' => escaped.push_str("&lt;"),
+      '>' => escaped.push_str("&gt;"),
+      '"' => escaped.push_str("&quot;"),
+      '\'' => escaped.push_str("&#39;"),
+      _ => escaped.push(c),
+    }
+  }
+  escaped
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn render_replaces_placeholders() {
+    let values = [("firstname", "Jane".to_string())];
+    let rendered =
+      render("Hello {{firstname}}, {{ firstname }}!", &values, false);
+    assert_eq!(rendered, "Hello Jane, Jane!");
+  }
+
+  #[test]
+  fn render_escapes_values_in_html() {
+    let values = [("name", "<b>Tom & 'Jerry'</b>".to_string())];
+    let rendered = render("<p>{{name}}</p>", &values, true);
+    assert_eq!(
+      rendered,
+      "<p>&lt;b&gt;Tom &amp; &#39;Jerry&#39;&lt;/b&gt;</p>"
+    );
+  }
+
+  #[test]
+  fn render_keeps_values_in_text() {
+    let values = [("name", "<b>Tom & Jerry</b>".to_string())];
+    let rendered = render("Hello {{name}}", &values, false);
+    assert_eq!(rendered, "Hello <b>Tom & Jerry</b>");
+  }
+
+  #[test]
+  fn render_removes_missing_placeholders() {
+    let rendered = render("Hello {{firstname}}!", &[], false);
+    assert_eq!(rendered, "Hello !");
+  }
+
+  #[test]
+  fn render_does_not_read_values_as_placeholders() {
+    let values = [
+      ("firstname", "{{code}}".to_string()),
+      ("code", "123456".to_string()),
+    ];
+    let rendered = render("{{firstname}} {{code}}", &values, false);
+    assert_eq!(rendered, "{{code}} 123456");
+  }
+
+  #[test]
+  fn render_keeps_unclosed_placeholders() {
+    let values = [("code", "123456".to_string())];
+    let rendered = render("{{code}} {{code", &values, false);
+    assert_eq!(rendered, "123456 {{code");
+  }
+
+  #[test]
+  fn escape_html_escapes_special_characters() {
+    assert_eq!(
+      escape_html(r#"<a href="x">'&'</a>"#),
+      "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;"
+    );
+    assert_eq!(escape_html("Jane Doe"), "Jane Doe");
+  }
+
+  #[test]
+  fn render_mail_escapes_the_subject_in_the_layout() {
+    let values = [
+      ("firstname", "Jane".to_string()),
+      ("organization", "<Acme>".to_string()),
+      ("link", "https://app/organizations/1".to_string()),
+    ];
+    let mail =
+      render_mail(MailTemplate::OrganizationCreated, Locale::Fr, &values);
+    assert_eq!(mail.subject, "Votre organisation <Acme> est prête");
+    assert!(mail.html.contains("<html lang=\"fr\">"));
+    assert!(mail.html.contains("&lt;Acme&gt;"));
+    assert!(!mail.html.contains("<Acme>"));
+    assert!(mail.text.contains("<Acme>"));
+  }
+}
@@ -0,0 +1,89 @@
+use crate::config::MailTransportConfig;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{
+  AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message,
+  Tokio1Executor,
+};
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use x_deploy_common::{CommonError, CommonResult};
+
+#[derive(Clone)]
+pub(crate) enum MailTransport {
+  Smtp(AsyncSmtpTransport<Tokio1Executor>),
+  File(AsyncFileTransport<Tokio1Executor>),
+  Memory(Arc<Mutex<Vec<Message>>>),
+}
+
+impl MailTransport {
+  pub(crate) fn from_config(
+    config: &MailTransportConfig
+  ) -> CommonResult<Self> {
+    return match config {
+      MailTransportConfig::Smtp {
+        host,
+        port,
+        username,
+        password,
+        insecure,
+      } => {
+        let mut builder = match *insecure {
+          true => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+          false => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .map_err(mail_error)?,
+        };
+        if let Some(port) = port {
+          builder = builder.port(*port);
+        }
+        if let (Some(username), Some(password)) = (username, password) {
+          builder = builder
+            .credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(MailTransport::Smtp(builder.build()))
+      }
+      MailTransportConfig::File { directory } => {
+        std::fs::create_dir_all(directory).map_err(mail_error)?;
+        Ok(MailTransport::File(AsyncFileTransport::new(directory)))
+      }
+      MailTransportConfig::Memory => {
+        Ok(MailTransport::Memory(Arc::new(Mutex::new(Vec::new()))))
+      }
+    };
+  }
+
+  pub(crate) async fn send(
+    &self,
+    message: Message,
+  ) -> CommonResult<()> {
+    match self {
+      MailTransport::Smtp(transport) => {
+        transport.send(message).await.map_err(mail_error)?;
+      }
+      MailTransport::File(transport) => {
+        transport.send(message).await.map_err(mail_error)?;
+      }
+      MailTransport::Memory(sent) => match sent.lock() {
+        Ok(mut sent) => sent.push(message),
+        Err(err) => return Err(mail_error(err)),
+      },
+    }
+    Ok(())
+  }
+
+  /// The emails sent through the memory transport, other transports keep
+  /// nothing.
+  #[cfg(test)]
+  pub(crate) fn sent(&self) -> Vec<Message> {
+    return match self {
+      MailTransport::Memory(sent) => match sent.lock() {
+        Ok(sent) => sent.clone(),
+        Err(_) => Vec::new(),
+      },
+      _ => Vec::new(),
+    };
+  }
+}
+
+pub(crate) fn mail_error<E: Display>(err: E) -> CommonError {
+  CommonError::MailError(err.to_string())
+}
@@ -1,15 +1,36 @@
 use crate::config::Config;
-use env_logger::filter::Filter;
+use crate::event::mail::{listen_mail_events, MailState};
+use crate::mailer::Mailer;
+use crate::organization::purge_deleted_organizations;
 use lazy_static::lazy_static;
-use x_deploy_common::event::user::UserEvent;
+use tokio::sync::watch;
+use x_deploy_common::crypto::keyring::Keyring;
+use x_deploy_common::crypto::migration::migrate_credentials;
+use x_deploy_common::event::cluster::{
+  ClusterCreatedEvent, ClusterDeleteRequestedEvent,
+};
+use x_deploy_common::event::producer::EventProducer;
+use x_deploy_common::event::user::UserRegisteredEvent;
+use x_deploy_common::event::CommonEvent;
 
 mod cluster;
 mod config;
 mod error;
 mod event;
+mod mailer;
+mod organization;
+
+const CONSUMER_GROUP: &str = "x-deploy-daemon";
 
 lazy_static! {
   pub(crate) static ref CONFIG: Config = Config::from_config_file();
+  pub(crate) static ref KEYRING: Keyring = Keyring::new(
+    CONFIG.encryption_key_id.clone(),
+    &CONFIG.encryption_keys
+  )
+  .expect("Error while loading the encryption keys");
+  pub(crate) static ref PRODUCER: EventProducer =
+    EventProducer::new(CONFIG.kafka_url.clone());
 }
 
 #[tokio::main]
@@ -17,16 +38,101 @@ async fn main() {
   env_logger::Builder::new()
     .filter_level(log::LevelFilter::max())
     .init();
-  let kafka_url = CONFIG.kafka_url.clone();
-  let user_registered = tokio::spawn(async move {
-    let result = UserEvent::listen_registered(
-      kafka_url.clone(),
-      event::user::listen_user_registered,
-    )
-    .await;
-    if let Err(err) = result {
-      log::error!("Error listening to user registered event {:?}", err);
+  let mongodb_client =
+    mongodb::Client::with_uri_str(CONFIG.mongodb_url.as_str())
+      .await
+      .expect("Failed to connect to mongodb");
+  let db = mongodb_client.database(CONFIG.mongodb_database.as_str());
+  match migrate_credentials(&db, &KEYRING).await {
+    Ok(updated) => log::info!("Encrypted or rotated {} credentials", updated),
+    Err(err) => log::error!("Error migrating the credentials {:?}", err),
+  }
+  let (shutdown_sender, shutdown) = watch::channel(false);
+  tokio::spawn(async move {
+    if let Err(err) = tokio::signal::ctrl_c().await {
+      log::error!("Error listening to the shutdown signal {:?}", err);
     }
+    log::info!("Shutting down, waiting for the consumers to stop");
+    let _ = shutdown_sender.send(true);
   });
-  tokio::join!(user_registered);
+
+  let common_event = CommonEvent::new(CONFIG.kafka_url.clone());
+  let user_registered = common_event.consume::<UserRegisteredEvent, _, _, _>(
+    CONSUMER_GROUP,
+    db.clone(),
+    shutdown.clone(),
+    event::user::listen_user_registered,
+  );
+  // Provisioning and teardown take minutes, their consumers run in their
+  // own tasks
+  let cluster_created = tokio::spawn({
+    let (db, shutdown) = (db.clone(), shutdown.clone());
+    async move {
+      CommonEvent::new(CONFIG.kafka_url.clone())
+        .consume::<ClusterCreatedEvent, _, _, _>(
+          CONSUMER_GROUP,
+          db,
+          shutdown,
+          event::cluster::listen_cluster_created,
+        )
+        .await
+    }
+  });
+  let cluster_delete_requested = tokio::spawn({
+    let (db, shutdown) = (db.clone(), shutdown.clone());
+    async move {
+      CommonEvent::new(CONFIG.kafka_url.clone())
+        .consume::<ClusterDeleteRequestedEvent, _, _, _>(
+          CONSUMER_GROUP,
+          db,
+          shutdown,
+          event::cluster::listen_cluster_delete_requested,
+        )
+        .await
+    }
+  });
+  let mail_events = async {
+    match &CONFIG.mailer {
+      Some(config) => {
+        let mailer =
+          Mailer::new(config).expect("Error while creating the mailer");
+        let state = MailState {
+          db: db.clone(),
+          mailer,
+        };
+        listen_mail_events(&common_event, state, shutdown.clone()).await
+      }
+      None => log::warn!("No mailer is configured, emails won't be sent"),
+    }
+  };
+  let organization_purge =
+    purge_deleted_organizations(db.clone(), shutdown.clone());
+  let (user_registered, cluster_created, cluster_delete_requested, _, _) =
+    tokio::join!(
+      user_registered,
+      cluster_created,
+      cluster_delete_requested,
+      mail_events,
+      organization_purge
+    );
+  if let Err(err) = user_registered {
+    log::error!("Error listening to user registered event {:?}", err);
+  }
+  match cluster_created {
+    Ok(Ok(_)) => {}
+    Ok(Err(err)) => {
+      log::error!("Error listening to cluster created event {:?}", err)
+    }
+    Err(err) => log::error!("Cluster created consumer panicked {:?}", err),
+  }
+  match cluster_delete_requested {
+    Ok(Ok(_)) => {}
+    Ok(Err(err)) => log::error!(
+      "Error listening to cluster delete requested event {:?}",
+      err
+    ),
+    Err(err) => {
+      log::error!("Cluster delete requested consumer panicked {:?}", err)
+    }
+  }
 }
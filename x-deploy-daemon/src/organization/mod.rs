@@ -0,0 +1,185 @@
+use crate::cluster::teardown_cluster;
+use crate::error::{DaemonError, DaemonResult};
+use crate::{CONFIG, PRODUCER};
+use mongodb::Database;
+use std::time::Duration;
+use tokio::sync::watch;
+use x_deploy_common::db::organization::Organization;
+use x_deploy_common::db::organization_apikey::OrganizationApiKey;
+use x_deploy_common::db::organization_credential_aws::OrganizationCredentialAws;
+use x_deploy_common::db::organization_credential_docker_hub::OrganizationCredentialDockerHub;
+use x_deploy_common::db::organization_credential_ovh::OrganizationCredentialOvh;
+use x_deploy_common::db::organization_invitation::OrganizationInvitation;
+use x_deploy_common::db::organization_member::OrganizationMember;
+use x_deploy_common::db::organization_project::OrganizationProject;
+use x_deploy_common::db::organization_project_cluster::OrganizationProjectCluster;
+use x_deploy_common::db::organization_project_deployment::OrganizationProjectDeployment;
+use x_deploy_common::db::organization_project_environment::OrganizationProjectEnvironment;
+use x_deploy_common::db::organization_role::OrganizationRole;
+use x_deploy_common::db::user::User;
+use x_deploy_common::db::CommonCollection;
+use x_deploy_common::event::organization::OrganizationDeletedEvent;
+use x_deploy_common::s3::bucket::CommonS3Bucket;
+use x_deploy_common::s3::config::CommonS3Config;
+use x_deploy_common::s3::file_type::CommonS3BucketType::OrganizationLogo;
+use x_deploy_common::s3::file_type::ToFilePrefix;
+
+// Time to wait before looking for organizations to purge when there is none
+const PURGE_IDLE_DELAY: Duration = Duration::from_secs(60);
+
+// Tearing down the clusters takes a while, a purge claimed for longer than
+// this is considered abandoned and is tried again
+const PURGE_CLAIM_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// Purges the deleted organizations whose restore window is over, until
+/// `shutdown` turns true.
+pub(crate) async fn purge_deleted_organizations(
+  db: Database,
+  mut shutdown: watch::Receiver<bool>,
+) {
+  log::info!("Organization purge started");
+  let org_coll = CommonCollection::<Organization>::new(&db);
+  while !*shutdown.borrow() {
+    let organization =
+      match org_coll.claim_next_purge(PURGE_CLAIM_TIMEOUT).await {
+        Ok(organization) => organization,
+        Err(err) => {
+          log::error!("Error claiming an organization to purge {:?}", err);
+          None
+        }
+      };
+    if let Some(organization) = organization {
+      match purge_organization(&db, &organization).await {
+        Ok(_) => log::info!("Organization {} is purged", organization.id),
+        Err(err) => log::error!(
+          "Error purging organization {}: {:?}",
+          organization.id,
+          err
+        ),
+      }
+      continue;
+    }
+    tokio::select! {
+      _ = tokio::time::sleep(PURGE_IDLE_DELAY) => {}
+      _ = shutdown.changed() => {}
+    }
+  }
+  log::info!("Organization purge stopped");
+}
+
+/// Deletes an organization and everything it owns. The clusters are torn
+/// down first since their teardown needs the credentials, and the
+/// organization itself is deleted last so that a failed purge is tried
+/// again.
+async fn purge_organization(
+  db: &Database,
+  organization: &Organization,
+) -> DaemonResult<()> {
+  let org_id = &organization.id;
+  let cluster_coll = CommonCollection::<OrganizationProjectCluster>::new(db);
+  for cluster in cluster_coll.get_of_org(org_id).await? {
+    match teardown_cluster(db, &cluster.id).await {
+      Ok(_) | Err(DaemonError::NotFound(_)) => {}
+      Err(err) => return Err(err),
+    }
+  }
+  CommonCollection::<OrganizationProjectDeployment>::new(db)
+    .delete_of_org(org_id)
+    .await?;
+  CommonCollection::<OrganizationProjectEnvironment>::new(db)
+    .delete_of_org(org_id)
+    .await?;
+  CommonCollection::<OrganizationProject>::new(db)
+    .delete_of_org(org_id)
+    .await?;
+  CommonCollection::<OrganizationApiKey>::new(db)
+    .delete_of_org(org_id)
+    .await?;
+  CommonCollection::<OrganizationInvitation>::new(db)
+    .delete_of_org(org_id)
+    .await?;
+  CommonCollection::<OrganizationMember>::new(db)
+    .delete_of_org(org_id)
+    .await?;
+  CommonCollection::<OrganizationRole>::new(db)
+    .delete_of_org(org_id)
+    .await?;
+  CommonCollection::<OrganizationCredentialAws>::new(db)
+    .delete_of_org(org_id)
+    .await?;
+  CommonCollection::<OrganizationCredentialOvh>::new(db)
+    .delete_of_org(org_id)
+    .await?;
+  CommonCollection::<OrganizationCredentialDockerHub>::new(db)
+    .delete_of_org(org_id)
+    .await?;
+  delete_logo(organization).await?;
+  publish_deleted(db, organization).await?;
+  CommonCollection::<Organization>::new(db)
+    .delete_by_id(org_id)
+    .await?;
+  Ok(())
+}
+
+async fn delete_logo(organization: &Organization) -> DaemonResult<()> {
+  let logo_url = match &organization.logo_url {
+    Some(logo_url) => logo_url,
+    None => return Ok(()),
+  };
+  // The url ends with the prefix of the logos followed by the filename
+  let prefix = format!("{}-", OrganizationLogo.to_prefix());
+  let filename = logo_url
+    .rsplit('/')
+    .next()
+    .and_then(|name| name.strip_prefix(prefix.as_str()));
+  let filename = match filename {
+    Some(filename) => filename.to_string(),
+    None => {
+      log::warn!("Logo {} is not in the bucket, skipping it", logo_url);
+      return Ok(());
+    }
+  };
+  let s3_config = CommonS3Config::new(
+    CONFIG.s3_endpoint.clone(),
+    CONFIG.s3_bucket.clone(),
+    CONFIG.s3_access_key.clone(),
+    CONFIG.s3_secret_key.clone(),
+    CONFIG.s3_region.clone(),
+  );
+  CommonS3Bucket::new(OrganizationLogo, s3_config)
+    .remove(&filename)
+    .await?;
+  Ok(())
+}
+
+async fn publish_deleted(
+  db: &Database,
+  organization: &Organization,
+) -> DaemonResult<()> {
+  let deleter = match &organization.deleted_by {
+    Some(deleter_id) => {
+      CommonCollection::<User>::new(db)
+        .get_by_id(deleter_id)
+        .await?
+    }
+    None => None,
+  };
+  let (deleter_firstname, deleter_lastname) = match deleter {
+    Some(deleter) => (deleter.firstname, deleter.lastname),
+    None => (String::new(), String::new()),
+  };
+  PRODUCER
+    .publish(&OrganizationDeletedEvent {
+      id: organization.id.to_string(),
+      name: organization.name.clone(),
+      description: organization.description.clone().unwrap_or_default(),
+      deleter_id: organization
+        .deleted_by
+        .map(|id| id.to_string())
+        .unwrap_or_default(),
+      deleter_firstname,
+      deleter_lastname,
+    })
+    .await?;
+  Ok(())
+}
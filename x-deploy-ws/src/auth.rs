@@ -0,0 +1,199 @@
+use crate::jwks::Jwks;
+use bson::oid::ObjectId;
+use jsonwebtoken::errors::ErrorKind;
+use mongodb::Database;
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::FromRequest;
+use rocket::{request, Request};
+use serde::Deserialize;
+use std::net::IpAddr;
+use std::str::FromStr;
+use x_deploy_common::cache::session::is_session_revoked;
+use x_deploy_common::data::ip_rule::is_ip_allowed;
+use x_deploy_common::db::organization_apikey::OrganizationApiKey;
+use x_deploy_common::db::query::organization_api_key::OrganizationApiKeyQuery;
+use x_deploy_common::db::CommonCollection;
+
+/// Claims of the bearer tokens issued by the API.
+#[derive(Debug, Deserialize)]
+struct BearerToken {
+  id: String,
+  exp: i64,
+  otp: Option<bool>,
+  #[serde(default)]
+  sid: Option<String>,
+}
+
+/// Authentication of a WebSocket connection, with the same bearer token
+/// and API key scheme as the API. A user keeps the session and expiration
+/// of its token, the connection is closed once either ends.
+pub enum Auth {
+  User {
+    id: ObjectId,
+    sid: Option<String>,
+    exp: i64,
+  },
+  ApiKey(OrganizationApiKeyQuery),
+}
+
+impl Auth {
+  async fn from_authorization(
+    db: &Database,
+    redis: &redis::Client,
+    jwks: &Jwks,
+    authorization: &str,
+    client_ip: Option<IpAddr>,
+  ) -> Result<Self, (Status, &'static str)> {
+    if let Some(token) = authorization.strip_prefix("Bearer ") {
+      return Self::from_bearer_token(redis, jwks, token).await;
+    }
+    Self::from_api_key(db, authorization, client_ip).await
+  }
+
+  async fn from_bearer_token(
+    redis: &redis::Client,
+    jwks: &Jwks,
+    token: &str,
+  ) -> Result<Self, (Status, &'static str)> {
+    let claims = match jwks.verify::<BearerToken>(token).await {
+      Ok(claims) => claims,
+      Err(e) => {
+        return match e.kind() {
+          ErrorKind::ExpiredSignature => {
+            Err((Status::Unauthorized, "Token is expired, please login again"))
+          }
+          _ => Err((Status::Unauthorized, "Error while parsing jwt token")),
+        };
+      }
+    };
+    if claims.otp == Some(false) {
+      return Err((Status::Unauthorized, "2FA not validated"));
+    }
+    check_session(redis, &claims.sid).await?;
+    return match ObjectId::from_str(claims.id.as_str()) {
+      Ok(id) => Ok(Auth::User {
+        id,
+        sid: claims.sid,
+        exp: claims.exp,
+      }),
+      Err(_) => Err((Status::Unauthorized, "Error while parsing token id")),
+    };
+  }
+
+  async fn from_api_key(
+    db: &Database,
+    value: &str,
+    client_ip: Option<IpAddr>,
+  ) -> Result<Self, (Status, &'static str)> {
+    let akc = CommonCollection::<OrganizationApiKey>::new(db);
+    let api_key = match akc.get_by_value(value).await {
+      Ok(Some(api_key)) => api_key,
+      Ok(None) => return Err((Status::Unauthorized, "Invalid api key")),
+      Err(_) => {
+        let message = "Failed to verify api key in database";
+        return Err((Status::InternalServerError, message));
+      }
+    };
+    check_api_key_expiration(&api_key)?;
+    if !api_key.allowed_ips.is_empty() {
+      let allowed = match &client_ip {
+        Some(ip) => is_ip_allowed(&api_key.allowed_ips, ip),
+        None => false,
+      };
+      if !allowed {
+        let message = "Your IP address is not allowed to use this api key";
+        return Err((Status::Forbidden, message));
+      }
+    }
+    Ok(Auth::ApiKey(api_key))
+  }
+}
+
+/// Verifies the session of a token wasn't revoked.
+pub(crate) async fn check_session(
+  redis: &redis::Client,
+  sid: &Option<String>,
+) -> Result<(), (Status, &'static str)> {
+  let sid = match sid {
+    Some(sid) => sid,
+    None => return Ok(()),
+  };
+  return match is_session_revoked(redis, sid).await {
+    Ok(false) => Ok(()),
+    Ok(true) => {
+      Err((Status::Unauthorized, "Session revoked, please login again."))
+    }
+    Err(_) => {
+      let message = "Failed to verify the session in cache";
+      Err((Status::InternalServerError, message))
+    }
+  };
+}
+
+pub(crate) fn check_api_key_expiration(
+  api_key: &OrganizationApiKeyQuery
+) -> Result<(), (Status, &'static str)> {
+  if let Some(expires_at) = api_key.expires_at {
+    if expires_at < bson::DateTime::now() {
+      return Err((
+        Status::Unauthorized,
+        "Api key is expired, please login again.",
+      ));
+    }
+  }
+  Ok(())
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Auth {
+  type Error = &'static str;
+
+  async fn from_request(
+    req: &'r Request<'_>
+  ) -> request::Outcome<Self, Self::Error> {
+    // Browsers can't set headers on WebSocket requests, so the value can
+    // also be given in the `authorization` query parameter.
+    let header = req.headers().get_one("Authorization");
+    let query = req
+      .query_value::<&str>("authorization")
+      .and_then(|value| value.ok());
+    let authorization = match header.or(query) {
+      Some(authorization) => authorization,
+      None => {
+        let message = "Authorization header must be present";
+        return Outcome::Error((Status::Unauthorized, message));
+      }
+    };
+    let db = match req.rocket().state::<Database>() {
+      Some(db) => db,
+      None => {
+        let message =
+          "Database connection error for validate your authentication";
+        return Outcome::Error((Status::InternalServerError, message));
+      }
+    };
+    let redis = match req.rocket().state::<redis::Client>() {
+      Some(redis) => redis,
+      None => {
+        let message = "Cache connection error for validate your authentication";
+        return Outcome::Error((Status::InternalServerError, message));
+      }
+    };
+    let jwks = match req.rocket().state::<Jwks>() {
+      Some(jwks) => jwks,
+      None => {
+        let message =
+          "Failed to load the keys for validate your authentication";
+        return Outcome::Error((Status::InternalServerError, message));
+      }
+    };
+    let client_ip = req.client_ip().map(|ip| ip.to_canonical());
+    let auth =
+      Auth::from_authorization(db, redis, jwks, authorization, client_ip);
+    return match auth.await {
+      Ok(auth) => Outcome::Success(auth),
+      Err(error) => Outcome::Error(error),
+    };
+  }
+}
@@ -0,0 +1,32 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct Config {
+  // General
+  /// The JWKS endpoint of the API, e.g. `https://api/.well-known/jwks.json`
+  pub(crate) jwks_url: String,
+  // MongoDB
+  pub(crate) mongodb_url: String,
+  pub(crate) mongodb_database: String,
+  // Kafka
+  pub(crate) kafka_url: Vec<String>,
+  // Every instance must use its own group to receive all the events
+  pub(crate) kafka_consumer_group: String,
+  // Redis, where the API marks the revoked sessions
+  pub(crate) redis_url: String,
+  // Header holding the client IP, only to set behind a reverse proxy which
+  // overwrites it on every request, e.g. `X-Real-IP`
+  #[serde(default)]
+  pub(crate) client_ip_header: Option<String>,
+}
+
+impl Config {
+  pub(crate) fn from_rocket_config() -> Self {
+    let figment = rocket::Config::figment();
+    let config = figment.extract::<Config>();
+    return match config {
+      Ok(config) => config,
+      Err(err) => panic!("Error while parsing config file: {}", err),
+    };
+  }
+}
@@ -0,0 +1,92 @@
+use crate::hub::{Channel, Hub, Notification};
+use crate::CONFIG;
+use bson::oid::ObjectId;
+use serde::Serialize;
+use std::str::FromStr;
+use tokio::sync::watch;
+use x_deploy_common::event::cluster::ClusterStatusChangedEvent;
+use x_deploy_common::event::deployment::DeploymentRolloutRequestedEvent;
+use x_deploy_common::event::organization::OrganizationInvitationCreatedEvent;
+use x_deploy_common::event::{CommonEvent, ToTopicName};
+use x_deploy_common::{CommonError, CommonResult};
+
+/// Consumes the events forwarded to the clients until `shutdown` turns
+/// true.
+pub async fn consume_events(
+  hub: Hub,
+  shutdown: watch::Receiver<bool>,
+) {
+  let event = CommonEvent::new(CONFIG.kafka_url.clone());
+  let group = CONFIG.kafka_consumer_group.as_str();
+  let (cluster_status, deployment_rollout, invitation) = tokio::join!(
+    event.consume(group, hub.clone(), shutdown.clone(), cluster_status_changed),
+    event.consume(
+      group,
+      hub.clone(),
+      shutdown.clone(),
+      deployment_rollout_requested
+    ),
+    event.consume(group, hub.clone(), shutdown, invitation_created),
+  );
+  for result in [cluster_status, deployment_rollout, invitation] {
+    if let Err(err) = result {
+      log::error!("Event consumer stopped: {:?}", err);
+    }
+  }
+}
+
+async fn cluster_status_changed(
+  event: ClusterStatusChangedEvent,
+  hub: Hub,
+) -> CommonResult<()> {
+  let channels = vec![
+    Channel::Organization(parse_id(&event.organization_id)?),
+    Channel::Project(parse_id(&event.project_id)?),
+  ];
+  publish(&hub, channels, None, &event)
+}
+
+async fn deployment_rollout_requested(
+  event: DeploymentRolloutRequestedEvent,
+  hub: Hub,
+) -> CommonResult<()> {
+  let channels = vec![
+    Channel::Organization(parse_id(&event.organization_id)?),
+    Channel::Project(parse_id(&event.project_id)?),
+  ];
+  let environment_id = parse_id(&event.environment_id)?;
+  publish(&hub, channels, Some(environment_id), &event)
+}
+
+async fn invitation_created(
+  event: OrganizationInvitationCreatedEvent,
+  hub: Hub,
+) -> CommonResult<()> {
+  let channels = vec![Channel::User(parse_id(&event.receiver_id)?)];
+  publish(&hub, channels, None, &event)
+}
+
+fn publish<T>(
+  hub: &Hub,
+  channels: Vec<Channel>,
+  environment_id: Option<ObjectId>,
+  event: &T,
+) -> CommonResult<()>
+where
+  T: ToTopicName + Serialize,
+{
+  hub.publish(Notification {
+    channels,
+    topic: T::topic_name(),
+    environment_id,
+    data: serde_json::to_value(event)?,
+  });
+  Ok(())
+}
+
+fn parse_id(id: &String) -> CommonResult<ObjectId> {
+  return match ObjectId::from_str(id) {
+    Ok(id) => Ok(id),
+    Err(_) => Err(CommonError::FromStrError(format!("Invalid id {}", id))),
+  };
+}
@@ -0,0 +1,78 @@
+use bson::oid::ObjectId;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt::Display;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+// Notifications kept for connections that are slow to read them
+const HUB_CAPACITY: usize = 1024;
+
+/// A stream of events clients can subscribe to.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub enum Channel {
+  Organization(ObjectId),
+  Project(ObjectId),
+  User(ObjectId),
+}
+
+impl Display for Channel {
+  fn fmt(
+    &self,
+    f: &mut std::fmt::Formatter<'_>,
+  ) -> std::fmt::Result {
+    match self {
+      Channel::Organization(id) => write!(f, "organization:{}", id),
+      Channel::Project(id) => write!(f, "project:{}", id),
+      Channel::User(id) => write!(f, "user:{}", id),
+    }
+  }
+}
+
+impl Serialize for Channel {
+  fn serialize<S>(
+    &self,
+    serializer: S,
+  ) -> Result<S::Ok, S::Error>
+  where
+    S: serde::Serializer,
+  {
+    serializer.serialize_str(self.to_string().as_str())
+  }
+}
+
+/// An event received from Kafka, with the channels it is published on.
+#[derive(Clone, Debug)]
+pub struct Notification {
+  pub channels: Vec<Channel>,
+  pub topic: String,
+  /// Set when the event is about a single environment, so that only the
+  /// roles allowed to read it receive the event.
+  pub environment_id: Option<ObjectId>,
+  pub data: Value,
+}
+
+/// Dispatches the notifications to every open connection.
+#[derive(Clone)]
+pub struct Hub {
+  sender: broadcast::Sender<Arc<Notification>>,
+}
+
+impl Hub {
+  pub fn new() -> Self {
+    let (sender, _) = broadcast::channel(HUB_CAPACITY);
+    Self { sender }
+  }
+
+  pub fn publish(
+    &self,
+    notification: Notification,
+  ) {
+    // Sending only fails when nobody is connected
+    let _ = self.sender.send(Arc::new(notification));
+  }
+
+  pub fn subscribe(&self) -> broadcast::Receiver<Arc<Notification>> {
+    self.sender.subscribe()
+  }
+}
@@ -0,0 +1,62 @@
+use jsonwebtoken::errors::ErrorKind;
+use jsonwebtoken::jwk::JwkSet;
+use serde::de::DeserializeOwned;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use x_deploy_common::crypto::jwt::{key_id_of, JwksVerifier};
+
+/// Minimal delay between two fetches triggered by an unknown key id, so
+/// that forged tokens can't be used to flood the API.
+const JWKS_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The public keys of the API, fetched again when a token is signed with
+/// a key we don't know yet, e.g. after a rotation.
+pub(crate) struct Jwks {
+  url: String,
+  state: RwLock<(JwksVerifier, Instant)>,
+}
+
+impl Jwks {
+  pub(crate) async fn fetch(url: String) -> Result<Self, String> {
+    let verifier = fetch_verifier(&url).await?;
+    Ok(Self {
+      url,
+      state: RwLock::new((verifier, Instant::now())),
+    })
+  }
+
+  pub(crate) async fn verify<T: DeserializeOwned>(
+    &self,
+    token: &str,
+  ) -> jsonwebtoken::errors::Result<T> {
+    let key_id = match key_id_of(token) {
+      Some(key_id) => key_id,
+      None => return Err(ErrorKind::InvalidToken.into()),
+    };
+    {
+      let (verifier, _) = &*self.state.read().await;
+      if verifier.has_key(&key_id) {
+        return verifier.verify(token);
+      }
+    }
+    let mut state = self.state.write().await;
+    if !state.0.has_key(&key_id) && state.1.elapsed() >= JWKS_REFRESH_INTERVAL {
+      match fetch_verifier(&self.url).await {
+        Ok(verifier) => *state = (verifier, Instant::now()),
+        Err(err) => log::warn!("Failed to refresh the jwks: {}", err),
+      }
+    }
+    state.0.verify(token)
+  }
+}
+
+async fn fetch_verifier(url: &String) -> Result<JwksVerifier, String> {
+  let response = reqwest::get(url).await.map_err(|e| e.to_string())?;
+  let jwks = response
+    .error_for_status()
+    .map_err(|e| e.to_string())?
+    .json::<JwkSet>()
+    .await
+    .map_err(|e| e.to_string())?;
+  JwksVerifier::new(&jwks).map_err(|e| format!("{:?}", e))
+}
@@ -1,36 +1,153 @@
 #[macro_use]
 extern crate rocket;
 
-use rocket::futures::stream::StreamExt;
-use rocket::http::ContentType;
-use rocket::response::stream::TextStream;
-use rocket::tokio::sync::mpsc;
-use rocket::tokio::sync::Mutex;
-use rocket::tokio::time::{interval, Duration};
-use rocket_ws::{Config, Stream, WebSocket};
-use std::sync::Arc;
+use crate::auth::Auth;
+use crate::config::Config;
+use crate::hub::Hub;
+use crate::jwks::Jwks;
+use crate::session::{ServerMessage, Session};
+use lazy_static::lazy_static;
+use mongodb::Database;
+use rocket::futures::{SinkExt, StreamExt};
+use rocket::http::uncased::Uncased;
+use rocket::State;
+use rocket_ws::stream::DuplexStream;
+use rocket_ws::{Channel, Config as WsConfig, Message, WebSocket};
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::watch;
+use tokio::time::{interval, sleep};
+
+mod auth;
+mod config;
+mod consumer;
+mod hub;
+mod jwks;
+mod session;
+
+lazy_static! {
+  pub(crate) static ref CONFIG: Config = Config::from_rocket_config();
+}
+
+// Delay between two checks of the session and memberships of a connection
+const SESSION_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
 
 #[get("/")]
-fn index(ws: WebSocket) -> Stream!['static] {
-  let ws = ws.config(Config {
+fn index(
+  ws: WebSocket,
+  auth: Auth,
+  db: &State<Database>,
+  redis: &State<redis::Client>,
+  hub: &State<Hub>,
+) -> Channel<'static> {
+  let ws = ws.config(WsConfig {
     max_send_queue: Some(5),
     ..Default::default()
   });
+  let db = db.inner().clone();
+  let redis = redis.inner().clone();
+  let mut receiver = hub.subscribe();
+  let mut session = Session::new(auth);
 
-  Stream! { ws =>
-      for await message in ws {
-          yield message?;
+  ws.channel(move |mut stream| {
+    Box::pin(async move {
+      let expiration = sleep(session.time_left().unwrap_or(Duration::MAX));
+      tokio::pin!(expiration);
+      let mut refresh = interval(SESSION_REFRESH_INTERVAL);
+      // The first tick is immediate, the session was just verified
+      refresh.tick().await;
+      loop {
+        select! {
+          message = stream.next() => match message {
+            Some(Ok(Message::Text(text))) => {
+              let reply = session.handle(&db, &text).await;
+              stream.send(to_message(&reply)).await?;
+            }
+            Some(Ok(Message::Close(_))) | None => break,
+            Some(Ok(_)) => {}
+            Some(Err(err)) => return Err(err),
+          },
+          notification = receiver.recv() => match notification {
+            Ok(notification) => {
+              for reply in session.deliver(&notification) {
+                stream.send(to_message(&reply)).await?;
+              }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+              log::warn!("A connection skipped {} notifications", skipped);
+            }
+            Err(RecvError::Closed) => break,
+          },
+          _ = &mut expiration => {
+            return close(&mut stream, "Authentication is expired, please login again").await;
+          }
+          _ = refresh.tick() => match session.refresh(&db, &redis).await {
+            Ok(replies) => {
+              for reply in replies {
+                stream.send(to_message(&reply)).await?;
+              }
+            }
+            Err(message) => return close(&mut stream, message).await,
+          },
+        }
       }
-  }
+      Ok(())
+    })
+  })
+}
+
+/// Closes a connection whose authentication is no longer valid.
+async fn close(
+  stream: &mut DuplexStream,
+  message: &str,
+) -> Result<(), rocket_ws::result::Error> {
+  let reply = ServerMessage::Error {
+    message: message.to_string(),
+  };
+  stream.send(to_message(&reply)).await?;
+  stream.send(Message::Close(None)).await
 }
 
-#[launch]
-fn rocket() -> _ {
+fn to_message(message: &ServerMessage) -> Message {
+  let text = serde_json::to_string(message).unwrap_or_default();
+  Message::Text(text)
+}
+
+#[rocket::main]
+async fn main() -> Result<(), rocket::Error> {
+  env_logger::init();
+
+  let mongodb_client = mongodb::Client::with_uri_str(&CONFIG.mongodb_url)
+    .await
+    .expect("Failed to connect to MongoDB");
+  let mongodb_database = mongodb_client.database(&CONFIG.mongodb_database);
+  let jwks = Jwks::fetch(CONFIG.jwks_url.clone())
+    .await
+    .expect("Failed to fetch the jwks of the API");
+  let redis_client = redis::Client::open(CONFIG.redis_url.as_str())
+    .expect("Failed to open the redis client");
+
+  let hub = Hub::new();
+  let (shutdown_sender, shutdown) = watch::channel(false);
+  let consumers = tokio::spawn(consumer::consume_events(hub.clone(), shutdown));
+
   rocket::build()
     .configure(rocket::Config {
       address: "0.0.0.0".parse().unwrap(),
       port: 8001,
+      ip_header: CONFIG.client_ip_header.clone().map(Uncased::from),
       ..rocket::Config::default()
     })
+    .manage(mongodb_database)
+    .manage(redis_client)
+    .manage(hub)
+    .manage(jwks)
     .mount("/", routes![index])
+    .launch()
+    .await?;
+
+  let _ = shutdown_sender.send(true);
+  let _ = consumers.await;
+  Ok(())
 }
@@ -0,0 +1,303 @@
+use crate::auth::{check_api_key_expiration, check_session, Auth};
+use crate::hub::{Channel, Notification};
+use bson::oid::ObjectId;
+use bson::DateTime;
+use mongodb::Database;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use x_deploy_common::db::organization_apikey::OrganizationApiKey;
+use x_deploy_common::db::organization_member::OrganizationMember;
+use x_deploy_common::db::organization_project::OrganizationProject;
+use x_deploy_common::db::organization_role::{
+  EnvironmentPermission, OrganizationRole, StandardPermission,
+};
+use x_deploy_common::db::CommonCollection;
+
+/// A message sent by a client.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ClientMessage {
+  #[serde(rename_all = "camelCase")]
+  Subscribe {
+    organization_id: String,
+    project_id: Option<String>,
+  },
+  #[serde(rename_all = "camelCase")]
+  Unsubscribe {
+    organization_id: String,
+    project_id: Option<String>,
+  },
+}
+
+/// A message sent to a client.
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ServerMessage {
+  Subscribed {
+    channel: Channel,
+  },
+  Unsubscribed {
+    channel: Channel,
+  },
+  Event {
+    channel: Channel,
+    topic: String,
+    data: Value,
+  },
+  Error {
+    message: String,
+  },
+}
+
+/// A channel a connection listens to, with the organization it belongs
+/// to and the role given in it. A role of None means the organization
+/// owner.
+struct Subscription {
+  organization_id: Option<ObjectId>,
+  role: Option<OrganizationRole>,
+}
+
+/// The channels a connection listens to. The roles are checked again on
+/// every refresh, so a member who lost access stops receiving events.
+pub struct Session {
+  auth: Auth,
+  subscriptions: HashMap<Channel, Subscription>,
+}
+
+impl Session {
+  pub fn new(auth: Auth) -> Self {
+    let mut subscriptions = HashMap::new();
+    // Users always receive the events addressed to them
+    if let Auth::User { id, .. } = &auth {
+      let subscription = Subscription {
+        organization_id: None,
+        role: None,
+      };
+      subscriptions.insert(Channel::User(id.clone()), subscription);
+    }
+    Self {
+      auth,
+      subscriptions,
+    }
+  }
+
+  pub async fn handle(
+    &mut self,
+    db: &Database,
+    text: &str,
+  ) -> ServerMessage {
+    let message = match serde_json::from_str::<ClientMessage>(text) {
+      Ok(message) => message,
+      Err(_) => return error("Invalid message"),
+    };
+    let result = match message {
+      ClientMessage::Subscribe {
+        organization_id,
+        project_id,
+      } => self.subscribe(db, &organization_id, &project_id).await,
+      ClientMessage::Unsubscribe {
+        organization_id,
+        project_id,
+      } => self.unsubscribe(&organization_id, &project_id),
+    };
+    return match result {
+      Ok(message) => message,
+      Err(message) => error(message),
+    };
+  }
+
+  /// Time left before the token or the API key of the connection expires.
+  pub fn time_left(&self) -> Option<Duration> {
+    let expires_at = match &self.auth {
+      Auth::User { exp, .. } => exp * 1000,
+      Auth::ApiKey(api_key) => api_key.expires_at?.timestamp_millis(),
+    };
+    let time_left = expires_at - DateTime::now().timestamp_millis();
+    Some(Duration::from_millis(time_left.max(0) as u64))
+  }
+
+  /// Checks the authentication and the subscriptions again, as the session
+  /// may have been revoked or a membership changed since the connection
+  /// was opened. Returns the messages for the channels the connection
+  /// lost, or an error when it must be closed.
+  pub async fn refresh(
+    &mut self,
+    db: &Database,
+    redis: &redis::Client,
+  ) -> Result<Vec<ServerMessage>, &'static str> {
+    match &mut self.auth {
+      Auth::User { sid, .. } => {
+        check_session(redis, sid)
+          .await
+          .map_err(|(_, message)| message)?;
+      }
+      Auth::ApiKey(api_key) => {
+        let akc = CommonCollection::<OrganizationApiKey>::new(db);
+        let found = akc
+          .get_by_id_of_org(&api_key.organization_id, &api_key.id)
+          .await
+          .map_err(|_| "Failed to verify api key in database")?;
+        let found = match found {
+          Some(found) => found,
+          None => return Err("The api key was deleted"),
+        };
+        check_api_key_expiration(&found).map_err(|(_, message)| message)?;
+        *api_key = found;
+      }
+    }
+    let channels: Vec<(Channel, ObjectId)> = self
+      .subscriptions
+      .iter()
+      .filter_map(|(channel, subscription)| {
+        let org_id = subscription.organization_id?;
+        Some((channel.clone(), org_id))
+      })
+      .collect();
+    let mut messages = Vec::new();
+    for (channel, org_id) in channels {
+      match self.get_role(db, &org_id).await {
+        Ok(role) => {
+          if let Some(subscription) = self.subscriptions.get_mut(&channel) {
+            subscription.role = role;
+          }
+        }
+        Err(_) => {
+          self.subscriptions.remove(&channel);
+          messages.push(ServerMessage::Unsubscribed { channel });
+        }
+      }
+    }
+    Ok(messages)
+  }
+
+  /// Returns the messages to send for a notification, one per channel of
+  /// the notification the connection is allowed to receive.
+  pub fn deliver(
+    &self,
+    notification: &Notification,
+  ) -> Vec<ServerMessage> {
+    let mut messages = Vec::new();
+    for channel in notification.channels.iter() {
+      let subscription = match self.subscriptions.get(channel) {
+        Some(subscription) => subscription,
+        None => continue,
+      };
+      if let Some(environment_id) = &notification.environment_id {
+        if !can_read_environment(&subscription.role, environment_id) {
+          continue;
+        }
+      }
+      messages.push(ServerMessage::Event {
+        channel: channel.clone(),
+        topic: notification.topic.clone(),
+        data: notification.data.clone(),
+      });
+    }
+    messages
+  }
+
+  async fn subscribe(
+    &mut self,
+    db: &Database,
+    org_id: &String,
+    project_id: &Option<String>,
+  ) -> Result<ServerMessage, &'static str> {
+    let org_id = parse_id(org_id)?;
+    let role = self.get_role(db, &org_id).await?;
+    let channel = match project_id {
+      Some(project_id) => {
+        let project_id = parse_id(project_id)?;
+        let project_coll = CommonCollection::<OrganizationProject>::new(db);
+        let project = project_coll
+          .get_with_id_of_org(&project_id, &org_id)
+          .await
+          .map_err(|_| "Failed to verify the project in database")?;
+        if project.is_none() {
+          return Err("The project does not exist");
+        }
+        Channel::Project(project_id)
+      }
+      None => Channel::Organization(org_id),
+    };
+    let subscription = Subscription {
+      organization_id: Some(org_id),
+      role,
+    };
+    self.subscriptions.insert(channel.clone(), subscription);
+    Ok(ServerMessage::Subscribed { channel })
+  }
+
+  fn unsubscribe(
+    &mut self,
+    org_id: &String,
+    project_id: &Option<String>,
+  ) -> Result<ServerMessage, &'static str> {
+    let channel = match project_id {
+      Some(project_id) => Channel::Project(parse_id(project_id)?),
+      None => Channel::Organization(parse_id(org_id)?),
+    };
+    if self.subscriptions.remove(&channel).is_none() {
+      return Err("You are not subscribed to this channel");
+    }
+    Ok(ServerMessage::Unsubscribed { channel })
+  }
+
+  async fn get_role(
+    &self,
+    db: &Database,
+    org_id: &ObjectId,
+  ) -> Result<Option<OrganizationRole>, &'static str> {
+    return match &self.auth {
+      Auth::User { id: user_id, .. } => {
+        let omc = CommonCollection::<OrganizationMember>::new(db);
+        let member = omc
+          .get_user_in_org(org_id, user_id)
+          .await
+          .map_err(|_| "Failed to verify the membership in database")?;
+        match member {
+          Some(member) => Ok(member.role),
+          None => Err("You are not a member of this organization"),
+        }
+      }
+      Auth::ApiKey(api_key) => {
+        if api_key.organization_id != *org_id {
+          return Err("You are not a member of this organization");
+        }
+        Ok(api_key.role.clone())
+      }
+    };
+  }
+}
+
+/// Mirrors the environment permission of the API: a role without a
+/// permission on the environment falls back to its project permission.
+fn can_read_environment(
+  role: &Option<OrganizationRole>,
+  environment_id: &ObjectId,
+) -> bool {
+  let role = match role {
+    Some(role) => role,
+    None => return true,
+  };
+  return match role.get_environment_permission(environment_id) {
+    Some(EnvironmentPermission::NoAccess) => false,
+    Some(_) => true,
+    None => match role.general_permission.project {
+      StandardPermission::None => false,
+      _ => true,
+    },
+  };
+}
+
+fn parse_id(id: &String) -> Result<ObjectId, &'static str> {
+  ObjectId::from_str(id).map_err(|_| "Invalid id")
+}
+
+fn error(message: &str) -> ServerMessage {
+  ServerMessage::Error {
+    message: message.to_string(),
+  }
+}